@@ -1,4 +1,7 @@
+use crate::DbResult;
 use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use std::time::Duration;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DatabaseConfig {
@@ -8,9 +11,189 @@ pub struct DatabaseConfig {
     pub username: String,
     pub password: String,
     pub database: String,
+    #[serde(default)]
+    pub pool: PoolConfig,
+    /// Read replicas; reads are spread across them when using `ReplicatedConnection`
+    #[serde(default)]
+    pub replicas: Vec<DatabaseConfig>,
+    /// Pragmas applied to SQLite connections
+    #[serde(default)]
+    pub sqlite: SqliteConfig,
 }
 
+/// SQLite pragmas set on each connection after it is opened.
+///
+/// The defaults (WAL journal, 5 second busy timeout) let concurrent writers
+/// wait for the lock instead of failing with `database is locked`.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SqliteConfig {
+    /// `PRAGMA journal_mode`, e.g. `"WAL"` or `"DELETE"`; not applied to
+    /// in-memory databases
+    pub journal_mode: String,
+    /// How long a statement waits for a locked database before failing
+    #[serde(with = "duration_format")]
+    pub busy_timeout: Duration,
+    /// `PRAGMA synchronous`, e.g. `"NORMAL"` or `"FULL"`
+    pub synchronous: String,
+    /// `PRAGMA foreign_keys`
+    pub foreign_keys: bool,
+}
+
+impl Default for SqliteConfig {
+    fn default() -> Self {
+        Self {
+            journal_mode: "WAL".to_string(),
+            busy_timeout: Duration::from_secs(5),
+            synchronous: "NORMAL".to_string(),
+            foreign_keys: true,
+        }
+    }
+}
+
+/// Serde format for `Duration` config fields.
+///
+/// Accepts integer or fractional seconds (`30`, `0.5`), strings with a unit
+/// (`"500ms"`, `"30s"`, `"10m"`, `"1h"`; a bare number is seconds) and the
+/// `{ secs, nanos }` form written by earlier versions. Durations are written
+/// as `"30s"`, or `"1500ms"` when not a whole number of seconds.
+mod duration_format {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::time::Duration;
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum DurationValue {
+        Seconds(u64),
+        FractionalSeconds(f64),
+        Text(String),
+        Struct {
+            secs: u64,
+            #[serde(default)]
+            nanos: u32,
+        },
+    }
+
+    fn parse(text: &str) -> Result<Duration, String> {
+        let text = text.trim().to_ascii_lowercase();
+        let (number, unit_secs) = if let Some(number) = text.strip_suffix("ms") {
+            (number, 0.001)
+        } else if let Some(number) = text.strip_suffix('h') {
+            (number, 3600.0)
+        } else if let Some(number) = text.strip_suffix('m') {
+            (number, 60.0)
+        } else {
+            (text.strip_suffix('s').unwrap_or(&text), 1.0)
+        };
+        number
+            .trim()
+            .parse::<f64>()
+            .ok()
+            .and_then(|number| Duration::try_from_secs_f64(number * unit_secs).ok())
+            .ok_or_else(|| {
+                format!(
+                    "Invalid duration '{}', expected e.g. 30, \"500ms\", \"30s\", \"10m\" or \"1h\"",
+                    text
+                )
+            })
+    }
+
+    fn from_value(value: DurationValue) -> Result<Duration, String> {
+        match value {
+            DurationValue::Seconds(secs) => Ok(Duration::from_secs(secs)),
+            DurationValue::FractionalSeconds(secs) => Duration::try_from_secs_f64(secs)
+                .map_err(|_| format!("Invalid duration {} seconds", secs)),
+            DurationValue::Text(text) => parse(&text),
+            DurationValue::Struct { secs, nanos } => Ok(Duration::new(secs, nanos)),
+        }
+    }
+
+    fn format(duration: &Duration) -> String {
+        if duration.subsec_nanos() == 0 {
+            format!("{}s", duration.as_secs())
+        } else {
+            format!("{}ms", duration.as_millis())
+        }
+    }
+
+    pub(super) fn serialize<S: Serializer>(
+        duration: &Duration,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format(duration))
+    }
+
+    pub(super) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Duration, D::Error> {
+        from_value(DurationValue::deserialize(deserializer)?).map_err(serde::de::Error::custom)
+    }
+
+    pub(super) mod option {
+        use serde::{Deserialize, Deserializer, Serializer};
+        use std::time::Duration;
+
+        pub(in super::super) fn serialize<S: Serializer>(
+            duration: &Option<Duration>,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            match duration {
+                Some(duration) => serializer.serialize_some(&super::format(duration)),
+                None => serializer.serialize_none(),
+            }
+        }
+
+        pub(in super::super) fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<Option<Duration>, D::Error> {
+            Option::<super::DurationValue>::deserialize(deserializer)?
+                .map(super::from_value)
+                .transpose()
+                .map_err(serde::de::Error::custom)
+        }
+    }
+}
+
+/// Connection pool settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PoolConfig {
+    /// Maximum number of open connections
+    pub max_connections: u32,
+    /// Minimum number of idle connections kept open
+    pub min_connections: u32,
+    /// Maximum time to wait for a connection
+    #[serde(with = "duration_format")]
+    pub connection_timeout: Duration,
+    /// Idle time after which a connection is closed
+    #[serde(with = "duration_format")]
+    pub idle_timeout: Duration,
+    /// Number of prepared statements cached per connection (0 disables caching)
+    pub statement_cache_capacity: usize,
+    /// Server-side limit on statement run time, set with `SET statement_timeout`
+    /// on every Postgres connection (ignored by other backends)
+    #[serde(with = "duration_format::option")]
+    pub statement_timeout: Option<Duration>,
+}
+
+impl Default for PoolConfig {
+    fn default() -> Self {
+        Self {
+            max_connections: 10,
+            min_connections: 0,
+            connection_timeout: Duration::from_secs(30),
+            idle_timeout: Duration::from_secs(600),
+            statement_cache_capacity: 100,
+            statement_timeout: None,
+        }
+    }
+}
+
+/// Supported database backends.
+///
+/// This is the single canonical database type for the crate: configs,
+/// connections, transactions and `ConnectionInfo` all use it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum DatabaseType {
     #[serde(rename = "mysql")]
     MySql,
@@ -22,7 +205,163 @@ pub enum DatabaseType {
     Redis,
 }
 
+impl DatabaseType {
+    /// Canonical lowercase name, as used in configuration
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DatabaseType::MySql => "mysql",
+            DatabaseType::Postgres => "postgres",
+            DatabaseType::Sqlite => "sqlite",
+            DatabaseType::Redis => "redis",
+        }
+    }
+
+    /// Detect the database type from a connection URL's scheme,
+    /// e.g. `postgres://localhost/app` or `sqlite:app.db`
+    pub fn from_scheme(url: &str) -> Result<Self, String> {
+        let (scheme, _) = url
+            .trim()
+            .split_once(':')
+            .ok_or_else(|| format!("Connection URL '{}' has no scheme", url))?;
+        match scheme.to_ascii_lowercase().as_str() {
+            "rediss" => Ok(DatabaseType::Redis),
+            scheme => scheme.parse().map_err(|_| {
+                format!(
+                    "Unsupported connection URL scheme '{}', expected mysql, postgres, postgresql, sqlite or redis",
+                    scheme
+                )
+            }),
+        }
+    }
+}
+
+impl std::fmt::Display for DatabaseType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for DatabaseType {
+    type Err = String;
+
+    /// Parse a database type name, case-insensitively
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "mysql" => Ok(DatabaseType::MySql),
+            "postgres" | "postgresql" => Ok(DatabaseType::Postgres),
+            "sqlite" => Ok(DatabaseType::Sqlite),
+            "redis" => Ok(DatabaseType::Redis),
+            other => Err(format!(
+                "Unknown database type '{}', expected one of: mysql, postgres, postgresql, sqlite, redis",
+                other
+            )),
+        }
+    }
+}
+
+impl TryFrom<&str> for DatabaseType {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+/// Connection details safe to log or expose (no credentials)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ConnectionInfo {
+    pub database_type: DatabaseType,
+    pub host: String,
+    pub port: u16,
+    pub database: String,
+}
+
+impl From<&DatabaseConfig> for ConnectionInfo {
+    fn from(config: &DatabaseConfig) -> Self {
+        Self {
+            database_type: config.database_type,
+            host: config.host.clone(),
+            port: config.port,
+            database: config.database.clone(),
+        }
+    }
+}
+
 impl DatabaseConfig {
+    /// Connection details without credentials
+    pub fn connection_info(&self) -> ConnectionInfo {
+        ConnectionInfo::from(self)
+    }
+
+    /// Check the fields each backend needs before connecting, so a bad
+    /// config fails with a clear message instead of an obscure driver error.
+    ///
+    /// For a SQLite file the parent directory is created if it is missing.
+    pub fn validate(&self) -> DbResult<()> {
+        let invalid = |reason: String| {
+            Err(format!(
+                "Invalid {} database config: {}",
+                self.database_type, reason
+            ))
+        };
+        match self.database_type {
+            DatabaseType::MySql | DatabaseType::Postgres | DatabaseType::Redis => {
+                if self.host.trim().is_empty() {
+                    return invalid("host is required".to_string());
+                }
+                if self.port == 0 {
+                    return invalid("port must not be 0".to_string());
+                }
+            }
+            DatabaseType::Sqlite => {}
+        }
+        match self.database_type {
+            DatabaseType::MySql | DatabaseType::Postgres if self.database.trim().is_empty() => {
+                return invalid("database name is required".to_string());
+            }
+            DatabaseType::Redis
+                if !self.database.is_empty() && self.database.parse::<u32>().is_err() =>
+            {
+                return invalid(format!(
+                    "database must be a Redis database number, got '{}'",
+                    self.database
+                ));
+            }
+            DatabaseType::Sqlite if self.database.trim().is_empty() => {
+                return invalid(
+                    "database path is required (use \":memory:\" for an in-memory database)"
+                        .to_string(),
+                );
+            }
+            DatabaseType::Sqlite
+                if !self.database.starts_with("file:")
+                    && !crate::sqlite::is_in_memory(&self.database) =>
+            {
+                let parent = std::path::Path::new(&self.database).parent();
+                if let Some(parent) = parent.filter(|dir| !dir.as_os_str().is_empty())
+                    && let Err(err) = std::fs::create_dir_all(parent)
+                {
+                    return invalid(format!(
+                        "cannot create directory {} for the database file: {}",
+                        parent.display(),
+                        err
+                    ));
+                }
+            }
+            _ => {}
+        }
+        if self.pool.max_connections == 0 {
+            return invalid("pool.max_connections must be at least 1".to_string());
+        }
+        if self.pool.min_connections > self.pool.max_connections {
+            return invalid(format!(
+                "pool.min_connections ({}) exceeds pool.max_connections ({})",
+                self.pool.min_connections, self.pool.max_connections
+            ));
+        }
+        Ok(())
+    }
+
     pub fn connection_string(&self) -> String {
         match self.database_type {
             DatabaseType::MySql => format!(
@@ -0,0 +1,73 @@
+use serde::{Deserialize, Serialize};
+
+/// Rows per page when no limit is given
+pub const DEFAULT_PAGE_SIZE: u64 = 50;
+
+/// Position in a keyset (cursor) paginated listing ordered by `id`.
+///
+/// Unlike `LIMIT .. OFFSET`, each page is read with `WHERE id > ?`, so deep
+/// pages cost the same as the first and rows inserted meanwhile do not shift
+/// the listing. The cursor serializes as JSON for handing to clients.
+///
+/// ```rust,ignore
+/// let page = User::query_page(&Cursor::new().limit(50)).await?;
+/// let next = User::query_page(&page.next_cursor.unwrap()).await?;
+/// let newest = User::query_page(&Cursor::new().descending()).await?;
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Cursor {
+    /// `id` of the last row already seen; `None` starts from the beginning
+    pub last_id: Option<i64>,
+    /// Maximum rows per page
+    pub limit: u64,
+    /// Walk from the highest `id` down
+    pub descending: bool,
+}
+
+impl Default for Cursor {
+    fn default() -> Self {
+        Self {
+            last_id: None,
+            limit: DEFAULT_PAGE_SIZE,
+            descending: false,
+        }
+    }
+}
+
+impl Cursor {
+    /// First page, ascending by `id`
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Rows after `last_id` in the cursor's direction
+    pub fn after(last_id: i64) -> Self {
+        Self::new().after_id(last_id)
+    }
+
+    /// The same cursor positioned after `last_id`
+    pub fn after_id(mut self, last_id: i64) -> Self {
+        self.last_id = Some(last_id);
+        self
+    }
+
+    /// Rows per page, at least one
+    pub fn limit(mut self, limit: u64) -> Self {
+        self.limit = limit.max(1);
+        self
+    }
+
+    /// Order by `id` descending, so `after` moves to lower ids
+    pub fn descending(mut self) -> Self {
+        self.descending = true;
+        self
+    }
+}
+
+/// One page of a cursor paginated listing
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// Cursor for the following page, `None` on the last page
+    pub next_cursor: Option<Cursor>,
+}
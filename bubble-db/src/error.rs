@@ -0,0 +1,77 @@
+/// Broad category of a database error message.
+///
+/// Backends report errors as driver messages, so the category is recognised
+/// from the wording used by MySQL, Postgres, SQLite and this crate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DbErrorKind {
+    /// The query matched no rows
+    NotFound,
+    /// A unique, foreign key, not-null or check constraint was violated
+    ConstraintViolation,
+    /// The database could not be reached or the connection was lost
+    Connection,
+    Other,
+}
+
+impl DbErrorKind {
+    /// Classify an error returned by a `DatabaseConnection`
+    pub fn of(error: &str) -> Self {
+        let error = error.to_ascii_lowercase();
+        let contains_any = |needles: &[&str]| needles.iter().any(|n| error.contains(n));
+        if contains_any(&[
+            "no rows found",
+            "no rows returned",
+            "query returned no rows",
+            "no data found",
+        ]) {
+            DbErrorKind::NotFound
+        } else if contains_any(&[
+            "constraint failed",
+            "violates unique constraint",
+            "violates foreign key constraint",
+            "violates not-null constraint",
+            "violates check constraint",
+            "duplicate entry",
+            "foreign key constraint fails",
+            "cannot be null",
+        ]) {
+            DbErrorKind::ConstraintViolation
+        } else if contains_any(&[
+            "connection refused",
+            "connection reset",
+            "connection closed",
+            "broken pipe",
+            "server has gone away",
+            "lost connection",
+            "pool error",
+            "pool timed out",
+            "timed out",
+        ]) {
+            DbErrorKind::Connection
+        } else {
+            DbErrorKind::Other
+        }
+    }
+
+    /// HTTP status a web handler should answer with: 404, 409, 503 or 500
+    pub fn http_status(&self) -> u16 {
+        match self {
+            DbErrorKind::NotFound => 404,
+            DbErrorKind::ConstraintViolation => 409,
+            DbErrorKind::Connection => 503,
+            DbErrorKind::Other => 500,
+        }
+    }
+}
+
+pub fn is_not_found(error: &str) -> bool {
+    DbErrorKind::of(error) == DbErrorKind::NotFound
+}
+
+pub fn is_constraint_violation(error: &str) -> bool {
+    DbErrorKind::of(error) == DbErrorKind::ConstraintViolation
+}
+
+pub fn is_connection_error(error: &str) -> bool {
+    DbErrorKind::of(error) == DbErrorKind::Connection
+}
@@ -0,0 +1,112 @@
+use crate::{DatabaseType, DbResult, ToSql};
+
+/// WHERE conditions joined with `AND`, with their values bound as parameters.
+///
+/// The date helpers accept any `ToSql` timestamp: `chrono::DateTime` values
+/// are zone-aware and compared as instants (converted to UTC on backends
+/// without a zone-aware type), while `NaiveDateTime`/`NaiveDate` values are
+/// compared as written.
+///
+/// ```rust,ignore
+/// let week_ago = chrono::Utc::now() - chrono::Duration::days(7);
+/// let filter = Filter::new().after("created_at", week_ago).condition("active = ?", true);
+/// let users = User::filter(&filter).await?;
+/// ```
+///
+/// Conditions are written with `?` placeholders, which `to_condition`
+/// numbers as `$n` for Postgres.
+#[derive(Default)]
+pub struct Filter {
+    conditions: Vec<String>,
+    params: Vec<Box<dyn ToSql>>,
+    error: Option<String>,
+}
+
+impl Filter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a raw condition with a single `?` placeholder bound to `value`
+    pub fn condition(mut self, sql: &str, value: impl ToSql + 'static) -> Self {
+        self.conditions.push(sql.to_string());
+        self.params.push(Box::new(value));
+        self
+    }
+
+    /// `column BETWEEN start AND end`, inclusive on both ends
+    pub fn between(
+        mut self,
+        column: &str,
+        start: impl ToSql + 'static,
+        end: impl ToSql + 'static,
+    ) -> Self {
+        if self.check_column(column) {
+            self.conditions.push(format!("{} BETWEEN ? AND ?", column));
+            self.params.push(Box::new(start));
+            self.params.push(Box::new(end));
+        }
+        self
+    }
+
+    /// `column < value`
+    pub fn before(self, column: &str, value: impl ToSql + 'static) -> Self {
+        self.compare(column, "<", value)
+    }
+
+    /// `column > value`
+    pub fn after(self, column: &str, value: impl ToSql + 'static) -> Self {
+        self.compare(column, ">", value)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.conditions.is_empty()
+    }
+
+    /// The combined condition with the backend's placeholders and the values
+    /// to bind, in order, as accepted by the `_with` methods
+    pub fn to_condition(
+        &self,
+        database_type: &DatabaseType,
+    ) -> DbResult<(String, Vec<&dyn ToSql>)> {
+        if let Some(error) = &self.error {
+            return Err(error.clone());
+        }
+        let condition = if self.conditions.is_empty() {
+            "1 = 1".to_string()
+        } else {
+            self.conditions
+                .iter()
+                .map(|condition| format!("({})", condition))
+                .collect::<Vec<_>>()
+                .join(" AND ")
+        };
+        let condition = if *database_type == DatabaseType::Postgres {
+            crate::types::number_placeholders(&condition)
+        } else {
+            condition
+        };
+        Ok((condition, self.params.iter().map(|p| p.as_ref()).collect()))
+    }
+
+    fn compare(mut self, column: &str, op: &str, value: impl ToSql + 'static) -> Self {
+        if self.check_column(column) {
+            self.conditions.push(format!("{} {} ?", column, op));
+            self.params.push(Box::new(value));
+        }
+        self
+    }
+
+    /// Column names are interpolated, so only plain (optionally qualified)
+    /// identifiers are accepted; the first bad one is reported by `to_condition`
+    fn check_column(&mut self, column: &str) -> bool {
+        let valid = !column.is_empty()
+            && column
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.');
+        if !valid && self.error.is_none() {
+            self.error = Some(format!("Invalid column name '{}'", column));
+        }
+        valid
+    }
+}
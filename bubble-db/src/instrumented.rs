@@ -0,0 +1,234 @@
+use crate::{
+    DatabaseConnection, DatabaseType, DbResult, ExecuteResult, Row, RowStream, ToSql, Transaction,
+};
+use async_trait::async_trait;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Upper bounds (in milliseconds) of the latency histogram buckets;
+/// a final overflow bucket counts slower calls
+pub const LATENCY_BUCKETS_MS: &[u64] = &[1, 5, 10, 50, 100, 500, 1000];
+
+/// Metrics of a single connection method
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct MethodMetrics {
+    /// Number of calls
+    pub calls: u64,
+    /// Number of calls that returned an error
+    pub errors: u64,
+    /// Sum of call latencies
+    pub total_latency: Duration,
+    /// Calls per latency bucket, aligned with `LATENCY_BUCKETS_MS` plus overflow
+    pub histogram: Vec<u64>,
+}
+
+impl MethodMetrics {
+    /// Mean latency over all calls
+    pub fn mean_latency(&self) -> Duration {
+        if self.calls == 0 {
+            Duration::ZERO
+        } else {
+            self.total_latency / self.calls as u32
+        }
+    }
+
+    fn record(&mut self, elapsed: Duration, failed: bool) {
+        if self.histogram.is_empty() {
+            self.histogram = vec![0; LATENCY_BUCKETS_MS.len() + 1];
+        }
+        self.calls += 1;
+        if failed {
+            self.errors += 1;
+        }
+        self.total_latency += elapsed;
+        let elapsed_ms = elapsed.as_millis() as u64;
+        let bucket = LATENCY_BUCKETS_MS
+            .iter()
+            .position(|bound| elapsed_ms <= *bound)
+            .unwrap_or(LATENCY_BUCKETS_MS.len());
+        self.histogram[bucket] += 1;
+    }
+}
+
+/// Snapshot of query metrics keyed by method name (`execute`, `query`, ...)
+pub type QueryMetrics = HashMap<&'static str, MethodMetrics>;
+
+/// Connection decorator recording call counts, errors and latencies
+#[derive(Debug)]
+pub struct InstrumentedConnection<C> {
+    inner: C,
+    metrics: Mutex<QueryMetrics>,
+    slow_query_threshold: Option<Duration>,
+}
+
+impl<C: DatabaseConnection> InstrumentedConnection<C> {
+    pub fn new(inner: C) -> Self {
+        Self {
+            inner,
+            metrics: Mutex::new(HashMap::new()),
+            slow_query_threshold: None,
+        }
+    }
+
+    /// Log queries slower than `threshold` at debug level
+    pub fn slow_query_threshold(mut self, threshold: Duration) -> Self {
+        self.slow_query_threshold = Some(threshold);
+        self
+    }
+
+    /// The wrapped connection
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+
+    /// Snapshot of the metrics recorded so far
+    pub fn metrics(&self) -> QueryMetrics {
+        self.metrics
+            .lock()
+            .map(|metrics| metrics.clone())
+            .unwrap_or_default()
+    }
+
+    /// Clear all recorded metrics
+    pub fn reset_metrics(&self) {
+        if let Ok(mut metrics) = self.metrics.lock() {
+            metrics.clear();
+        }
+    }
+
+    fn record<T>(&self, method: &'static str, sql: &str, started: Instant, result: &DbResult<T>) {
+        let elapsed = started.elapsed();
+        if let Ok(mut metrics) = self.metrics.lock() {
+            metrics
+                .entry(method)
+                .or_default()
+                .record(elapsed, result.is_err());
+        }
+        if let Some(threshold) = self.slow_query_threshold
+            && elapsed >= threshold
+        {
+            tracing::debug!(method, ?elapsed, sql, "slow query");
+        }
+    }
+}
+
+#[async_trait]
+impl<C: DatabaseConnection> DatabaseConnection for InstrumentedConnection<C> {
+    fn database_type(&self) -> DatabaseType {
+        self.inner.database_type()
+    }
+
+    async fn execute(&self, sql: &str) -> DbResult<u64> {
+        let started = Instant::now();
+        let result = self.inner.execute(sql).await;
+        self.record("execute", sql, started, &result);
+        result
+    }
+
+    async fn execute_result(&self, sql: &str) -> DbResult<ExecuteResult> {
+        let started = Instant::now();
+        let result = self.inner.execute_result(sql).await;
+        self.record("execute", sql, started, &result);
+        result
+    }
+
+    async fn query(&self, sql: &str) -> DbResult<String> {
+        let started = Instant::now();
+        let result = self.inner.query(sql).await;
+        self.record("query", sql, started, &result);
+        result
+    }
+
+    async fn query_one(&self, sql: &str) -> DbResult<String> {
+        let started = Instant::now();
+        let result = self.inner.query_one(sql).await;
+        self.record("query_one", sql, started, &result);
+        result
+    }
+
+    async fn query_rows(&self, sql: &str) -> DbResult<Vec<Row>> {
+        let started = Instant::now();
+        let result = self.inner.query_rows(sql).await;
+        self.record("query_rows", sql, started, &result);
+        result
+    }
+
+    async fn execute_with(&self, sql: &str, params: &[&dyn ToSql]) -> DbResult<u64> {
+        let started = Instant::now();
+        let result = self.inner.execute_with(sql, params).await;
+        self.record("execute", sql, started, &result);
+        result
+    }
+
+    async fn execute_result_with(
+        &self,
+        sql: &str,
+        params: &[&dyn ToSql],
+    ) -> DbResult<ExecuteResult> {
+        let started = Instant::now();
+        let result = self.inner.execute_result_with(sql, params).await;
+        self.record("execute", sql, started, &result);
+        result
+    }
+
+    async fn query_with(&self, sql: &str, params: &[&dyn ToSql]) -> DbResult<String> {
+        let started = Instant::now();
+        let result = self.inner.query_with(sql, params).await;
+        self.record("query", sql, started, &result);
+        result
+    }
+
+    async fn query_one_with(&self, sql: &str, params: &[&dyn ToSql]) -> DbResult<String> {
+        let started = Instant::now();
+        let result = self.inner.query_one_with(sql, params).await;
+        self.record("query_one", sql, started, &result);
+        result
+    }
+
+    async fn query_rows_with(&self, sql: &str, params: &[&dyn ToSql]) -> DbResult<Vec<Row>> {
+        let started = Instant::now();
+        let result = self.inner.query_rows_with(sql, params).await;
+        self.record("query_rows", sql, started, &result);
+        result
+    }
+
+    async fn execute_returning_id_with(&self, sql: &str, params: &[&dyn ToSql]) -> DbResult<i64> {
+        let started = Instant::now();
+        let result = self.inner.execute_returning_id_with(sql, params).await;
+        self.record("execute_returning_id", sql, started, &result);
+        result
+    }
+
+    /// Not recorded, since rows are produced after the call returns
+    fn query_stream(&self, sql: &str, params: &[&dyn ToSql]) -> RowStream<'_> {
+        self.inner.query_stream(sql, params)
+    }
+
+    async fn insert_batch(&self, table: &str, json_data: &str) -> DbResult<u64> {
+        let started = Instant::now();
+        let result = self.inner.insert_batch(table, json_data).await;
+        self.record("insert_batch", table, started, &result);
+        result
+    }
+
+    async fn begin_transaction(&self) -> DbResult<Box<dyn Transaction + '_>> {
+        self.inner.begin_transaction().await
+    }
+
+    async fn ping(&self) -> DbResult<()> {
+        self.inner.ping().await
+    }
+
+    async fn close(&self) -> DbResult<()> {
+        self.inner.close().await
+    }
+
+    async fn execute_returning_id(&self, sql: &str) -> DbResult<i64> {
+        let started = Instant::now();
+        let result = self.inner.execute_returning_id(sql).await;
+        self.record("execute_returning_id", sql, started, &result);
+        result
+    }
+}
@@ -1,24 +1,271 @@
 pub mod config;
+pub mod cursor;
+pub mod error;
+pub mod filter;
+pub mod instrumented;
+pub mod mock;
 pub mod mysql;
+pub mod pool;
 pub mod postgres;
 pub mod redis;
+pub mod registry;
+pub mod replica;
 pub mod sqlite;
+pub mod stream;
+pub mod transaction;
 pub mod types;
 
 use async_trait::async_trait;
 use serde::Serialize;
 use std::fmt::Debug;
 
-pub use config::{DatabaseConfig, DatabaseType};
+pub use config::{ConnectionInfo, DatabaseConfig, DatabaseType, PoolConfig, SqliteConfig};
+pub use cursor::{Cursor, DEFAULT_PAGE_SIZE, Page};
+pub use error::{DbErrorKind, is_connection_error, is_constraint_violation, is_not_found};
+pub use filter::Filter;
+pub use instrumented::{InstrumentedConnection, MethodMetrics, QueryMetrics};
+pub use mock::MockConnection;
+pub use pool::{ConnectionPool, PoolStatus, PooledConnection};
+pub use registry::{
+    close_databases, database, database_names, register_database, register_shared_database,
+};
+pub use replica::ReplicatedConnection;
+pub use stream::{RowStream, STREAM_CHUNK_SIZE, paged_rows, shared_query_stream};
+pub use transaction::{Transaction, with_transaction};
+pub use types::{
+    ExecuteResult, FromSqlValue, Row, SqlParam, SqlValue, ToSql, prepare_params,
+    quote_sql_string_for, rewrite_named_params,
+};
+
+pub use futures;
 
 pub type DbResult<T> = Result<T, String>;
 
+/// Attach context to database errors
+pub trait DbResultExt<T> {
+    /// Prefix the error with `context`: `"{context}: {error}"`
+    fn context(self, context: impl std::fmt::Display) -> DbResult<T>;
+
+    /// Describe the failed operation and the SQL it ran:
+    /// `"{operation} failed: {error} (sql: {sql})"`
+    fn query_context(self, operation: impl std::fmt::Display, sql: &str) -> DbResult<T>;
+}
+
+impl<T, E: std::fmt::Display> DbResultExt<T> for Result<T, E> {
+    fn context(self, context: impl std::fmt::Display) -> DbResult<T> {
+        self.map_err(|e| format!("{}: {}", context, e))
+    }
+
+    fn query_context(self, operation: impl std::fmt::Display, sql: &str) -> DbResult<T> {
+        self.map_err(|e| format!("{} failed: {} (sql: {})", operation, e, sql))
+    }
+}
+
 #[async_trait]
 pub trait DatabaseConnection: Send + Sync + Debug {
+    fn database_type(&self) -> DatabaseType;
     async fn execute(&self, sql: &str) -> DbResult<u64>;
     async fn query(&self, sql: &str) -> DbResult<String>;
     async fn query_one(&self, sql: &str) -> DbResult<String>;
     async fn insert_batch(&self, table: &str, json_data: &str) -> DbResult<u64>;
+    async fn begin_transaction(&self) -> DbResult<Box<dyn Transaction + '_>>;
+
+    /// Check that the database is reachable
+    async fn ping(&self) -> DbResult<()> {
+        self.query_one("SELECT 1").await.map(|_| ())
+    }
+
+    /// Close the connection once in-flight queries have finished.
+    ///
+    /// Later calls fail; closing twice is a no-op. The default does nothing
+    /// and leaves the handle to be closed on drop.
+    async fn close(&self) -> DbResult<()> {
+        Ok(())
+    }
+
+    /// Execute a statement, reporting the affected rows and the key it
+    /// generated as returned by the driver
+    async fn execute_result(&self, sql: &str) -> DbResult<ExecuteResult> {
+        Ok(ExecuteResult {
+            rows_affected: self.execute(sql).await?,
+            last_insert_id: None,
+        })
+    }
+
+    /// Execute a statement with `params` bound by the driver; see
+    /// `prepare_params` for the placeholder syntax of each backend.
+    ///
+    /// The default only runs statements without parameters.
+    async fn execute_result_with(
+        &self,
+        sql: &str,
+        params: &[&dyn ToSql],
+    ) -> DbResult<ExecuteResult> {
+        if !params.is_empty() {
+            return Err(binding_unsupported(&self.database_type()));
+        }
+        self.execute_result(sql).await
+    }
+
+    /// Execute an INSERT and return the generated auto-increment key
+    async fn execute_returning_id(&self, _sql: &str) -> DbResult<i64> {
+        Err(format!(
+            "Returning generated keys is not supported by the {} backend",
+            self.database_type()
+        ))
+    }
+
+    /// Run a query returning typed rows, keeping SQL NULL distinct from
+    /// empty strings
+    async fn query_rows(&self, _sql: &str) -> DbResult<Vec<Row>> {
+        Err(format!(
+            "Typed rows are not supported by the {} backend",
+            self.database_type()
+        ))
+    }
+
+    /// Run a query with `params` bound by the driver, returning typed rows.
+    ///
+    /// The default only runs queries without parameters.
+    async fn query_rows_with(&self, sql: &str, params: &[&dyn ToSql]) -> DbResult<Vec<Row>> {
+        if !params.is_empty() {
+            return Err(binding_unsupported(&self.database_type()));
+        }
+        self.query_rows(sql).await
+    }
+
+    /// First column of the first row, for aggregates such as `SELECT COUNT(*)`;
+    /// convert it with `FromSqlValue`
+    async fn query_scalar_with(&self, sql: &str, params: &[&dyn ToSql]) -> DbResult<SqlValue> {
+        let rows = self.query_rows_with(sql, params).await?;
+        let row = rows.into_iter().next().ok_or("No rows found")?;
+        row.iter()
+            .next()
+            .map(|(_, value)| value.clone())
+            .ok_or_else(|| "Query returned no columns".to_string())
+    }
+
+    /// The default only runs statements without parameters
+    async fn execute_returning_id_with(&self, sql: &str, params: &[&dyn ToSql]) -> DbResult<i64> {
+        if !params.is_empty() {
+            return Err(binding_unsupported(&self.database_type()));
+        }
+        self.execute_returning_id(sql).await
+    }
+
+    async fn execute_with(&self, sql: &str, params: &[&dyn ToSql]) -> DbResult<u64> {
+        if params.is_empty() {
+            return self.execute(sql).await;
+        }
+        Ok(self.execute_result_with(sql, params).await?.rows_affected)
+    }
+
+    async fn query_with(&self, sql: &str, params: &[&dyn ToSql]) -> DbResult<String> {
+        if params.is_empty() {
+            return self.query(sql).await;
+        }
+        types::rows_to_json(&self.query_rows_with(sql, params).await?)
+    }
+
+    async fn query_one_with(&self, sql: &str, params: &[&dyn ToSql]) -> DbResult<String> {
+        if params.is_empty() {
+            return self.query_one(sql).await;
+        }
+        types::first_row_to_json(&self.query_rows_with(sql, params).await?)
+    }
+
+    /// Stream the rows of a query instead of buffering the whole result.
+    ///
+    /// Backends without a native cursor fetch `STREAM_CHUNK_SIZE` rows per
+    /// round trip (see `paged_rows`), so the query should have an `ORDER BY`.
+    fn query_stream(&self, sql: &str, params: &[&dyn ToSql]) -> RowStream<'_> {
+        let params = params.iter().map(|param| param.to_sql()).collect();
+        paged_rows(self, sql.to_string(), params, STREAM_CHUNK_SIZE)
+    }
+
+    /// Query plan of `sql`, one line per plan row
+    async fn explain(&self, sql: &str, params: &[&dyn ToSql]) -> DbResult<String> {
+        let prefix = explain_prefix(&self.database_type(), false)?;
+        let rows = self
+            .query_rows_with(&format!("{}{}", prefix, sql), params)
+            .await?;
+        Ok(format_plan(&rows))
+    }
+
+    /// Query plan of `sql` with actual run-time statistics.
+    ///
+    /// This executes the statement, so only use it on statements that are
+    /// safe to run. Not supported by SQLite.
+    async fn explain_analyze(&self, sql: &str, params: &[&dyn ToSql]) -> DbResult<String> {
+        let prefix = explain_prefix(&self.database_type(), true)?;
+        let rows = self
+            .query_rows_with(&format!("{}{}", prefix, sql), params)
+            .await?;
+        Ok(format_plan(&rows))
+    }
+
+    /// Execute a statement with `:name` placeholders
+    async fn execute_named(&self, sql: &str, params: &[(&str, &dyn ToSql)]) -> DbResult<u64> {
+        let (sql, params) = rewrite_named_params(sql, params, &self.database_type())?;
+        self.execute_with(&sql, &params).await
+    }
+
+    /// Run a query with `:name` placeholders
+    async fn query_named(&self, sql: &str, params: &[(&str, &dyn ToSql)]) -> DbResult<String> {
+        let (sql, params) = rewrite_named_params(sql, params, &self.database_type())?;
+        self.query_with(&sql, &params).await
+    }
+
+    async fn query_one_named(&self, sql: &str, params: &[(&str, &dyn ToSql)]) -> DbResult<String> {
+        let (sql, params) = rewrite_named_params(sql, params, &self.database_type())?;
+        self.query_one_with(&sql, &params).await
+    }
+}
+
+/// Error for a backend that cannot bind statement parameters
+pub(crate) fn binding_unsupported(database_type: &DatabaseType) -> String {
+    format!(
+        "Parameter binding is not supported by the {} backend",
+        database_type
+    )
+}
+
+/// Statement prefix requesting the query plan on each backend
+fn explain_prefix(database_type: &DatabaseType, analyze: bool) -> DbResult<&'static str> {
+    match (database_type, analyze) {
+        (DatabaseType::MySql | DatabaseType::Postgres, false) => Ok("EXPLAIN "),
+        (DatabaseType::MySql | DatabaseType::Postgres, true) => Ok("EXPLAIN ANALYZE "),
+        (DatabaseType::Sqlite, false) => Ok("EXPLAIN QUERY PLAN "),
+        (DatabaseType::Sqlite, true) => {
+            Err("EXPLAIN ANALYZE is not supported by the sqlite backend".to_string())
+        }
+        (DatabaseType::Redis, _) => {
+            Err("EXPLAIN is not supported by the redis backend".to_string())
+        }
+    }
+}
+
+/// Format plan rows: single-column plans (Postgres, MySQL `ANALYZE`) as their
+/// text, tabular plans as `column=value` pairs
+fn format_plan(rows: &[Row]) -> String {
+    rows.iter()
+        .map(|row| {
+            if row.len() == 1 {
+                row.iter()
+                    .next()
+                    .and_then(|(_, value)| value.to_text())
+                    .unwrap_or_default()
+            } else {
+                row.iter()
+                    .map(|(name, value)| {
+                        format!("{}={}", name, value.to_text().as_deref().unwrap_or("NULL"))
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
 }
 
 pub fn to_sql_value<T: Serialize>(value: &T) -> DbResult<String> {
@@ -42,6 +289,15 @@ pub enum DbConnection {
 
 #[async_trait]
 impl DatabaseConnection for DbConnection {
+    fn database_type(&self) -> DatabaseType {
+        match self {
+            DbConnection::MySql(conn) => conn.database_type(),
+            DbConnection::Postgres(conn) => conn.database_type(),
+            DbConnection::Sqlite(conn) => conn.database_type(),
+            DbConnection::Redis(conn) => conn.database_type(),
+        }
+    }
+
     async fn execute(&self, sql: &str) -> DbResult<u64> {
         match self {
             DbConnection::MySql(conn) => conn.execute(sql).await,
@@ -69,6 +325,15 @@ impl DatabaseConnection for DbConnection {
         }
     }
 
+    async fn query_rows(&self, sql: &str) -> DbResult<Vec<Row>> {
+        match self {
+            DbConnection::MySql(conn) => conn.query_rows(sql).await,
+            DbConnection::Postgres(conn) => conn.query_rows(sql).await,
+            DbConnection::Sqlite(conn) => conn.query_rows(sql).await,
+            DbConnection::Redis(conn) => conn.query_rows(sql).await,
+        }
+    }
+
     async fn insert_batch(&self, table: &str, json_data: &str) -> DbResult<u64> {
         match self {
             DbConnection::MySql(conn) => conn.insert_batch(table, json_data).await,
@@ -77,9 +342,200 @@ impl DatabaseConnection for DbConnection {
             DbConnection::Redis(conn) => conn.insert_batch(table, json_data).await,
         }
     }
+
+    async fn begin_transaction(&self) -> DbResult<Box<dyn Transaction + '_>> {
+        match self {
+            DbConnection::MySql(conn) => conn.begin_transaction().await,
+            DbConnection::Postgres(conn) => conn.begin_transaction().await,
+            DbConnection::Sqlite(conn) => conn.begin_transaction().await,
+            DbConnection::Redis(conn) => conn.begin_transaction().await,
+        }
+    }
+
+    async fn ping(&self) -> DbResult<()> {
+        match self {
+            DbConnection::MySql(conn) => conn.ping().await,
+            DbConnection::Postgres(conn) => conn.ping().await,
+            DbConnection::Sqlite(conn) => conn.ping().await,
+            DbConnection::Redis(conn) => conn.ping().await,
+        }
+    }
+
+    async fn close(&self) -> DbResult<()> {
+        match self {
+            DbConnection::MySql(conn) => conn.close().await,
+            DbConnection::Postgres(conn) => conn.close().await,
+            DbConnection::Sqlite(conn) => conn.close().await,
+            DbConnection::Redis(conn) => conn.close().await,
+        }
+    }
+
+    async fn execute_result(&self, sql: &str) -> DbResult<ExecuteResult> {
+        match self {
+            DbConnection::MySql(conn) => conn.execute_result(sql).await,
+            DbConnection::Postgres(conn) => conn.execute_result(sql).await,
+            DbConnection::Sqlite(conn) => conn.execute_result(sql).await,
+            DbConnection::Redis(conn) => conn.execute_result(sql).await,
+        }
+    }
+
+    async fn execute_result_with(
+        &self,
+        sql: &str,
+        params: &[&dyn ToSql],
+    ) -> DbResult<ExecuteResult> {
+        match self {
+            DbConnection::MySql(conn) => conn.execute_result_with(sql, params).await,
+            DbConnection::Postgres(conn) => conn.execute_result_with(sql, params).await,
+            DbConnection::Sqlite(conn) => conn.execute_result_with(sql, params).await,
+            DbConnection::Redis(conn) => conn.execute_result_with(sql, params).await,
+        }
+    }
+
+    async fn execute_with(&self, sql: &str, params: &[&dyn ToSql]) -> DbResult<u64> {
+        match self {
+            DbConnection::MySql(conn) => conn.execute_with(sql, params).await,
+            DbConnection::Postgres(conn) => conn.execute_with(sql, params).await,
+            DbConnection::Sqlite(conn) => conn.execute_with(sql, params).await,
+            DbConnection::Redis(conn) => conn.execute_with(sql, params).await,
+        }
+    }
+
+    async fn query_with(&self, sql: &str, params: &[&dyn ToSql]) -> DbResult<String> {
+        match self {
+            DbConnection::MySql(conn) => conn.query_with(sql, params).await,
+            DbConnection::Postgres(conn) => conn.query_with(sql, params).await,
+            DbConnection::Sqlite(conn) => conn.query_with(sql, params).await,
+            DbConnection::Redis(conn) => conn.query_with(sql, params).await,
+        }
+    }
+
+    async fn query_one_with(&self, sql: &str, params: &[&dyn ToSql]) -> DbResult<String> {
+        match self {
+            DbConnection::MySql(conn) => conn.query_one_with(sql, params).await,
+            DbConnection::Postgres(conn) => conn.query_one_with(sql, params).await,
+            DbConnection::Sqlite(conn) => conn.query_one_with(sql, params).await,
+            DbConnection::Redis(conn) => conn.query_one_with(sql, params).await,
+        }
+    }
+
+    async fn query_rows_with(&self, sql: &str, params: &[&dyn ToSql]) -> DbResult<Vec<Row>> {
+        match self {
+            DbConnection::MySql(conn) => conn.query_rows_with(sql, params).await,
+            DbConnection::Postgres(conn) => conn.query_rows_with(sql, params).await,
+            DbConnection::Sqlite(conn) => conn.query_rows_with(sql, params).await,
+            DbConnection::Redis(conn) => conn.query_rows_with(sql, params).await,
+        }
+    }
+
+    async fn execute_returning_id_with(&self, sql: &str, params: &[&dyn ToSql]) -> DbResult<i64> {
+        match self {
+            DbConnection::MySql(conn) => conn.execute_returning_id_with(sql, params).await,
+            DbConnection::Postgres(conn) => conn.execute_returning_id_with(sql, params).await,
+            DbConnection::Sqlite(conn) => conn.execute_returning_id_with(sql, params).await,
+            DbConnection::Redis(conn) => conn.execute_returning_id_with(sql, params).await,
+        }
+    }
+
+    fn query_stream(&self, sql: &str, params: &[&dyn ToSql]) -> RowStream<'_> {
+        match self {
+            DbConnection::MySql(conn) => conn.query_stream(sql, params),
+            DbConnection::Postgres(conn) => conn.query_stream(sql, params),
+            DbConnection::Sqlite(conn) => conn.query_stream(sql, params),
+            DbConnection::Redis(conn) => conn.query_stream(sql, params),
+        }
+    }
+
+    async fn execute_returning_id(&self, sql: &str) -> DbResult<i64> {
+        match self {
+            DbConnection::MySql(conn) => conn.execute_returning_id(sql).await,
+            DbConnection::Postgres(conn) => conn.execute_returning_id(sql).await,
+            DbConnection::Sqlite(conn) => conn.execute_returning_id(sql).await,
+            DbConnection::Redis(conn) => conn.execute_returning_id(sql).await,
+        }
+    }
+}
+
+/// Outcome of pinging one connection
+#[derive(Debug, Clone, Serialize)]
+pub struct PingHealth {
+    /// Whether the database answered the ping
+    pub healthy: bool,
+    /// Round-trip time of the ping in milliseconds
+    pub latency_ms: u64,
+    /// Error reported when the ping failed
+    pub error: Option<String>,
+}
+
+/// Ping the database and report its health
+pub async fn health_check<C: DatabaseConnection + ?Sized>(conn: &C) -> PingHealth {
+    let started = std::time::Instant::now();
+    let result = conn.ping().await;
+    PingHealth {
+        healthy: result.is_ok(),
+        latency_ms: started.elapsed().as_millis() as u64,
+        error: result.err(),
+    }
+}
+
+/// Ping outcome for the database behind one pool
+#[derive(Debug, Clone, Serialize)]
+pub struct ConnectionHealth {
+    pub connection: ConnectionInfo,
+    pub ping: PingHealth,
+}
+
+/// Combined health of several pools, e.g. for a `/healthz` endpoint.
+///
+/// `connections` and `pools` hold one entry per checked pool, in order.
+#[derive(Debug, Clone, Serialize)]
+pub struct DbHealth {
+    /// Whether every pool answered its ping
+    pub healthy: bool,
+    pub connections: Vec<ConnectionHealth>,
+    pub pools: Vec<PoolStatus>,
+}
+
+/// Ping a connection from each pool and gather its status.
+///
+/// A pool that cannot hand out a connection is reported as unhealthy with
+/// the checkout error; an empty `pools` is an error.
+pub async fn collect_db_health(pools: &[&ConnectionPool]) -> DbResult<DbHealth> {
+    if pools.is_empty() {
+        return Err("No connection pools to check".to_string());
+    }
+    let mut connections = Vec::with_capacity(pools.len());
+    let mut statuses = Vec::with_capacity(pools.len());
+    for pool in pools {
+        let started = std::time::Instant::now();
+        let ping = match pool.get().await {
+            Ok(conn) => health_check(&*conn).await,
+            Err(err) => PingHealth {
+                healthy: false,
+                latency_ms: started.elapsed().as_millis() as u64,
+                error: Some(err),
+            },
+        };
+        connections.push(ConnectionHealth {
+            connection: pool.connection_info(),
+            ping,
+        });
+        statuses.push(pool.status());
+    }
+    Ok(DbHealth {
+        healthy: connections.iter().all(|conn| conn.ping.healthy),
+        connections,
+        pools: statuses,
+    })
 }
 
 pub async fn connect(config: &DatabaseConfig) -> DbResult<DbConnection> {
+    if config.pool.statement_timeout.is_some() && config.database_type != DatabaseType::Postgres {
+        tracing::debug!(
+            database_type = %config.database_type,
+            "statement_timeout is only supported by Postgres, ignoring it"
+        );
+    }
     match config.database_type {
         DatabaseType::MySql => {
             let conn = mysql::MySqlConnection::connect(config).await?;
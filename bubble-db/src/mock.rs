@@ -0,0 +1,377 @@
+use crate::{
+    DatabaseConnection, DatabaseType, DbResult, ExecuteResult, Row, SqlParam, SqlValue, ToSql,
+    Transaction, prepare_params,
+};
+use async_trait::async_trait;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicI64, Ordering};
+
+/// Canned result returned for a matching statement
+#[derive(Debug, Clone)]
+enum MockResult {
+    Rows(String),
+    Affected(u64),
+    Error(String),
+}
+
+#[derive(Debug)]
+struct MockResponse {
+    pattern: String,
+    result: MockResult,
+}
+
+/// In-memory connection for tests.
+///
+/// Every statement is recorded with its bound parameters, and results come
+/// from responses queued with
+/// `push_rows`/`push_affected`/`push_error`. A statement consumes the oldest
+/// queued response whose pattern is a substring of its SQL (an empty pattern
+/// matches anything). Without a match, `query` returns `[]`, `query_one`
+/// fails with "No rows found", and `execute` reports zero affected rows.
+///
+/// The `_with` methods check placeholders against the parameters as the
+/// backend named by `database_type` would. `close` only marks the connection
+/// closed, as reported by `is_closed`.
+///
+/// ```rust,ignore
+/// let conn = MockConnection::new(DatabaseType::Postgres);
+/// conn.push_rows("FROM users", r#"[{"id": "1", "name": "alice"}]"#);
+/// let users = conn.query("SELECT * FROM users").await?;
+/// assert_eq!(conn.executed(), vec!["SELECT * FROM users"]);
+///
+/// conn.execute_with("DELETE FROM users WHERE id = $1", &[&1]).await?;
+/// assert_eq!(conn.last_params(), Some(vec![SqlParam::Int(1)]));
+/// ```
+#[derive(Debug)]
+pub struct MockConnection {
+    database_type: DatabaseType,
+    executed: Mutex<Vec<(String, Vec<SqlParam>)>>,
+    responses: Mutex<VecDeque<MockResponse>>,
+    next_id: AtomicI64,
+    closed: AtomicBool,
+}
+
+impl MockConnection {
+    pub fn new(database_type: DatabaseType) -> Self {
+        Self {
+            database_type,
+            executed: Mutex::new(Vec::new()),
+            responses: Mutex::new(VecDeque::new()),
+            next_id: AtomicI64::new(1),
+            closed: AtomicBool::new(false),
+        }
+    }
+
+    /// Queue JSON rows for the next query matching `pattern`
+    pub fn push_rows(&self, pattern: impl Into<String>, json: impl Into<String>) {
+        self.push(pattern.into(), MockResult::Rows(json.into()));
+    }
+
+    /// Queue an affected row count (or generated id) for the next statement
+    /// matching `pattern`
+    pub fn push_affected(&self, pattern: impl Into<String>, affected: u64) {
+        self.push(pattern.into(), MockResult::Affected(affected));
+    }
+
+    /// Queue an error for the next statement matching `pattern`
+    pub fn push_error(&self, pattern: impl Into<String>, error: impl Into<String>) {
+        self.push(pattern.into(), MockResult::Error(error.into()));
+    }
+
+    /// Statements run so far, in order
+    pub fn executed(&self) -> Vec<String> {
+        self.executed
+            .lock()
+            .map(|executed| executed.iter().map(|(sql, _)| sql.clone()).collect())
+            .unwrap_or_default()
+    }
+
+    /// Parameters bound to each statement, aligned with `executed`
+    pub fn executed_params(&self) -> Vec<Vec<SqlParam>> {
+        self.executed
+            .lock()
+            .map(|executed| executed.iter().map(|(_, params)| params.clone()).collect())
+            .unwrap_or_default()
+    }
+
+    /// The most recent statement
+    pub fn last_sql(&self) -> Option<String> {
+        self.executed
+            .lock()
+            .ok()
+            .and_then(|executed| executed.last().map(|(sql, _)| sql.clone()))
+    }
+
+    /// Parameters bound to the most recent statement
+    pub fn last_params(&self) -> Option<Vec<SqlParam>> {
+        self.executed
+            .lock()
+            .ok()
+            .and_then(|executed| executed.last().map(|(_, params)| params.clone()))
+    }
+
+    /// Number of queued responses not yet consumed
+    pub fn pending(&self) -> usize {
+        self.responses
+            .lock()
+            .map(|responses| responses.len())
+            .unwrap_or(0)
+    }
+
+    /// Whether `close` has been called
+    pub fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::SeqCst)
+    }
+
+    /// Forget recorded statements and queued responses
+    pub fn reset(&self) {
+        if let Ok(mut executed) = self.executed.lock() {
+            executed.clear();
+        }
+        if let Ok(mut responses) = self.responses.lock() {
+            responses.clear();
+        }
+    }
+
+    fn push(&self, pattern: String, result: MockResult) {
+        if let Ok(mut responses) = self.responses.lock() {
+            responses.push_back(MockResponse { pattern, result });
+        }
+    }
+
+    /// Record `sql` with its parameters and take the first queued response
+    /// matching it
+    fn respond(&self, sql: &str, params: Vec<SqlParam>) -> Option<MockResult> {
+        if let Ok(mut executed) = self.executed.lock() {
+            executed.push((sql.to_string(), params));
+        }
+        let mut responses = self.responses.lock().ok()?;
+        let index = responses
+            .iter()
+            .position(|response| sql.contains(&response.pattern))?;
+        responses.remove(index).map(|response| response.result)
+    }
+
+    fn run_execute(&self, sql: &str, params: Vec<SqlParam>) -> DbResult<u64> {
+        match self.respond(sql, params) {
+            Some(MockResult::Affected(affected)) => Ok(affected),
+            Some(MockResult::Error(error)) => Err(error),
+            Some(MockResult::Rows(_)) | None => Ok(0),
+        }
+    }
+
+    fn run_query(&self, sql: &str, params: Vec<SqlParam>) -> DbResult<String> {
+        match self.respond(sql, params) {
+            Some(MockResult::Rows(json)) => Ok(json),
+            Some(MockResult::Error(error)) => Err(error),
+            Some(MockResult::Affected(_)) | None => Ok("[]".to_string()),
+        }
+    }
+
+    fn run_query_one(&self, sql: &str, params: Vec<SqlParam>) -> DbResult<String> {
+        let json = match self.respond(sql, params) {
+            Some(MockResult::Rows(json)) => json,
+            Some(MockResult::Error(error)) => return Err(error),
+            Some(MockResult::Affected(_)) | None => return Err("No rows found".to_string()),
+        };
+        // Queued rows may be a single object or an array of them
+        let value: serde_json::Value = serde_json::from_str(&json).map_err(|e| e.to_string())?;
+        match value {
+            serde_json::Value::Array(rows) => match rows.into_iter().next() {
+                Some(row) => Ok(row.to_string()),
+                None => Err("No rows found".to_string()),
+            },
+            row => Ok(row.to_string()),
+        }
+    }
+
+    /// Rows of a query as typed columns
+    fn run_query_rows(&self, sql: &str, params: Vec<SqlParam>) -> DbResult<Vec<Row>> {
+        let json = self.run_query(sql, params)?;
+        let rows: Vec<serde_json::Map<String, serde_json::Value>> =
+            serde_json::from_str(&json).map_err(|e| e.to_string())?;
+        Ok(rows
+            .into_iter()
+            .map(|columns| {
+                let mut row = Row::new();
+                for (name, value) in columns {
+                    row.push(name, json_to_sql_value(value));
+                }
+                row
+            })
+            .collect())
+    }
+
+    fn run_returning_id(&self, sql: &str, params: Vec<SqlParam>) -> DbResult<i64> {
+        match self.respond(sql, params) {
+            Some(MockResult::Affected(id)) => Ok(id as i64),
+            Some(MockResult::Error(error)) => Err(error),
+            Some(MockResult::Rows(_)) | None => Ok(self.next_id.fetch_add(1, Ordering::SeqCst)),
+        }
+    }
+}
+
+/// Transaction on a `MockConnection`; `BEGIN`, `COMMIT` and `ROLLBACK` are
+/// recorded alongside the statements
+pub struct MockTransaction<'a> {
+    conn: &'a MockConnection,
+}
+
+#[async_trait]
+impl Transaction for MockTransaction<'_> {
+    fn database_type(&self) -> DatabaseType {
+        self.conn.database_type
+    }
+
+    async fn execute(&mut self, sql: &str) -> DbResult<u64> {
+        self.conn.run_execute(sql, Vec::new())
+    }
+
+    async fn query(&mut self, sql: &str) -> DbResult<String> {
+        self.conn.run_query(sql, Vec::new())
+    }
+
+    async fn query_one(&mut self, sql: &str) -> DbResult<String> {
+        self.conn.run_query_one(sql, Vec::new())
+    }
+
+    async fn execute_returning_id(&mut self, sql: &str) -> DbResult<i64> {
+        self.conn.run_returning_id(sql, Vec::new())
+    }
+
+    async fn execute_with(&mut self, sql: &str, params: &[&dyn ToSql]) -> DbResult<u64> {
+        let (sql, params) = prepare_params(sql, params, &self.conn.database_type)?;
+        self.conn.run_execute(&sql, params)
+    }
+
+    async fn query_with(&mut self, sql: &str, params: &[&dyn ToSql]) -> DbResult<String> {
+        let (sql, params) = prepare_params(sql, params, &self.conn.database_type)?;
+        self.conn.run_query(&sql, params)
+    }
+
+    async fn query_one_with(&mut self, sql: &str, params: &[&dyn ToSql]) -> DbResult<String> {
+        let (sql, params) = prepare_params(sql, params, &self.conn.database_type)?;
+        self.conn.run_query_one(&sql, params)
+    }
+
+    async fn execute_returning_id_with(
+        &mut self,
+        sql: &str,
+        params: &[&dyn ToSql],
+    ) -> DbResult<i64> {
+        let (sql, params) = prepare_params(sql, params, &self.conn.database_type)?;
+        self.conn.run_returning_id(&sql, params)
+    }
+
+    async fn commit(self: Box<Self>) -> DbResult<()> {
+        self.conn.run_execute("COMMIT", Vec::new()).map(|_| ())
+    }
+
+    async fn rollback(self: Box<Self>) -> DbResult<()> {
+        self.conn.run_execute("ROLLBACK", Vec::new()).map(|_| ())
+    }
+}
+
+#[async_trait]
+impl DatabaseConnection for MockConnection {
+    fn database_type(&self) -> DatabaseType {
+        self.database_type
+    }
+
+    async fn close(&self) -> DbResult<()> {
+        self.closed.store(true, Ordering::SeqCst);
+        Ok(())
+    }
+
+    async fn execute(&self, sql: &str) -> DbResult<u64> {
+        self.run_execute(sql, Vec::new())
+    }
+
+    async fn query(&self, sql: &str) -> DbResult<String> {
+        self.run_query(sql, Vec::new())
+    }
+
+    async fn query_one(&self, sql: &str) -> DbResult<String> {
+        self.run_query_one(sql, Vec::new())
+    }
+
+    async fn query_rows(&self, sql: &str) -> DbResult<Vec<Row>> {
+        self.run_query_rows(sql, Vec::new())
+    }
+
+    async fn execute_result_with(
+        &self,
+        sql: &str,
+        params: &[&dyn ToSql],
+    ) -> DbResult<ExecuteResult> {
+        let (sql, params) = prepare_params(sql, params, &self.database_type)?;
+        Ok(ExecuteResult {
+            rows_affected: self.run_execute(&sql, params)?,
+            last_insert_id: None,
+        })
+    }
+
+    async fn execute_with(&self, sql: &str, params: &[&dyn ToSql]) -> DbResult<u64> {
+        let (sql, params) = prepare_params(sql, params, &self.database_type)?;
+        self.run_execute(&sql, params)
+    }
+
+    async fn query_with(&self, sql: &str, params: &[&dyn ToSql]) -> DbResult<String> {
+        let (sql, params) = prepare_params(sql, params, &self.database_type)?;
+        self.run_query(&sql, params)
+    }
+
+    async fn query_one_with(&self, sql: &str, params: &[&dyn ToSql]) -> DbResult<String> {
+        let (sql, params) = prepare_params(sql, params, &self.database_type)?;
+        self.run_query_one(&sql, params)
+    }
+
+    async fn query_rows_with(&self, sql: &str, params: &[&dyn ToSql]) -> DbResult<Vec<Row>> {
+        let (sql, params) = prepare_params(sql, params, &self.database_type)?;
+        self.run_query_rows(&sql, params)
+    }
+
+    async fn execute_returning_id_with(&self, sql: &str, params: &[&dyn ToSql]) -> DbResult<i64> {
+        let (sql, params) = prepare_params(sql, params, &self.database_type)?;
+        self.run_returning_id(&sql, params)
+    }
+
+    async fn insert_batch(&self, table: &str, json_data: &str) -> DbResult<u64> {
+        let items: Vec<serde_json::Value> = serde_json::from_str(json_data)
+            .map_err(|e| format!("Failed to parse JSON data: {}", e))?;
+        let sql = format!("INSERT INTO {} VALUES {}", table, json_data);
+        match self.respond(&sql, Vec::new()) {
+            Some(MockResult::Affected(affected)) => Ok(affected),
+            Some(MockResult::Error(error)) => Err(error),
+            Some(MockResult::Rows(_)) | None => Ok(items.len() as u64),
+        }
+    }
+
+    async fn begin_transaction(&self) -> DbResult<Box<dyn Transaction + '_>> {
+        self.run_execute("BEGIN", Vec::new())?;
+        Ok(Box::new(MockTransaction { conn: self }))
+    }
+
+    async fn ping(&self) -> DbResult<()> {
+        Ok(())
+    }
+
+    async fn execute_returning_id(&self, sql: &str) -> DbResult<i64> {
+        self.run_returning_id(sql, Vec::new())
+    }
+}
+
+/// Typed value of a queued JSON column
+fn json_to_sql_value(value: serde_json::Value) -> SqlValue {
+    match value {
+        serde_json::Value::Null => SqlValue::Null,
+        serde_json::Value::Bool(b) => SqlValue::Bool(b),
+        serde_json::Value::Number(n) => match n.as_i64() {
+            Some(i) => SqlValue::Int(i),
+            None => SqlValue::Float(n.as_f64().unwrap_or_default()),
+        },
+        serde_json::Value::String(s) => SqlValue::Text(s),
+        other => SqlValue::Text(other.to_string()),
+    }
+}
@@ -1,158 +1,490 @@
-use crate::{DatabaseConfig, DatabaseConnection, DbResult};
+use crate::{
+    DatabaseConfig, DatabaseConnection, DatabaseType, DbResult, ExecuteResult, SqlParam, SqlValue,
+    ToSql, Transaction, prepare_params,
+};
 use async_trait::async_trait;
-use mysql_async::{Conn, prelude::Queryable};
-use std::collections::HashMap;
-use tokio::sync::Mutex;
+use mysql_async::{Conn, Params, prelude::Queryable};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::{MappedMutexGuard, Mutex, MutexGuard};
+
+/// Connections idle for longer than this are pinged before the next
+/// statement, so one dropped by the server (e.g. after `wait_timeout`) is
+/// replaced before anything is sent on it
+const PING_AFTER_IDLE: Duration = Duration::from_secs(1);
 
 #[derive(Debug)]
 pub struct MySqlConnection {
-    conn: Mutex<Conn>,
+    // `None` once the connection has been closed
+    conn: Mutex<Option<Conn>>,
+    prepared_statements: bool,
+    config: DatabaseConfig,
+    // Set when a transaction is dropped without commit or rollback
+    pending_rollback: AtomicBool,
+    // Set when a statement failed because the connection was lost
+    connection_lost: AtomicBool,
+    // When the connection was last handed out, in ms since the Unix epoch
+    last_used: AtomicU64,
 }
 
 impl MySqlConnection {
+    /// Connect using the statement cache size from the pool settings.
+    ///
+    /// When the cache is enabled, queries run as prepared statements cached
+    /// per connection by SQL text; the cache is dropped with the connection.
     pub async fn connect(config: &DatabaseConfig) -> DbResult<Self> {
-        let conn = Conn::new(
-            mysql_async::Opts::from_url(&config.connection_string()).map_err(|e| e.to_string())?,
-        )
-        .await
-        .map_err(|e| e.to_string())?;
-
+        config.validate()?;
+        let conn = Self::open(config).await?;
         Ok(Self {
-            conn: Mutex::new(conn),
+            conn: Mutex::new(Some(conn)),
+            prepared_statements: config.pool.statement_cache_capacity > 0,
+            config: config.clone(),
+            pending_rollback: AtomicBool::new(false),
+            connection_lost: AtomicBool::new(false),
+            last_used: AtomicU64::new(now_millis()),
         })
     }
+
+    async fn open(config: &DatabaseConfig) -> DbResult<Conn> {
+        let opts =
+            mysql_async::Opts::from_url(&config.connection_string()).map_err(|e| e.to_string())?;
+        let opts = mysql_async::OptsBuilder::from_opts(opts)
+            .stmt_cache_size(Some(config.pool.statement_cache_capacity));
+        Conn::new(opts).await.map_err(|e| e.to_string())
+    }
+
+    /// Lock the connection, failing once it has been closed.
+    ///
+    /// A connection the server has dropped is replaced first (see
+    /// `ensure_connected`), then a transaction abandoned by a previous
+    /// holder is rolled back.
+    async fn lock(&self) -> DbResult<MappedMutexGuard<'_, Conn>> {
+        let mut conn = MutexGuard::try_map(self.conn.lock().await, Option::as_mut)
+            .map_err(|_| "MySQL connection closed".to_string())?;
+        if self.ensure_connected(&mut conn).await? {
+            // The server discarded the transaction with the old connection
+            self.pending_rollback.store(false, Ordering::Release);
+        }
+        if self.pending_rollback.swap(false, Ordering::AcqRel)
+            && let Err(err) = conn.query_drop("ROLLBACK").await
+        {
+            tracing::warn!("Failed to roll back abandoned MySQL transaction: {}", err);
+        }
+        Ok(conn)
+    }
+
+    /// Ping a connection that has been idle for `PING_AFTER_IDLE` or whose
+    /// last statement lost it, reconnecting when the ping fails; returns
+    /// whether it was replaced.
+    ///
+    /// Statements themselves are never retried: one that failed mid-flight
+    /// may already have run on the server.
+    async fn ensure_connected(&self, conn: &mut Conn) -> DbResult<bool> {
+        let idle = now_millis().saturating_sub(self.last_used.load(Ordering::Acquire));
+        let lost = self.connection_lost.swap(false, Ordering::AcqRel);
+        let mut reconnected = false;
+        if (lost || idle >= PING_AFTER_IDLE.as_millis() as u64)
+            && let Err(err) = conn.ping().await
+        {
+            tracing::warn!("MySQL connection lost ({}), reconnecting", err);
+            if let Err(err) = self.reconnect(conn).await {
+                self.connection_lost.store(true, Ordering::Release);
+                return Err(err);
+            }
+            reconnected = true;
+        }
+        self.last_used.store(now_millis(), Ordering::Release);
+        Ok(reconnected)
+    }
+
+    /// Describe a failed statement, flagging the connection for a check
+    /// before the next one when the failure was a lost connection
+    fn statement_error(&self, err: mysql_async::Error) -> String {
+        if is_connection_lost(&err) {
+            self.connection_lost.store(true, Ordering::Release);
+        }
+        err.to_string()
+    }
+
+    async fn execute_params(&self, sql: &str, params: &[SqlParam]) -> DbResult<ExecuteResult> {
+        let mut conn = self.lock().await?;
+        execute_on(&mut conn, sql, params)
+            .await
+            .map_err(|e| self.statement_error(e))
+    }
+
+    async fn insert_params(&self, sql: &str, params: &[SqlParam]) -> DbResult<i64> {
+        let mut conn = self.lock().await?;
+        insert_returning_id(&mut conn, sql, params)
+            .await
+            .map_err(|e| self.statement_error(e))
+    }
+
+    async fn fetch_params(&self, sql: &str, params: &[SqlParam]) -> DbResult<Vec<crate::Row>> {
+        let mut conn = self.lock().await?;
+        fetch_rows(&mut conn, sql, params, self.prepared_statements)
+            .await
+            .map_err(|e| self.statement_error(e))
+    }
+
+    /// Replace a dropped connection with a fresh one
+    async fn reconnect(&self, conn: &mut Conn) -> DbResult<()> {
+        *conn = Self::open(&self.config)
+            .await
+            .map_err(|e| format!("MySQL reconnect failed: {}", e))?;
+        Ok(())
+    }
 }
 
-#[async_trait]
-impl DatabaseConnection for MySqlConnection {
-    async fn execute(&self, sql: &str) -> DbResult<u64> {
-        let mut conn = self.conn.lock().await;
-        conn.query_drop(sql).await.map_err(|e| e.to_string())?;
-        let result = conn
-            .query_iter("SELECT ROW_COUNT()")
+/// Current time in milliseconds since the Unix epoch
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// Whether `err` means the server closed the connection (e.g. after
+/// `wait_timeout`), as opposed to a failure of the statement itself
+fn is_connection_lost(err: &mysql_async::Error) -> bool {
+    match err {
+        mysql_async::Error::Io(_) => true,
+        mysql_async::Error::Driver(mysql_async::DriverError::ConnectionClosed) => true,
+        // CR_SERVER_GONE_ERROR, CR_SERVER_LOST, ER_CLIENT_INTERACTION_TIMEOUT
+        mysql_async::Error::Server(err) => matches!(err.code, 2006 | 2013 | 4031),
+        _ => false,
+    }
+}
+
+/// Run a query and collect its typed rows, preparing the statement when
+/// `prepared` is set or there are parameters to bind
+async fn fetch_rows(
+    conn: &mut Conn,
+    sql: &str,
+    params: &[SqlParam],
+    prepared: bool,
+) -> Result<Vec<crate::Row>, mysql_async::Error> {
+    if prepared || !params.is_empty() {
+        conn.exec_iter(sql, mysql_params(params))
+            .await?
+            .map_and_drop(|row| typed_row(&row))
             .await
-            .map_err(|e| e.to_string())?;
-        let rows = result
-            .map_and_drop(|row| row)
+    } else {
+        conn.query_iter(sql)
+            .await?
+            .map_and_drop(|row| typed_row(&row))
             .await
-            .map_err(|e| e.to_string())?;
+    }
+}
+
+/// Run a statement, as a prepared statement when there are parameters to bind
+async fn run_statement(
+    conn: &mut Conn,
+    sql: &str,
+    params: &[SqlParam],
+) -> Result<(), mysql_async::Error> {
+    if params.is_empty() {
+        conn.query_drop(sql).await
+    } else {
+        conn.exec_drop(sql, mysql_params(params)).await
+    }
+}
+
+/// Execute a statement, taking the affected rows and generated key from
+/// its OK packet
+async fn execute_on(
+    conn: &mut Conn,
+    sql: &str,
+    params: &[SqlParam],
+) -> Result<ExecuteResult, mysql_async::Error> {
+    run_statement(conn, sql, params).await?;
+    Ok(ExecuteResult {
+        rows_affected: conn.affected_rows(),
+        last_insert_id: conn
+            .last_insert_id()
+            .filter(|id| *id != 0)
+            .map(|id| id as i64),
+    })
+}
+
+/// Execute an INSERT and return the generated key
+async fn insert_returning_id(
+    conn: &mut Conn,
+    sql: &str,
+    params: &[SqlParam],
+) -> Result<i64, mysql_async::Error> {
+    run_statement(conn, sql, params).await?;
+    Ok(conn.last_insert_id().unwrap_or(0) as i64)
+}
 
-        if let Some(row) = rows.first() {
-            let affected: i64 = row.get(0).unwrap_or(0);
-            Ok(affected.max(0) as u64)
-        } else {
-            Ok(0)
+/// Positional statement parameters
+fn mysql_params(params: &[SqlParam]) -> Params {
+    if params.is_empty() {
+        Params::Empty
+    } else {
+        Params::Positional(params.iter().map(mysql_value).collect())
+    }
+}
+
+/// The MySQL value bound for a parameter
+fn mysql_value(param: &SqlParam) -> mysql_async::Value {
+    use chrono::{Datelike, Timelike};
+    use mysql_async::Value;
+    let datetime = |t: &chrono::NaiveDateTime| {
+        Value::Date(
+            t.year() as u16,
+            t.month() as u8,
+            t.day() as u8,
+            t.hour() as u8,
+            t.minute() as u8,
+            t.second() as u8,
+            // A leap second is carried in the nanoseconds
+            (t.nanosecond() / 1_000).min(999_999),
+        )
+    };
+    match param {
+        SqlParam::Null => Value::NULL,
+        SqlParam::Int(v) => Value::Int(*v),
+        SqlParam::Float(v) => Value::Double(*v),
+        SqlParam::Text(v) => Value::Bytes(v.clone().into_bytes()),
+        SqlParam::Bool(v) => Value::Int(i64::from(*v)),
+        SqlParam::Bytes(v) => Value::Bytes(v.clone()),
+        SqlParam::Timestamp(v) => datetime(v),
+        SqlParam::TimestampTz(v) => datetime(&v.naive_utc()),
+        SqlParam::Date(v) => datetime(&v.and_time(chrono::NaiveTime::MIN)),
+        SqlParam::Uuid(v) => Value::Bytes(v.hyphenated().to_string().into_bytes()),
+        SqlParam::Json(v) => Value::Bytes(v.to_string().into_bytes()),
+    }
+}
+
+/// Convert a row into typed column values
+fn typed_row(row: &mysql_async::Row) -> crate::Row {
+    use mysql_async::Value;
+    let mut typed = crate::Row::new();
+    for (i, column) in row.columns_ref().iter().enumerate() {
+        let value = match row.get::<Value, _>(i) {
+            None | Some(Value::NULL) => SqlValue::Null,
+            Some(Value::Int(i)) => SqlValue::Int(i),
+            Some(Value::UInt(u)) => i64::try_from(u)
+                .map(SqlValue::Int)
+                .unwrap_or_else(|_| SqlValue::Text(u.to_string())),
+            Some(Value::Float(f)) => SqlValue::Float(f64::from(f)),
+            Some(Value::Double(d)) => SqlValue::Float(d),
+            Some(Value::Bytes(bytes)) if is_binary_column(column) => SqlValue::Bytes(bytes),
+            value => SqlValue::Text(value_to_string(column, value)),
+        };
+        typed.push(column.name_str().to_string(), value);
+    }
+    typed
+}
+
+/// Character set id MySQL reports for binary data
+const BINARY_CHARSET: u16 = 63;
+
+/// Whether the column holds binary (BLOB/VARBINARY/BINARY) data
+fn is_binary_column(column: &mysql_async::Column) -> bool {
+    use mysql_async::consts::ColumnType;
+    column.character_set() == BINARY_CHARSET
+        && matches!(
+            column.column_type(),
+            ColumnType::MYSQL_TYPE_TINY_BLOB
+                | ColumnType::MYSQL_TYPE_MEDIUM_BLOB
+                | ColumnType::MYSQL_TYPE_LONG_BLOB
+                | ColumnType::MYSQL_TYPE_BLOB
+                | ColumnType::MYSQL_TYPE_VAR_STRING
+                | ColumnType::MYSQL_TYPE_VARCHAR
+                | ColumnType::MYSQL_TYPE_STRING
+        )
+}
+
+/// Convert a column value into its string representation.
+/// Binary columns are base64-encoded so they survive the JSON round-trip.
+fn value_to_string(column: &mysql_async::Column, value: Option<mysql_async::Value>) -> String {
+    match value {
+        Some(mysql_async::Value::Int(i)) => i.to_string(),
+        Some(mysql_async::Value::UInt(u)) => u.to_string(),
+        Some(mysql_async::Value::Float(f)) => f.to_string(),
+        Some(mysql_async::Value::Double(d)) => d.to_string(),
+        Some(mysql_async::Value::Bytes(bytes)) => {
+            if is_binary_column(column) {
+                crate::types::encode_binary(&bytes)
+            } else {
+                String::from_utf8_lossy(&bytes).to_string()
+            }
         }
+        Some(mysql_async::Value::Date(year, month, day, hour, minute, second, micro)) => {
+            format!(
+                "{}-{:02}-{:02} {:02}:{:02}:{:02}.{:06}",
+                year as i32, month, day, hour, minute, second, micro
+            )
+        }
+        Some(mysql_async::Value::Time(neg, days, hours, minutes, seconds, micros)) => {
+            format!(
+                "{}{} days {}:{:02}:{:02}.{:06}",
+                if neg { "-" } else { "" },
+                days,
+                hours,
+                minutes,
+                seconds,
+                micros
+            )
+        }
+        None | Some(mysql_async::Value::NULL) => "".to_string(),
     }
+}
 
-    async fn query(&self, sql: &str) -> DbResult<String> {
-        let mut conn = self.conn.lock().await;
-        let result = conn.query_iter(sql).await.map_err(|e| e.to_string())?;
-        let rows = result
-            .map_and_drop(|row| row)
+/// Transaction holding the MySQL connection lock until commit or rollback.
+///
+/// Dropping it without either releases the lock and leaves the rollback to
+/// the next caller to lock the connection.
+pub struct MySqlTransaction<'a> {
+    conn: MappedMutexGuard<'a, Conn>,
+    prepared_statements: bool,
+    pending_rollback: &'a AtomicBool,
+    finished: bool,
+}
+
+impl Drop for MySqlTransaction<'_> {
+    fn drop(&mut self) {
+        if !self.finished {
+            self.pending_rollback.store(true, Ordering::Release);
+        }
+    }
+}
+
+impl MySqlTransaction<'_> {
+    async fn execute_params(&mut self, sql: &str, params: &[SqlParam]) -> DbResult<u64> {
+        run_statement(&mut self.conn, sql, params)
             .await
             .map_err(|e| e.to_string())?;
-        let mut results = Vec::new();
-        for row in rows {
-            let mut map = HashMap::new();
-            for (i, column) in row.columns_ref().iter().enumerate() {
-                let name = column.name_str().to_string();
-                let opt_value: Option<mysql_async::Value> = row.get(i);
-                let value = match opt_value {
-                    Some(mysql_async::Value::Int(i)) => i.to_string(),
-                    Some(mysql_async::Value::UInt(u)) => u.to_string(),
-                    Some(mysql_async::Value::Float(f)) => f.to_string(),
-                    Some(mysql_async::Value::Double(d)) => d.to_string(),
-                    Some(mysql_async::Value::Bytes(bytes)) => {
-                        String::from_utf8_lossy(&bytes).to_string()
-                    }
-                    Some(mysql_async::Value::Date(
-                        year,
-                        month,
-                        day,
-                        hour,
-                        minute,
-                        second,
-                        micro,
-                    )) => {
-                        format!(
-                            "{}-{:02}-{:02} {:02}:{:02}:{:02}.{:06}",
-                            year as i32, month, day, hour, minute, second, micro
-                        )
-                    }
-                    Some(mysql_async::Value::Time(neg, days, hours, minutes, seconds, micros)) => {
-                        let total = (days as i64 * 86400
-                            + hours as i64 * 3600
-                            + minutes as i64 * 60
-                            + seconds as i64) as i64;
-                        let total = if neg { -total } else { total };
-                        format!(
-                            "{} days {}:{:02}:{:02}.{:06}",
-                            days, hours, minutes, seconds, micros
-                        )
-                    }
-                    None | Some(mysql_async::Value::NULL) => "".to_string(),
-                };
-                map.insert(name, value);
-            }
-            results.push(map);
-        }
-        serde_json::to_string(&results).map_err(|e| e.to_string())
+        Ok(self.conn.affected_rows())
     }
 
-    async fn query_one(&self, sql: &str) -> DbResult<String> {
-        let mut conn = self.conn.lock().await;
-        let result = conn.query_iter(sql).await.map_err(|e| e.to_string())?;
-        let rows = result
-            .map_and_drop(|row| row)
+    async fn fetch_rows(&mut self, sql: &str, params: &[SqlParam]) -> DbResult<Vec<crate::Row>> {
+        fetch_rows(&mut self.conn, sql, params, self.prepared_statements)
+            .await
+            .map_err(|e| e.to_string())
+    }
+}
+
+#[async_trait]
+impl Transaction for MySqlTransaction<'_> {
+    fn database_type(&self) -> DatabaseType {
+        DatabaseType::MySql
+    }
+
+    async fn execute(&mut self, sql: &str) -> DbResult<u64> {
+        self.execute_params(sql, &[]).await
+    }
+
+    async fn query(&mut self, sql: &str) -> DbResult<String> {
+        crate::types::rows_to_json(&self.fetch_rows(sql, &[]).await?)
+    }
+
+    async fn query_one(&mut self, sql: &str) -> DbResult<String> {
+        crate::types::first_row_to_json(&self.fetch_rows(sql, &[]).await?)
+    }
+
+    async fn execute_returning_id(&mut self, sql: &str) -> DbResult<i64> {
+        insert_returning_id(&mut self.conn, sql, &[])
+            .await
+            .map_err(|e| e.to_string())
+    }
+
+    async fn execute_with(&mut self, sql: &str, params: &[&dyn ToSql]) -> DbResult<u64> {
+        let (sql, params) = prepare_params(sql, params, &DatabaseType::MySql)?;
+        self.execute_params(&sql, &params).await
+    }
+
+    async fn query_with(&mut self, sql: &str, params: &[&dyn ToSql]) -> DbResult<String> {
+        let (sql, params) = prepare_params(sql, params, &DatabaseType::MySql)?;
+        crate::types::rows_to_json(&self.fetch_rows(&sql, &params).await?)
+    }
+
+    async fn query_one_with(&mut self, sql: &str, params: &[&dyn ToSql]) -> DbResult<String> {
+        let (sql, params) = prepare_params(sql, params, &DatabaseType::MySql)?;
+        crate::types::first_row_to_json(&self.fetch_rows(&sql, &params).await?)
+    }
+
+    async fn execute_returning_id_with(
+        &mut self,
+        sql: &str,
+        params: &[&dyn ToSql],
+    ) -> DbResult<i64> {
+        let (sql, params) = prepare_params(sql, params, &DatabaseType::MySql)?;
+        insert_returning_id(&mut self.conn, &sql, &params)
+            .await
+            .map_err(|e| e.to_string())
+    }
+
+    async fn commit(mut self: Box<Self>) -> DbResult<()> {
+        self.conn
+            .query_drop("COMMIT")
             .await
             .map_err(|e| e.to_string())?;
-        if let Some(row) = rows.first() {
-            let mut map = HashMap::new();
-            for (i, column) in row.columns_ref().iter().enumerate() {
-                let name = column.name_str().to_string();
-                let opt_value: Option<mysql_async::Value> = row.get(i);
-                let value = match opt_value {
-                    Some(mysql_async::Value::Int(i)) => i.to_string(),
-                    Some(mysql_async::Value::UInt(u)) => u.to_string(),
-                    Some(mysql_async::Value::Float(f)) => f.to_string(),
-                    Some(mysql_async::Value::Double(d)) => d.to_string(),
-                    Some(mysql_async::Value::Bytes(bytes)) => {
-                        String::from_utf8_lossy(&bytes).to_string()
-                    }
-                    Some(mysql_async::Value::Date(
-                        year,
-                        month,
-                        day,
-                        hour,
-                        minute,
-                        second,
-                        micro,
-                    )) => {
-                        format!(
-                            "{}-{:02}-{:02} {:02}:{:02}:{:02}.{:06}",
-                            year as i32, month, day, hour, minute, second, micro
-                        )
-                    }
-                    Some(mysql_async::Value::Time(neg, days, hours, minutes, seconds, micros)) => {
-                        let total = (days as i64 * 86400
-                            + hours as i64 * 3600
-                            + minutes as i64 * 60
-                            + seconds as i64) as i64;
-                        let total = if neg { -total } else { total };
-                        format!(
-                            "{} days {}:{:02}:{:02}.{:06}",
-                            days, hours, minutes, seconds, micros
-                        )
-                    }
-                    None | Some(mysql_async::Value::NULL) => "".to_string(),
-                };
-                map.insert(name, value);
-            }
-            serde_json::to_string(&map).map_err(|e| e.to_string())
-        } else {
-            Err("No rows found".to_string())
-        }
+        self.finished = true;
+        Ok(())
+    }
+
+    async fn rollback(mut self: Box<Self>) -> DbResult<()> {
+        self.conn
+            .query_drop("ROLLBACK")
+            .await
+            .map_err(|e| e.to_string())?;
+        self.finished = true;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl DatabaseConnection for MySqlConnection {
+    fn database_type(&self) -> DatabaseType {
+        DatabaseType::MySql
+    }
+
+    async fn execute(&self, sql: &str) -> DbResult<u64> {
+        Ok(self.execute_result(sql).await?.rows_affected)
+    }
+
+    async fn execute_result(&self, sql: &str) -> DbResult<ExecuteResult> {
+        self.execute_params(sql, &[]).await
+    }
+
+    async fn execute_result_with(
+        &self,
+        sql: &str,
+        params: &[&dyn ToSql],
+    ) -> DbResult<ExecuteResult> {
+        let (sql, params) = prepare_params(sql, params, &DatabaseType::MySql)?;
+        self.execute_params(&sql, &params).await
+    }
+
+    async fn execute_returning_id(&self, sql: &str) -> DbResult<i64> {
+        self.insert_params(sql, &[]).await
+    }
+
+    async fn execute_returning_id_with(&self, sql: &str, params: &[&dyn ToSql]) -> DbResult<i64> {
+        let (sql, params) = prepare_params(sql, params, &DatabaseType::MySql)?;
+        self.insert_params(&sql, &params).await
+    }
+
+    async fn query(&self, sql: &str) -> DbResult<String> {
+        let rows = self.query_rows(sql).await?;
+        crate::types::rows_to_json(&rows)
+    }
+
+    async fn query_rows(&self, sql: &str) -> DbResult<Vec<crate::Row>> {
+        self.fetch_params(sql, &[]).await
+    }
+
+    async fn query_rows_with(&self, sql: &str, params: &[&dyn ToSql]) -> DbResult<Vec<crate::Row>> {
+        let (sql, params) = prepare_params(sql, params, &DatabaseType::MySql)?;
+        self.fetch_params(&sql, &params).await
+    }
+
+    async fn query_one(&self, sql: &str) -> DbResult<String> {
+        let rows = self.query_rows(sql).await?;
+        crate::types::first_row_to_json(&rows)
     }
 
     async fn insert_batch(&self, table: &str, json_data: &str) -> DbResult<u64> {
@@ -161,11 +493,11 @@ impl DatabaseConnection for MySqlConnection {
         if items.is_empty() {
             return Ok(0);
         }
-        let mut conn = self.conn.lock().await;
+        let mut conn = self.lock().await?;
         let mut count = 0;
         conn.query_drop("START TRANSACTION")
             .await
-            .map_err(|e| e.to_string())?;
+            .map_err(|e| self.statement_error(e))?;
         for item in items {
             let value = crate::to_sql_value(&item)?;
             let sql = format!("INSERT INTO {} VALUES ({})", table, value);
@@ -175,4 +507,26 @@ impl DatabaseConnection for MySqlConnection {
         conn.query_drop("COMMIT").await.map_err(|e| e.to_string())?;
         Ok(count)
     }
+
+    async fn begin_transaction(&self) -> DbResult<Box<dyn Transaction + '_>> {
+        let mut conn = self.lock().await?;
+        conn.query_drop("START TRANSACTION")
+            .await
+            .map_err(|e| self.statement_error(e))?;
+        Ok(Box::new(MySqlTransaction {
+            conn,
+            prepared_statements: self.prepared_statements,
+            pending_rollback: &self.pending_rollback,
+            finished: false,
+        }))
+    }
+
+    async fn close(&self) -> DbResult<()> {
+        // Waits for a running query or open transaction to release the lock
+        let conn = self.conn.lock().await.take();
+        match conn {
+            Some(conn) => conn.disconnect().await.map_err(|e| e.to_string()),
+            None => Ok(()),
+        }
+    }
 }
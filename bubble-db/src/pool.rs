@@ -0,0 +1,238 @@
+use crate::{
+    ConnectionInfo, DatabaseConfig, DatabaseConnection, DatabaseType, DbConnection, DbResult,
+};
+use serde::Serialize;
+use std::ops::{Deref, DerefMut};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tokio::task::JoinHandle;
+use tokio::time::MissedTickBehavior;
+
+/// Snapshot of the pool's state
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct PoolStatus {
+    /// Number of open connections, idle or checked out
+    pub size: usize,
+    /// Number of open connections waiting in the pool
+    pub idle: usize,
+    /// Number of callers waiting for a connection
+    pub waiting: usize,
+}
+
+#[derive(Debug)]
+struct PoolInner {
+    config: DatabaseConfig,
+    idle: Mutex<Vec<DbConnection>>,
+    permits: Arc<Semaphore>,
+    max_connections: usize,
+    size: AtomicUsize,
+    waiting: AtomicUsize,
+    monitored: Mutex<Option<PoolStatus>>,
+}
+
+/// Pool of database connections bounded by `PoolConfig::max_connections`
+#[derive(Debug, Clone)]
+pub struct ConnectionPool {
+    inner: Arc<PoolInner>,
+}
+
+impl ConnectionPool {
+    /// Create a pool; connections are opened lazily on `get`.
+    ///
+    /// An in-memory SQLite database is held by a single connection reused by
+    /// every checkout, so its tables persist for the lifetime of the pool.
+    /// Checkouts of it are therefore serialized: while one is held, `get`
+    /// waits for it to be returned.
+    pub fn new(config: DatabaseConfig) -> Self {
+        let max_connections = if config.database_type == DatabaseType::Sqlite
+            && crate::sqlite::is_in_memory(&config.database)
+        {
+            1
+        } else {
+            config.pool.max_connections.max(1) as usize
+        };
+        Self {
+            inner: Arc::new(PoolInner {
+                config,
+                idle: Mutex::new(Vec::new()),
+                permits: Arc::new(Semaphore::new(max_connections)),
+                max_connections,
+                size: AtomicUsize::new(0),
+                waiting: AtomicUsize::new(0),
+                monitored: Mutex::new(None),
+            }),
+        }
+    }
+
+    /// Check out a connection.
+    ///
+    /// When every connection is in use, waits up to
+    /// `PoolConfig::connection_timeout` for one to be returned before failing.
+    pub async fn get(&self) -> DbResult<PooledConnection> {
+        let timeout = self.inner.config.pool.connection_timeout;
+        self.inner.waiting.fetch_add(1, Ordering::SeqCst);
+        let acquired =
+            tokio::time::timeout(timeout, self.inner.permits.clone().acquire_owned()).await;
+        self.inner.waiting.fetch_sub(1, Ordering::SeqCst);
+        let permit = match acquired {
+            Ok(Ok(permit)) => permit,
+            Ok(Err(_)) => return Err("Pool error: pool is closed".to_string()),
+            Err(_) => {
+                return Err(format!(
+                    "Pool error: timed out after {:?} waiting for a connection",
+                    timeout
+                ));
+            }
+        };
+
+        let idle = self.inner.idle.lock().map_err(|e| e.to_string())?.pop();
+        let conn = match idle {
+            Some(conn) => conn,
+            None => {
+                let conn = crate::connect(&self.inner.config).await?;
+                self.inner.size.fetch_add(1, Ordering::SeqCst);
+                conn
+            }
+        };
+
+        Ok(PooledConnection {
+            conn: Some(conn),
+            pool: self.inner.clone(),
+            _permit: permit,
+        })
+    }
+
+    /// Close the pool: new checkouts fail, checked-out connections get up to
+    /// `PoolConfig::connection_timeout` to be returned, and every idle
+    /// connection is closed.
+    ///
+    /// Connections still checked out after the timeout are dropped when
+    /// returned instead of going back to the pool.
+    pub async fn close(&self) -> DbResult<()> {
+        let timeout = self.inner.config.pool.connection_timeout;
+        let drained = tokio::time::timeout(
+            timeout,
+            self.inner
+                .permits
+                .acquire_many(self.inner.max_connections as u32),
+        )
+        .await;
+        if drained.is_err() {
+            tracing::warn!(
+                database = %self.inner.config.database,
+                "closing pool with connections still checked out after {:?}",
+                timeout
+            );
+        }
+        self.inner.permits.close();
+
+        let idle = std::mem::take(&mut *self.inner.idle.lock().map_err(|e| e.to_string())?);
+        let mut result = Ok(());
+        for conn in idle {
+            let closed = conn.close().await;
+            self.inner.size.fetch_sub(1, Ordering::SeqCst);
+            if result.is_ok() {
+                result = closed;
+            }
+        }
+        result
+    }
+
+    /// Current pool status
+    pub fn status(&self) -> PoolStatus {
+        self.inner.status()
+    }
+
+    /// Details of the database the pool connects to, without credentials
+    pub fn connection_info(&self) -> ConnectionInfo {
+        self.inner.config.connection_info()
+    }
+
+    /// Status recorded by the most recent `spawn_monitor` tick, or `None`
+    /// before the first one
+    pub fn last_monitored_status(&self) -> Option<PoolStatus> {
+        self.inner.monitored.lock().ok().and_then(|status| *status)
+    }
+
+    /// Record the pool status every `interval`, logging it at debug level
+    /// and keeping it for `last_monitored_status`.
+    ///
+    /// The task holds only a weak reference and ends once every handle to
+    /// the pool has been dropped.
+    pub fn spawn_monitor(&self, interval: Duration) -> JoinHandle<()> {
+        let pool = Arc::downgrade(&self.inner);
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            loop {
+                ticker.tick().await;
+                let Some(pool) = pool.upgrade() else {
+                    break;
+                };
+                let status = pool.status();
+                tracing::debug!(
+                    database = %pool.config.database,
+                    size = status.size,
+                    idle = status.idle,
+                    waiting = status.waiting,
+                    "connection pool status"
+                );
+                if let Ok(mut monitored) = pool.monitored.lock() {
+                    *monitored = Some(status);
+                }
+            }
+        })
+    }
+}
+
+impl PoolInner {
+    fn status(&self) -> PoolStatus {
+        PoolStatus {
+            size: self.size.load(Ordering::SeqCst),
+            idle: self.idle.lock().map(|idle| idle.len()).unwrap_or(0),
+            waiting: self.waiting.load(Ordering::SeqCst),
+        }
+    }
+}
+
+/// Connection checked out of a `ConnectionPool`, returned to the pool on drop
+#[derive(Debug)]
+pub struct PooledConnection {
+    conn: Option<DbConnection>,
+    pool: Arc<PoolInner>,
+    // Released after the connection is back in the idle list
+    _permit: OwnedSemaphorePermit,
+}
+
+impl Deref for PooledConnection {
+    type Target = DbConnection;
+
+    fn deref(&self) -> &DbConnection {
+        self.conn
+            .as_ref()
+            .expect("connection already returned to pool")
+    }
+}
+
+impl DerefMut for PooledConnection {
+    fn deref_mut(&mut self) -> &mut DbConnection {
+        self.conn
+            .as_mut()
+            .expect("connection already returned to pool")
+    }
+}
+
+impl Drop for PooledConnection {
+    fn drop(&mut self) {
+        if let Some(conn) = self.conn.take() {
+            match self.pool.idle.lock() {
+                Ok(mut idle) if !self.pool.permits.is_closed() => idle.push(conn),
+                _ => {
+                    self.pool.size.fetch_sub(1, Ordering::SeqCst);
+                }
+            }
+        }
+    }
+}
@@ -1,66 +1,375 @@
-use crate::{DatabaseConfig, DatabaseConnection, DbResult};
+use crate::{
+    DatabaseConfig, DatabaseConnection, DatabaseType, DbResult, ExecuteResult, RowStream, SqlParam,
+    SqlValue, ToSql, Transaction, prepare_params,
+};
 use async_trait::async_trait;
-use sqlx::{Column, Pool, Postgres, Row, postgres::PgPool};
-use std::collections::HashMap;
+use futures::StreamExt;
+use sqlx::encode::IsNull;
+use sqlx::postgres::types::Oid;
+use sqlx::postgres::{
+    PgArgumentBuffer, PgArguments, PgConnectOptions, PgPoolOptions, PgRow, PgTypeInfo,
+};
+use sqlx::query::Query;
+use sqlx::{Column, Pool, Postgres, Row, TypeInfo, ValueRef};
+use std::str::FromStr;
 
 #[derive(Debug)]
 pub struct PostgresConnection {
     pool: Pool<Postgres>,
+    persistent_statements: bool,
 }
 
 impl PostgresConnection {
+    /// Connect using the pool settings from the configuration.
+    ///
+    /// `PoolConfig::statement_timeout` is applied to each connection as it is
+    /// opened, so it holds for every checkout of that connection.
+    ///
+    /// Prepared statements are cached per physical connection, keyed by SQL
+    /// text, so a recycled connection starts with an empty cache.
     pub async fn connect(config: &DatabaseConfig) -> DbResult<Self> {
-        let pool = PgPool::connect(&config.connection_string())
+        config.validate()?;
+        let capacity = config.pool.statement_cache_capacity;
+        let options = PgConnectOptions::from_str(&config.connection_string())
+            .map_err(|e| e.to_string())?
+            .statement_cache_capacity(capacity);
+        let statement_timeout = config.pool.statement_timeout;
+        let pool = PgPoolOptions::new()
+            .after_connect(move |conn, _meta| {
+                Box::pin(async move {
+                    if let Some(timeout) = statement_timeout {
+                        let sql = format!("SET statement_timeout = {}", timeout.as_millis());
+                        sqlx::Executor::execute(conn, sql.as_str()).await?;
+                    }
+                    Ok(())
+                })
+            })
+            .max_connections(config.pool.max_connections)
+            .min_connections(config.pool.min_connections)
+            .acquire_timeout(config.pool.connection_timeout)
+            .idle_timeout(Some(config.pool.idle_timeout))
+            .connect_with(options)
             .await
             .map_err(|e| e.to_string())?;
 
-        Ok(Self { pool })
+        Ok(Self {
+            pool,
+            persistent_statements: capacity > 0,
+        })
     }
-}
-
-#[async_trait]
-impl DatabaseConnection for PostgresConnection {
-    async fn execute(&self, sql: &str) -> DbResult<u64> {
-        let result = sqlx::query(sql)
-            .execute(&self.pool)
-            .await
-            .map_err(|e| e.to_string())?;
 
+    async fn execute_params(&self, sql: &str, params: &[SqlParam]) -> DbResult<u64> {
+        let query = bind_query(self.persistent_statements, sql, params);
+        let result = query.execute(&self.pool).await.map_err(|e| e.to_string())?;
         Ok(result.rows_affected())
     }
 
-    async fn query(&self, sql: &str) -> DbResult<String> {
-        let rows = sqlx::query(sql)
+    async fn fetch_rows(&self, sql: &str, params: &[SqlParam]) -> DbResult<Vec<crate::Row>> {
+        let query = bind_query(self.persistent_statements, sql, params);
+        let rows = query
             .fetch_all(&self.pool)
             .await
             .map_err(|e| e.to_string())?;
-        let mut results = Vec::new();
-        for row in rows {
-            let mut map = HashMap::new();
-            let columns = row.columns();
-            for (i, column) in columns.iter().enumerate() {
-                let name = column.name().to_string();
-                let value: String = row.try_get(i).unwrap_or_default();
-                map.insert(name, value);
-            }
-            results.push(map);
-        }
-        serde_json::to_string(&results).map_err(|e| e.to_string())
+        Ok(rows.iter().map(Self::typed_row).collect())
     }
 
-    async fn query_one(&self, sql: &str) -> DbResult<String> {
-        let row = sqlx::query(sql)
+    async fn fetch_one(&self, sql: &str, params: &[SqlParam]) -> DbResult<String> {
+        let query = bind_query(self.persistent_statements, sql, params);
+        let row = query
             .fetch_one(&self.pool)
             .await
             .map_err(|e| e.to_string())?;
-        let mut map = HashMap::new();
-        let columns = row.columns();
-        for (i, column) in columns.iter().enumerate() {
-            let name = column.name().to_string();
-            let value: String = row.try_get(i).unwrap_or_default();
-            map.insert(name, value);
+        serde_json::to_string(&Self::typed_row(&row).to_text_map()).map_err(|e| e.to_string())
+    }
+
+    async fn insert_returning_id(&self, sql: &str, params: &[SqlParam]) -> DbResult<i64> {
+        let sql = returning_id_sql(sql);
+        let query = bind_query(self.persistent_statements, &sql, params);
+        let row = query
+            .fetch_one(&self.pool)
+            .await
+            .map_err(|e| e.to_string())?;
+        row.try_get::<i64, _>(0).map_err(|e| e.to_string())
+    }
+
+    /// Convert a row into typed column values
+    fn typed_row(row: &PgRow) -> crate::Row {
+        let mut typed = crate::Row::new();
+        for (i, column) in row.columns().iter().enumerate() {
+            let is_null = row.try_get_raw(i).map(|raw| raw.is_null()).unwrap_or(true);
+            let value = if is_null {
+                SqlValue::Null
+            } else {
+                let decoded = match column.type_info().name() {
+                    "BOOL" => row.try_get::<bool, _>(i).map(SqlValue::Bool),
+                    "INT2" => row.try_get::<i16, _>(i).map(|v| SqlValue::Int(v.into())),
+                    "INT4" => row.try_get::<i32, _>(i).map(|v| SqlValue::Int(v.into())),
+                    "INT8" => row.try_get::<i64, _>(i).map(SqlValue::Int),
+                    "FLOAT4" => row.try_get::<f32, _>(i).map(|v| SqlValue::Float(v.into())),
+                    "FLOAT8" => row.try_get::<f64, _>(i).map(SqlValue::Float),
+                    "BYTEA" => row.try_get::<Vec<u8>, _>(i).map(SqlValue::Bytes),
+                    "UUID" => row
+                        .try_get::<uuid::Uuid, _>(i)
+                        .map(|v| SqlValue::Text(v.to_string())),
+                    "TIMESTAMPTZ" => row
+                        .try_get::<chrono::DateTime<chrono::Utc>, _>(i)
+                        .map(|v| SqlValue::Text(v.to_rfc3339())),
+                    "TIMESTAMP" => row
+                        .try_get::<chrono::NaiveDateTime, _>(i)
+                        .map(|v| SqlValue::Text(v.to_string())),
+                    "DATE" => row
+                        .try_get::<chrono::NaiveDate, _>(i)
+                        .map(|v| SqlValue::Text(v.to_string())),
+                    "JSON" | "JSONB" => row
+                        .try_get::<serde_json::Value, _>(i)
+                        .map(|v| SqlValue::Text(v.to_string())),
+                    _ => row.try_get::<String, _>(i).map(SqlValue::Text),
+                };
+                decoded.unwrap_or_else(|_| SqlValue::Text(String::new()))
+            };
+            typed.push(column.name().to_string(), value);
         }
-        serde_json::to_string(&map).map_err(|e| e.to_string())
+        typed
+    }
+}
+
+/// `sql` with `RETURNING id` appended unless it already has a RETURNING
+/// clause, whose first column is then taken as the generated key
+fn returning_id_sql(sql: &str) -> String {
+    let sql = sql.trim_end().trim_end_matches(';');
+    if sql.to_ascii_uppercase().contains(" RETURNING ") {
+        sql.to_string()
+    } else {
+        format!("{} RETURNING id", sql)
+    }
+}
+
+/// NULL sent without a declared type, so the server infers it from the
+/// placeholder's context
+struct UntypedNull;
+
+impl sqlx::Type<Postgres> for UntypedNull {
+    fn type_info() -> PgTypeInfo {
+        PgTypeInfo::with_oid(Oid(0))
+    }
+}
+
+impl sqlx::Encode<'_, Postgres> for UntypedNull {
+    fn encode_by_ref(&self, _buf: &mut PgArgumentBuffer) -> IsNull {
+        IsNull::Yes
+    }
+}
+
+/// Bind `params` with their Postgres types
+fn bind_query<'q>(
+    persistent: bool,
+    sql: &'q str,
+    params: &'q [SqlParam],
+) -> Query<'q, Postgres, PgArguments> {
+    // The type inferred for an untyped NULL would stick to a cached statement
+    // and break later calls binding a value of another type
+    let persistent = persistent && !params.contains(&SqlParam::Null);
+    let mut query = sqlx::query(sql).persistent(persistent);
+    for param in params {
+        query = match param {
+            SqlParam::Null => query.bind(UntypedNull),
+            SqlParam::Int(v) => query.bind(*v),
+            SqlParam::Float(v) => query.bind(*v),
+            SqlParam::Text(v) => query.bind(v.as_str()),
+            SqlParam::Bool(v) => query.bind(*v),
+            SqlParam::Bytes(v) => query.bind(v.as_slice()),
+            SqlParam::Timestamp(v) => query.bind(*v),
+            SqlParam::TimestampTz(v) => query.bind(*v),
+            SqlParam::Date(v) => query.bind(*v),
+            SqlParam::Uuid(v) => query.bind(*v),
+            SqlParam::Json(v) => query.bind(sqlx::types::Json(v)),
+        };
+    }
+    query
+}
+
+/// Transaction on a connection checked out of the Postgres pool
+pub struct PostgresTransaction {
+    tx: sqlx::Transaction<'static, Postgres>,
+    persistent_statements: bool,
+}
+
+impl PostgresTransaction {
+    async fn execute_params(&mut self, sql: &str, params: &[SqlParam]) -> DbResult<u64> {
+        let query = bind_query(self.persistent_statements, sql, params);
+        let result = query
+            .execute(&mut *self.tx)
+            .await
+            .map_err(|e| e.to_string())?;
+        Ok(result.rows_affected())
+    }
+
+    async fn fetch_rows(&mut self, sql: &str, params: &[SqlParam]) -> DbResult<Vec<crate::Row>> {
+        let query = bind_query(self.persistent_statements, sql, params);
+        let rows = query
+            .fetch_all(&mut *self.tx)
+            .await
+            .map_err(|e| e.to_string())?;
+        Ok(rows.iter().map(PostgresConnection::typed_row).collect())
+    }
+
+    async fn fetch_one(&mut self, sql: &str, params: &[SqlParam]) -> DbResult<String> {
+        let query = bind_query(self.persistent_statements, sql, params);
+        let row = query
+            .fetch_one(&mut *self.tx)
+            .await
+            .map_err(|e| e.to_string())?;
+        serde_json::to_string(&PostgresConnection::typed_row(&row).to_text_map())
+            .map_err(|e| e.to_string())
+    }
+
+    async fn insert_returning_id(&mut self, sql: &str, params: &[SqlParam]) -> DbResult<i64> {
+        let sql = returning_id_sql(sql);
+        let query = bind_query(self.persistent_statements, &sql, params);
+        let row = query
+            .fetch_one(&mut *self.tx)
+            .await
+            .map_err(|e| e.to_string())?;
+        row.try_get::<i64, _>(0).map_err(|e| e.to_string())
+    }
+}
+
+#[async_trait]
+impl Transaction for PostgresTransaction {
+    fn database_type(&self) -> DatabaseType {
+        DatabaseType::Postgres
+    }
+
+    async fn execute(&mut self, sql: &str) -> DbResult<u64> {
+        self.execute_params(sql, &[]).await
+    }
+
+    async fn query(&mut self, sql: &str) -> DbResult<String> {
+        crate::types::rows_to_json(&self.fetch_rows(sql, &[]).await?)
+    }
+
+    async fn query_one(&mut self, sql: &str) -> DbResult<String> {
+        self.fetch_one(sql, &[]).await
+    }
+
+    async fn execute_returning_id(&mut self, sql: &str) -> DbResult<i64> {
+        self.insert_returning_id(sql, &[]).await
+    }
+
+    async fn execute_with(&mut self, sql: &str, params: &[&dyn ToSql]) -> DbResult<u64> {
+        let (sql, params) = prepare_params(sql, params, &DatabaseType::Postgres)?;
+        self.execute_params(&sql, &params).await
+    }
+
+    async fn query_with(&mut self, sql: &str, params: &[&dyn ToSql]) -> DbResult<String> {
+        let (sql, params) = prepare_params(sql, params, &DatabaseType::Postgres)?;
+        crate::types::rows_to_json(&self.fetch_rows(&sql, &params).await?)
+    }
+
+    async fn query_one_with(&mut self, sql: &str, params: &[&dyn ToSql]) -> DbResult<String> {
+        let (sql, params) = prepare_params(sql, params, &DatabaseType::Postgres)?;
+        self.fetch_one(&sql, &params).await
+    }
+
+    async fn execute_returning_id_with(
+        &mut self,
+        sql: &str,
+        params: &[&dyn ToSql],
+    ) -> DbResult<i64> {
+        let (sql, params) = prepare_params(sql, params, &DatabaseType::Postgres)?;
+        self.insert_returning_id(&sql, &params).await
+    }
+
+    async fn commit(self: Box<Self>) -> DbResult<()> {
+        self.tx.commit().await.map_err(|e| e.to_string())
+    }
+
+    async fn rollback(self: Box<Self>) -> DbResult<()> {
+        self.tx.rollback().await.map_err(|e| e.to_string())
+    }
+}
+
+#[async_trait]
+impl DatabaseConnection for PostgresConnection {
+    fn database_type(&self) -> DatabaseType {
+        DatabaseType::Postgres
+    }
+
+    async fn execute(&self, sql: &str) -> DbResult<u64> {
+        self.execute_params(sql, &[]).await
+    }
+
+    async fn execute_result_with(
+        &self,
+        sql: &str,
+        params: &[&dyn ToSql],
+    ) -> DbResult<ExecuteResult> {
+        let (sql, params) = prepare_params(sql, params, &DatabaseType::Postgres)?;
+        Ok(ExecuteResult {
+            rows_affected: self.execute_params(&sql, &params).await?,
+            last_insert_id: None,
+        })
+    }
+
+    async fn query(&self, sql: &str) -> DbResult<String> {
+        crate::types::rows_to_json(&self.fetch_rows(sql, &[]).await?)
+    }
+
+    async fn query_rows(&self, sql: &str) -> DbResult<Vec<crate::Row>> {
+        self.fetch_rows(sql, &[]).await
+    }
+
+    async fn query_rows_with(&self, sql: &str, params: &[&dyn ToSql]) -> DbResult<Vec<crate::Row>> {
+        let (sql, params) = prepare_params(sql, params, &DatabaseType::Postgres)?;
+        self.fetch_rows(&sql, &params).await
+    }
+
+    async fn query_one(&self, sql: &str) -> DbResult<String> {
+        self.fetch_one(sql, &[]).await
+    }
+
+    async fn query_one_with(&self, sql: &str, params: &[&dyn ToSql]) -> DbResult<String> {
+        let (sql, params) = prepare_params(sql, params, &DatabaseType::Postgres)?;
+        self.fetch_one(&sql, &params).await
+    }
+
+    async fn execute_returning_id(&self, sql: &str) -> DbResult<i64> {
+        self.insert_returning_id(sql, &[]).await
+    }
+
+    async fn execute_returning_id_with(&self, sql: &str, params: &[&dyn ToSql]) -> DbResult<i64> {
+        let (sql, params) = prepare_params(sql, params, &DatabaseType::Postgres)?;
+        self.insert_returning_id(&sql, &params).await
+    }
+
+    /// Streams with sqlx's `fetch` on a background task that hands rows over
+    /// a bounded channel, so a slow consumer pauses the cursor
+    fn query_stream(&self, sql: &str, params: &[&dyn ToSql]) -> RowStream<'_> {
+        let (sql, params) = match prepare_params(sql, params, &DatabaseType::Postgres) {
+            Ok(prepared) => prepared,
+            Err(e) => return crate::stream::failed(e),
+        };
+        let pool = self.pool.clone();
+        let persistent = self.persistent_statements;
+        let (tx, rx) = tokio::sync::mpsc::channel(crate::STREAM_CHUNK_SIZE);
+        tokio::spawn(async move {
+            let mut rows = bind_query(persistent, &sql, &params).fetch(&pool);
+            while let Some(row) = rows.next().await {
+                let row = row
+                    .map(|row| Self::typed_row(&row))
+                    .map_err(|e| e.to_string());
+                let failed = row.is_err();
+                // A send error means the stream was dropped
+                if tx.send(row).await.is_err() || failed {
+                    break;
+                }
+            }
+        });
+        futures::stream::unfold(
+            rx,
+            |mut rx| async move { rx.recv().await.map(|row| (row, rx)) },
+        )
+        .boxed()
     }
 
     async fn insert_batch(&self, table: &str, json_data: &str) -> DbResult<u64> {
@@ -80,4 +389,18 @@ impl DatabaseConnection for PostgresConnection {
         }
         self.execute(&sql).await
     }
+
+    async fn begin_transaction(&self) -> DbResult<Box<dyn Transaction + '_>> {
+        let tx = self.pool.begin().await.map_err(|e| e.to_string())?;
+        Ok(Box::new(PostgresTransaction {
+            tx,
+            persistent_statements: self.persistent_statements,
+        }))
+    }
+
+    async fn close(&self) -> DbResult<()> {
+        // Waits for checked-out connections to be returned, then closes them
+        self.pool.close().await;
+        Ok(())
+    }
 }
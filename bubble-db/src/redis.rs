@@ -1,4 +1,4 @@
-use crate::{DatabaseConfig, DatabaseConnection, DbResult};
+use crate::{DatabaseConfig, DatabaseConnection, DatabaseType, DbResult, Transaction};
 use async_trait::async_trait;
 use redis::{Client, Commands};
 use std::collections::HashMap;
@@ -10,17 +10,31 @@ pub struct RedisConnection {
 
 impl RedisConnection {
     pub async fn connect(config: &DatabaseConfig) -> DbResult<Self> {
+        config.validate()?;
         let client = Client::open(config.connection_string()).map_err(|e| e.to_string())?;
         Ok(Self { client })
     }
 
-    fn get_connection(&self) -> DbResult<redis::Connection> {
+    /// Open a synchronous connection for commands outside the `DatabaseConnection` API
+    pub fn get_connection(&self) -> DbResult<redis::Connection> {
         self.client.get_connection().map_err(|e| e.to_string())
     }
+
+    /// Open a multiplexed async connection; clones of it share one socket
+    pub async fn get_multiplexed_connection(&self) -> DbResult<redis::aio::MultiplexedConnection> {
+        self.client
+            .get_multiplexed_tokio_connection()
+            .await
+            .map_err(|e| e.to_string())
+    }
 }
 
 #[async_trait]
 impl DatabaseConnection for RedisConnection {
+    fn database_type(&self) -> DatabaseType {
+        DatabaseType::Redis
+    }
+
     async fn execute(&self, sql: &str) -> DbResult<u64> {
         let mut conn = self.get_connection()?;
         let parts: Vec<&str> = sql.split_whitespace().collect();
@@ -58,15 +72,23 @@ impl DatabaseConnection for RedisConnection {
                     .map_err(|e| e.to_string())?;
                 Ok(1)
             }
-            _ => Err("Unsupported Redis command".to_string()),
+            _ => Err(unsupported("command", &parts)),
         }
     }
 
+    async fn ping(&self) -> DbResult<()> {
+        let mut conn = self.get_connection()?;
+        let _: String = redis::cmd("PING")
+            .query(&mut conn)
+            .map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
     async fn query(&self, sql: &str) -> DbResult<String> {
         let mut conn = self.get_connection()?;
         let parts: Vec<&str> = sql.split_whitespace().collect();
-        match parts[0].to_uppercase().as_str() {
-            "GET" if parts.len() == 2 => {
+        match parts.first().map(|verb| verb.to_uppercase()).as_deref() {
+            Some("GET") if parts.len() == 2 => {
                 let value: Option<String> = conn.get(parts[1]).map_err(|e| e.to_string())?;
 
                 let result = if let Some(val) = value {
@@ -77,21 +99,21 @@ impl DatabaseConnection for RedisConnection {
 
                 serde_json::to_string(&result).map_err(|e| e.to_string())
             }
-            "HGETALL" if parts.len() == 2 => {
+            Some("HGETALL") if parts.len() == 2 => {
                 let map: HashMap<String, String> =
                     conn.hgetall(parts[1]).map_err(|e| e.to_string())?;
 
                 serde_json::to_string(&map).map_err(|e| e.to_string())
             }
-            _ => Err("Unsupported Redis query".to_string()),
+            _ => Err(unsupported("query", &parts)),
         }
     }
 
     async fn query_one(&self, sql: &str) -> DbResult<String> {
         let mut conn = self.get_connection()?;
         let parts: Vec<&str> = sql.split_whitespace().collect();
-        match parts[0].to_uppercase().as_str() {
-            "GET" if parts.len() == 2 => {
+        match parts.first().map(|verb| verb.to_uppercase()).as_deref() {
+            Some("GET") if parts.len() == 2 => {
                 let value: Option<String> = conn.get(parts[1]).map_err(|e| e.to_string())?;
 
                 if let Some(val) = value {
@@ -101,13 +123,13 @@ impl DatabaseConnection for RedisConnection {
                     Err("No data found".to_string())
                 }
             }
-            "HGETALL" if parts.len() == 2 => {
+            Some("HGETALL") if parts.len() == 2 => {
                 let map: HashMap<String, String> =
                     conn.hgetall(parts[1]).map_err(|e| e.to_string())?;
 
                 serde_json::to_string(&map).map_err(|e| e.to_string())
             }
-            _ => Err("Unsupported Redis query".to_string()),
+            _ => Err(unsupported("query", &parts)),
         }
     }
 
@@ -124,4 +146,22 @@ impl DatabaseConnection for RedisConnection {
         }
         Ok(count)
     }
+
+    async fn begin_transaction(&self) -> DbResult<Box<dyn Transaction + '_>> {
+        Err("Transactions are not supported by the Redis backend".to_string())
+    }
+}
+
+/// Error for a statement the backend cannot map to a Redis command, naming
+/// the attempted verb and its argument count
+fn unsupported(kind: &str, parts: &[&str]) -> String {
+    match parts.split_first() {
+        Some((verb, args)) => format!(
+            "Unsupported Redis {}: {} ({} arguments)",
+            kind,
+            verb.to_uppercase(),
+            args.len()
+        ),
+        None => format!("Unsupported Redis {}: empty statement", kind),
+    }
 }
@@ -0,0 +1,81 @@
+use crate::{DatabaseConnection, DbResult};
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+static DATABASES: Lazy<RwLock<HashMap<String, Arc<dyn DatabaseConnection>>>> =
+    Lazy::new(|| RwLock::new(HashMap::new()));
+
+/// Register a connection under `name` for models declared with
+/// `#[orm(connection = "name")]`.
+///
+/// Registering a name again replaces the connection used by later lookups
+/// and closes the replaced one in the background (it is dropped unclosed
+/// outside a Tokio runtime); callers still holding it see their queries fail.
+pub fn register_database(name: impl Into<String>, conn: impl DatabaseConnection + 'static) {
+    register_shared_database(name, Arc::new(conn));
+}
+
+/// Register a connection that is also used elsewhere, e.g. by a
+/// `HealthCheck`, under `name`; see `register_database`
+pub fn register_shared_database(name: impl Into<String>, conn: Arc<dyn DatabaseConnection>) {
+    let name = name.into();
+    let replaced = match DATABASES.write() {
+        Ok(mut databases) => databases.insert(name.clone(), conn),
+        Err(_) => return,
+    };
+    let Some(replaced) = replaced else {
+        return;
+    };
+    match tokio::runtime::Handle::try_current() {
+        Ok(runtime) => {
+            runtime.spawn(async move {
+                if let Err(err) = replaced.close().await {
+                    tracing::warn!("Failed to close replaced database '{}': {}", name, err);
+                }
+            });
+        }
+        Err(_) => tracing::debug!("Dropping replaced database '{}' without closing it", name),
+    }
+}
+
+/// The connection registered under `name`
+pub fn database(name: &str) -> DbResult<Arc<dyn DatabaseConnection>> {
+    DATABASES
+        .read()
+        .map_err(|e| e.to_string())?
+        .get(name)
+        .cloned()
+        .ok_or_else(|| format!("No database registered under '{}'", name))
+}
+
+/// Names of all registered connections
+pub fn database_names() -> Vec<String> {
+    DATABASES
+        .read()
+        .map(|databases| databases.keys().cloned().collect())
+        .unwrap_or_default()
+}
+
+/// Close every registered connection, e.g. on shutdown.
+///
+/// Connections stay registered, so later queries through them fail. All
+/// connections are closed even if one fails; the first error is returned.
+pub async fn close_databases() -> DbResult<()> {
+    let connections: Vec<(String, Arc<dyn DatabaseConnection>)> = DATABASES
+        .read()
+        .map_err(|e| e.to_string())?
+        .iter()
+        .map(|(name, conn)| (name.clone(), conn.clone()))
+        .collect();
+    let mut result = Ok(());
+    for (name, conn) in connections {
+        if let Err(err) = conn.close().await {
+            tracing::warn!("Failed to close database '{}': {}", name, err);
+            if result.is_ok() {
+                result = Err(err);
+            }
+        }
+    }
+    result
+}
@@ -0,0 +1,139 @@
+use crate::{
+    DatabaseConfig, DatabaseConnection, DatabaseType, DbResult, ExecuteResult, Row, RowStream,
+    ToSql, Transaction,
+};
+use async_trait::async_trait;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Connection routing reads to read replicas and everything else to the primary.
+///
+/// `query`/`query_one`/`query_rows` and their `_with` variants are round-robined
+/// across the replicas; writes, batch inserts and transactions always use the
+/// primary so they never observe replication lag. With no replicas every call
+/// goes to the primary.
+#[derive(Debug)]
+pub struct ReplicatedConnection {
+    primary: Box<dyn DatabaseConnection>,
+    replicas: Vec<Box<dyn DatabaseConnection>>,
+    next: AtomicUsize,
+}
+
+impl ReplicatedConnection {
+    pub fn new(
+        primary: Box<dyn DatabaseConnection>,
+        replicas: Vec<Box<dyn DatabaseConnection>>,
+    ) -> Self {
+        Self {
+            primary,
+            replicas,
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    /// Connect to the primary described by `config` and to each of its `replicas`
+    pub async fn connect(config: &DatabaseConfig) -> DbResult<Self> {
+        let primary = crate::connect(config).await?;
+        let mut replicas: Vec<Box<dyn DatabaseConnection>> = Vec::new();
+        for replica in &config.replicas {
+            replicas.push(Box::new(crate::connect(replica).await?));
+        }
+        Ok(Self::new(Box::new(primary), replicas))
+    }
+
+    /// The primary connection
+    pub fn primary(&self) -> &dyn DatabaseConnection {
+        self.primary.as_ref()
+    }
+
+    /// The next replica in round-robin order, or the primary when there are none
+    fn reader(&self) -> &dyn DatabaseConnection {
+        if self.replicas.is_empty() {
+            return self.primary.as_ref();
+        }
+        let index = self.next.fetch_add(1, Ordering::Relaxed) % self.replicas.len();
+        self.replicas[index].as_ref()
+    }
+}
+
+#[async_trait]
+impl DatabaseConnection for ReplicatedConnection {
+    fn database_type(&self) -> DatabaseType {
+        self.primary.database_type()
+    }
+
+    async fn execute(&self, sql: &str) -> DbResult<u64> {
+        self.primary.execute(sql).await
+    }
+
+    async fn execute_result(&self, sql: &str) -> DbResult<ExecuteResult> {
+        self.primary.execute_result(sql).await
+    }
+
+    async fn execute_result_with(
+        &self,
+        sql: &str,
+        params: &[&dyn ToSql],
+    ) -> DbResult<ExecuteResult> {
+        self.primary.execute_result_with(sql, params).await
+    }
+
+    async fn query(&self, sql: &str) -> DbResult<String> {
+        self.reader().query(sql).await
+    }
+
+    async fn query_one(&self, sql: &str) -> DbResult<String> {
+        self.reader().query_one(sql).await
+    }
+
+    async fn query_rows(&self, sql: &str) -> DbResult<Vec<Row>> {
+        self.reader().query_rows(sql).await
+    }
+
+    async fn query_with(&self, sql: &str, params: &[&dyn ToSql]) -> DbResult<String> {
+        self.reader().query_with(sql, params).await
+    }
+
+    async fn query_one_with(&self, sql: &str, params: &[&dyn ToSql]) -> DbResult<String> {
+        self.reader().query_one_with(sql, params).await
+    }
+
+    async fn query_rows_with(&self, sql: &str, params: &[&dyn ToSql]) -> DbResult<Vec<Row>> {
+        self.reader().query_rows_with(sql, params).await
+    }
+
+    fn query_stream(&self, sql: &str, params: &[&dyn ToSql]) -> RowStream<'_> {
+        self.reader().query_stream(sql, params)
+    }
+
+    /// Runs on the primary since `ANALYZE` executes the statement
+    async fn explain_analyze(&self, sql: &str, params: &[&dyn ToSql]) -> DbResult<String> {
+        self.primary.explain_analyze(sql, params).await
+    }
+
+    async fn insert_batch(&self, table: &str, json_data: &str) -> DbResult<u64> {
+        self.primary.insert_batch(table, json_data).await
+    }
+
+    async fn begin_transaction(&self) -> DbResult<Box<dyn Transaction + '_>> {
+        self.primary.begin_transaction().await
+    }
+
+    async fn ping(&self) -> DbResult<()> {
+        self.primary.ping().await
+    }
+
+    async fn close(&self) -> DbResult<()> {
+        for replica in &self.replicas {
+            replica.close().await?;
+        }
+        self.primary.close().await
+    }
+
+    async fn execute_returning_id(&self, sql: &str) -> DbResult<i64> {
+        self.primary.execute_returning_id(sql).await
+    }
+
+    async fn execute_returning_id_with(&self, sql: &str, params: &[&dyn ToSql]) -> DbResult<i64> {
+        self.primary.execute_returning_id_with(sql, params).await
+    }
+}
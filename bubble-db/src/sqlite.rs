@@ -1,8 +1,11 @@
-use crate::{DatabaseConfig, DatabaseConnection, DbResult};
+use crate::{
+    DatabaseConfig, DatabaseConnection, DatabaseType, DbResult, ExecuteResult, SqlParam, SqlValue,
+    ToSql, Transaction, prepare_params,
+};
 use async_trait::async_trait;
-use rusqlite::{Connection, Row};
-use std::collections::HashMap;
-use tokio::sync::Mutex;
+use rusqlite::types::{Value, ValueRef};
+use rusqlite::{Connection, OpenFlags, ParamsFromIter, Row, params_from_iter};
+use tokio::sync::{Mutex, MutexGuard};
 
 #[derive(Debug)]
 pub struct SqliteConnection {
@@ -10,57 +13,251 @@ pub struct SqliteConnection {
 }
 
 impl SqliteConnection {
+    /// Open the database file named by `config.database`.
+    ///
+    /// `:memory:` opens a private in-memory database, and `file:` URIs such as
+    /// `file::memory:?cache=shared` are opened with URI handling enabled.
     pub async fn connect(config: &DatabaseConfig) -> DbResult<Self> {
-        let conn = Connection::open(&config.database).map_err(|e| e.to_string())?;
+        config.validate()?;
+        let conn = if config.database == ":memory:" {
+            Connection::open_in_memory()
+        } else if config.database.starts_with("file:") {
+            Connection::open_with_flags(
+                &config.database,
+                OpenFlags::default() | OpenFlags::SQLITE_OPEN_URI,
+            )
+        } else {
+            Connection::open(&config.database)
+        }
+        .map_err(|e| e.to_string())?;
+        Self::apply_pragmas(&conn, config).map_err(|e| e.to_string())?;
         Ok(Self {
             conn: Mutex::new(conn),
         })
     }
 
-    fn row_to_map(row: &Row) -> DbResult<HashMap<String, String>> {
-        let mut map = HashMap::new();
+    /// Apply the `SqliteConfig` pragmas to a newly opened connection
+    fn apply_pragmas(conn: &Connection, config: &DatabaseConfig) -> rusqlite::Result<()> {
+        let pragmas = &config.sqlite;
+        conn.busy_timeout(pragmas.busy_timeout)?;
+        if !is_in_memory(&config.database) {
+            conn.pragma_update(None, "journal_mode", &pragmas.journal_mode)?;
+        }
+        conn.pragma_update(None, "synchronous", &pragmas.synchronous)?;
+        conn.pragma_update(None, "foreign_keys", pragmas.foreign_keys)
+    }
+
+    /// Convert a row into typed column values
+    fn typed_row(row: &Row) -> DbResult<crate::Row> {
+        let mut typed = crate::Row::new();
         for (i, column) in row.as_ref().column_names().iter().enumerate() {
-            let name = column.to_string();
-            let value: String = row.get(i).unwrap_or_default();
-            map.insert(name, value);
+            let value = match row.get_ref(i).map_err(|e| e.to_string())? {
+                ValueRef::Null => SqlValue::Null,
+                ValueRef::Integer(i) => SqlValue::Int(i),
+                ValueRef::Real(f) => SqlValue::Float(f),
+                ValueRef::Text(text) => SqlValue::Text(String::from_utf8_lossy(text).to_string()),
+                ValueRef::Blob(bytes) => SqlValue::Bytes(bytes.to_vec()),
+            };
+            typed.push(column.to_string(), value);
+        }
+        Ok(typed)
+    }
+
+    fn fetch_rows(conn: &Connection, sql: &str, params: &[SqlParam]) -> DbResult<Vec<crate::Row>> {
+        let mut stmt = conn.prepare(sql).map_err(|e| e.to_string())?;
+        let mut rows = stmt
+            .query(sqlite_params(params))
+            .map_err(|e| e.to_string())?;
+        let mut results = Vec::new();
+        while let Some(row) = rows.next().map_err(|e| e.to_string())? {
+            results.push(Self::typed_row(row)?);
+        }
+        Ok(results)
+    }
+
+    /// Execute a statement, reporting the affected rows and the rowid it inserted
+    fn execute_on(conn: &Connection, sql: &str, params: &[SqlParam]) -> DbResult<ExecuteResult> {
+        let rows_affected = conn
+            .execute(sql, sqlite_params(params))
+            .map_err(|e| e.to_string())? as u64;
+        // last_insert_rowid() keeps its value across non-INSERT statements
+        let keyword = sql.trim_start().get(..7).unwrap_or("").to_ascii_uppercase();
+        let inserted = rows_affected > 0 && (keyword == "INSERT " || keyword == "REPLACE");
+        Ok(ExecuteResult {
+            rows_affected,
+            last_insert_id: inserted.then(|| conn.last_insert_rowid()),
+        })
+    }
+
+    /// Execute an INSERT and return the rowid it generated
+    fn insert_returning_id(conn: &Connection, sql: &str, params: &[SqlParam]) -> DbResult<i64> {
+        conn.execute(sql, sqlite_params(params))
+            .map_err(|e| e.to_string())?;
+        Ok(conn.last_insert_rowid())
+    }
+}
+
+/// Positional statement parameters
+fn sqlite_params(params: &[SqlParam]) -> ParamsFromIter<Vec<Value>> {
+    params_from_iter(params.iter().map(sqlite_value).collect())
+}
+
+/// The SQLite value bound for a parameter; dates and times are stored as
+/// text in the format of `CURRENT_TIMESTAMP`
+fn sqlite_value(param: &SqlParam) -> Value {
+    let timestamp = |t: &chrono::NaiveDateTime| t.format("%Y-%m-%d %H:%M:%S%.f").to_string();
+    match param {
+        SqlParam::Null => Value::Null,
+        SqlParam::Int(v) => Value::Integer(*v),
+        SqlParam::Float(v) => Value::Real(*v),
+        SqlParam::Text(v) => Value::Text(v.clone()),
+        SqlParam::Bool(v) => Value::Integer(i64::from(*v)),
+        SqlParam::Bytes(v) => Value::Blob(v.clone()),
+        SqlParam::Timestamp(v) => Value::Text(timestamp(v)),
+        SqlParam::TimestampTz(v) => Value::Text(timestamp(&v.naive_utc())),
+        SqlParam::Date(v) => Value::Text(v.format("%Y-%m-%d").to_string()),
+        SqlParam::Uuid(v) => Value::Text(v.hyphenated().to_string()),
+        SqlParam::Json(v) => Value::Text(v.to_string()),
+    }
+}
+
+/// Whether `database` names an in-memory database, which lives only as long
+/// as the connections holding it
+pub(crate) fn is_in_memory(database: &str) -> bool {
+    database == ":memory:"
+        || database.starts_with("file::memory:")
+        || (database.starts_with("file:") && database.contains("mode=memory"))
+}
+
+/// Transaction holding the SQLite connection lock until commit or rollback.
+///
+/// Dropping it without either rolls the transaction back.
+pub struct SqliteTransaction<'a> {
+    conn: MutexGuard<'a, Connection>,
+}
+
+impl Drop for SqliteTransaction<'_> {
+    fn drop(&mut self) {
+        if !self.conn.is_autocommit()
+            && let Err(err) = self.conn.execute_batch("ROLLBACK")
+        {
+            tracing::warn!("Failed to roll back abandoned SQLite transaction: {}", err);
         }
-        Ok(map)
+    }
+}
+
+#[async_trait]
+impl Transaction for SqliteTransaction<'_> {
+    fn database_type(&self) -> DatabaseType {
+        DatabaseType::Sqlite
+    }
+
+    async fn execute(&mut self, sql: &str) -> DbResult<u64> {
+        SqliteConnection::execute_on(&self.conn, sql, &[]).map(|result| result.rows_affected)
+    }
+
+    async fn query(&mut self, sql: &str) -> DbResult<String> {
+        crate::types::rows_to_json(&SqliteConnection::fetch_rows(&self.conn, sql, &[])?)
+    }
+
+    async fn query_one(&mut self, sql: &str) -> DbResult<String> {
+        crate::types::first_row_to_json(&SqliteConnection::fetch_rows(&self.conn, sql, &[])?)
+    }
+
+    async fn execute_returning_id(&mut self, sql: &str) -> DbResult<i64> {
+        SqliteConnection::insert_returning_id(&self.conn, sql, &[])
+    }
+
+    async fn execute_with(&mut self, sql: &str, params: &[&dyn ToSql]) -> DbResult<u64> {
+        let (sql, params) = prepare_params(sql, params, &DatabaseType::Sqlite)?;
+        SqliteConnection::execute_on(&self.conn, &sql, &params).map(|result| result.rows_affected)
+    }
+
+    async fn query_with(&mut self, sql: &str, params: &[&dyn ToSql]) -> DbResult<String> {
+        let (sql, params) = prepare_params(sql, params, &DatabaseType::Sqlite)?;
+        crate::types::rows_to_json(&SqliteConnection::fetch_rows(&self.conn, &sql, &params)?)
+    }
+
+    async fn query_one_with(&mut self, sql: &str, params: &[&dyn ToSql]) -> DbResult<String> {
+        let (sql, params) = prepare_params(sql, params, &DatabaseType::Sqlite)?;
+        crate::types::first_row_to_json(&SqliteConnection::fetch_rows(&self.conn, &sql, &params)?)
+    }
+
+    async fn execute_returning_id_with(
+        &mut self,
+        sql: &str,
+        params: &[&dyn ToSql],
+    ) -> DbResult<i64> {
+        let (sql, params) = prepare_params(sql, params, &DatabaseType::Sqlite)?;
+        SqliteConnection::insert_returning_id(&self.conn, &sql, &params)
+    }
+
+    async fn commit(self: Box<Self>) -> DbResult<()> {
+        self.conn.execute_batch("COMMIT").map_err(|e| e.to_string())
+    }
+
+    async fn rollback(self: Box<Self>) -> DbResult<()> {
+        self.conn
+            .execute_batch("ROLLBACK")
+            .map_err(|e| e.to_string())
     }
 }
 
 #[async_trait]
 impl DatabaseConnection for SqliteConnection {
+    fn database_type(&self) -> DatabaseType {
+        DatabaseType::Sqlite
+    }
+
     async fn execute(&self, sql: &str) -> DbResult<u64> {
-        let mut conn = self.conn.lock().await;
-        conn.execute(sql, [])
-            .map(|n| n as u64)
-            .map_err(|e| e.to_string())
+        Ok(self.execute_result(sql).await?.rows_affected)
+    }
+
+    async fn execute_result(&self, sql: &str) -> DbResult<ExecuteResult> {
+        let conn = self.conn.lock().await;
+        Self::execute_on(&conn, sql, &[])
+    }
+
+    async fn execute_result_with(
+        &self,
+        sql: &str,
+        params: &[&dyn ToSql],
+    ) -> DbResult<ExecuteResult> {
+        let (sql, params) = prepare_params(sql, params, &DatabaseType::Sqlite)?;
+        let conn = self.conn.lock().await;
+        Self::execute_on(&conn, &sql, &params)
+    }
+
+    async fn execute_returning_id(&self, sql: &str) -> DbResult<i64> {
+        let conn = self.conn.lock().await;
+        Self::insert_returning_id(&conn, sql, &[])
+    }
+
+    async fn execute_returning_id_with(&self, sql: &str, params: &[&dyn ToSql]) -> DbResult<i64> {
+        let (sql, params) = prepare_params(sql, params, &DatabaseType::Sqlite)?;
+        let conn = self.conn.lock().await;
+        Self::insert_returning_id(&conn, &sql, &params)
     }
 
     async fn query(&self, sql: &str) -> DbResult<String> {
         let conn = self.conn.lock().await;
-        let mut stmt = conn.prepare(sql).map_err(|e| e.to_string())?;
-        let rows = stmt.query([]).map_err(|e| e.to_string())?;
-        let mut results = Vec::new();
-        let mut rows_iter = rows;
-        while let Some(row) = rows_iter.next().map_err(|e| e.to_string())? {
-            let map = Self::row_to_map(&row)?;
-            results.push(map);
-        }
-        serde_json::to_string(&results).map_err(|e| e.to_string())
+        crate::types::rows_to_json(&Self::fetch_rows(&conn, sql, &[])?)
     }
 
-    async fn query_one(&self, sql: &str) -> DbResult<String> {
+    async fn query_rows(&self, sql: &str) -> DbResult<Vec<crate::Row>> {
         let conn = self.conn.lock().await;
-        let mut stmt = conn.prepare(sql).map_err(|e| e.to_string())?;
-        let mut rows = stmt.query([]).map_err(|e| e.to_string())?;
+        Self::fetch_rows(&conn, sql, &[])
+    }
 
-        if let Some(row) = rows.next().map_err(|e| e.to_string())? {
-            let map = Self::row_to_map(&row)?;
-            serde_json::to_string(&map).map_err(|e| e.to_string())
-        } else {
-            Err("No rows found".to_string())
-        }
+    async fn query_rows_with(&self, sql: &str, params: &[&dyn ToSql]) -> DbResult<Vec<crate::Row>> {
+        let (sql, params) = prepare_params(sql, params, &DatabaseType::Sqlite)?;
+        let conn = self.conn.lock().await;
+        Self::fetch_rows(&conn, &sql, &params)
+    }
+
+    async fn query_one(&self, sql: &str) -> DbResult<String> {
+        let conn = self.conn.lock().await;
+        crate::types::first_row_to_json(&Self::fetch_rows(&conn, sql, &[])?)
     }
 
     async fn insert_batch(&self, table: &str, json_data: &str) -> DbResult<u64> {
@@ -80,4 +277,10 @@ impl DatabaseConnection for SqliteConnection {
         tx.commit().map_err(|e| e.to_string())?;
         Ok(items.len() as u64)
     }
+
+    async fn begin_transaction(&self) -> DbResult<Box<dyn Transaction + '_>> {
+        let conn = self.conn.lock().await;
+        conn.execute_batch("BEGIN").map_err(|e| e.to_string())?;
+        Ok(Box::new(SqliteTransaction { conn }))
+    }
 }
@@ -0,0 +1,70 @@
+use crate::{DatabaseConnection, DbResult, Row, SqlParam, ToSql};
+use futures::stream::{self, BoxStream, StreamExt, TryStreamExt};
+
+/// Rows yielded lazily by `query_stream`
+pub type RowStream<'a> = BoxStream<'a, DbResult<Row>>;
+
+/// Rows fetched per round trip when a backend streams by paging
+pub const STREAM_CHUNK_SIZE: usize = 1000;
+
+/// Stream the rows of `sql` by fetching `chunk_size` rows at a time with
+/// `LIMIT`/`OFFSET`, so at most one chunk is held in memory. `params` are
+/// bound to every page query.
+///
+/// Pages are only consistent if `sql` has a deterministic `ORDER BY`.
+pub fn paged_rows<C: DatabaseConnection + ?Sized>(
+    conn: &C,
+    sql: String,
+    params: Vec<SqlParam>,
+    chunk_size: usize,
+) -> RowStream<'_> {
+    let sql = sql.trim_end().trim_end_matches(';').to_string();
+    let state = (sql, params, Some(0usize));
+    stream::try_unfold(state, move |(sql, params, offset)| async move {
+        let Some(offset) = offset else {
+            return DbResult::Ok(None);
+        };
+        let page = format!(
+            "SELECT * FROM ({}) AS bubble_stream LIMIT {} OFFSET {}",
+            sql, chunk_size, offset
+        );
+        let refs: Vec<&dyn ToSql> = params.iter().map(|param| param as &dyn ToSql).collect();
+        let rows = conn.query_rows_with(&page, &refs).await?;
+        let next = (rows.len() == chunk_size).then_some(offset + chunk_size);
+        Ok(Some((rows, (sql, params, next))))
+    })
+    .map_ok(|rows| stream::iter(rows.into_iter().map(Ok)))
+    .try_flatten()
+    .boxed()
+}
+
+/// Stream the rows of `sql` from a shared connection, such as one returned
+/// by `database`.
+///
+/// The rows are forwarded from a task that keeps the connection alive until
+/// the stream ends or is dropped.
+pub fn shared_query_stream(
+    conn: std::sync::Arc<dyn DatabaseConnection>,
+    sql: String,
+) -> RowStream<'static> {
+    let (tx, rx) = tokio::sync::mpsc::channel(STREAM_CHUNK_SIZE);
+    tokio::spawn(async move {
+        let mut rows = conn.query_stream(&sql, &[]);
+        while let Some(row) = rows.next().await {
+            // A send error means the stream was dropped
+            if tx.send(row).await.is_err() {
+                break;
+            }
+        }
+    });
+    stream::unfold(
+        rx,
+        |mut rx| async move { rx.recv().await.map(|row| (row, rx)) },
+    )
+    .boxed()
+}
+
+/// A stream yielding only `error`
+pub(crate) fn failed(error: String) -> RowStream<'static> {
+    stream::once(async move { Err(error) }).boxed()
+}
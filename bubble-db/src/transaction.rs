@@ -0,0 +1,98 @@
+use crate::{DatabaseConnection, DatabaseType, DbResult, ToSql, binding_unsupported};
+use async_trait::async_trait;
+use futures::FutureExt;
+use futures::future::BoxFuture;
+use std::panic::AssertUnwindSafe;
+
+/// A database transaction bound to a single connection
+#[async_trait]
+pub trait Transaction: Send {
+    fn database_type(&self) -> DatabaseType;
+    async fn execute(&mut self, sql: &str) -> DbResult<u64>;
+    async fn query(&mut self, sql: &str) -> DbResult<String>;
+    async fn query_one(&mut self, sql: &str) -> DbResult<String>;
+    async fn commit(self: Box<Self>) -> DbResult<()>;
+    async fn rollback(self: Box<Self>) -> DbResult<()>;
+
+    /// Execute an INSERT and return the generated auto-increment key
+    async fn execute_returning_id(&mut self, _sql: &str) -> DbResult<i64> {
+        Err(format!(
+            "Returning generated keys is not supported by the {} backend",
+            self.database_type()
+        ))
+    }
+
+    /// Execute a statement with `params` bound by the driver.
+    ///
+    /// The `_with` defaults only run statements without parameters.
+    async fn execute_with(&mut self, sql: &str, params: &[&dyn ToSql]) -> DbResult<u64> {
+        if !params.is_empty() {
+            return Err(binding_unsupported(&self.database_type()));
+        }
+        self.execute(sql).await
+    }
+
+    async fn query_with(&mut self, sql: &str, params: &[&dyn ToSql]) -> DbResult<String> {
+        if !params.is_empty() {
+            return Err(binding_unsupported(&self.database_type()));
+        }
+        self.query(sql).await
+    }
+
+    async fn query_one_with(&mut self, sql: &str, params: &[&dyn ToSql]) -> DbResult<String> {
+        if !params.is_empty() {
+            return Err(binding_unsupported(&self.database_type()));
+        }
+        self.query_one(sql).await
+    }
+
+    async fn execute_returning_id_with(
+        &mut self,
+        sql: &str,
+        params: &[&dyn ToSql],
+    ) -> DbResult<i64> {
+        if !params.is_empty() {
+            return Err(binding_unsupported(&self.database_type()));
+        }
+        self.execute_returning_id(sql).await
+    }
+}
+
+/// Run `f` inside a transaction.
+///
+/// The transaction is committed when `f` returns `Ok` and rolled back when it
+/// returns `Err` or panics; a panic is resumed after the rollback.
+///
+/// ```rust,ignore
+/// let moved = with_transaction(&conn, |tx| {
+///     Box::pin(async move {
+///         tx.execute_with("UPDATE accounts SET balance = balance - $1 WHERE id = $2", &[&amount, &from]).await?;
+///         tx.execute_with("UPDATE accounts SET balance = balance + $1 WHERE id = $2", &[&amount, &to]).await
+///     })
+/// })
+/// .await?;
+/// ```
+pub async fn with_transaction<C, T, F>(conn: &C, f: F) -> DbResult<T>
+where
+    C: DatabaseConnection + ?Sized,
+    F: for<'t> FnOnce(&'t mut dyn Transaction) -> BoxFuture<'t, DbResult<T>>,
+{
+    let mut tx = conn.begin_transaction().await?;
+    let outcome = AssertUnwindSafe(f(tx.as_mut())).catch_unwind().await;
+    match outcome {
+        Ok(Ok(value)) => {
+            tx.commit().await?;
+            Ok(value)
+        }
+        Ok(Err(err)) => {
+            if let Err(rollback_err) = tx.rollback().await {
+                return Err(format!("{} (rollback failed: {})", err, rollback_err));
+            }
+            Err(err)
+        }
+        Err(panic) => {
+            let _ = tx.rollback().await;
+            std::panic::resume_unwind(panic)
+        }
+    }
+}
@@ -1 +1,708 @@
+use crate::DatabaseType;
+use std::collections::HashMap;
+
 pub type DbResult<T> = Result<T, String>;
+
+/// Conversion of a Rust value into a statement parameter
+pub trait ToSql: Send + Sync {
+    /// The value the driver binds to the placeholder
+    fn to_sql(&self) -> SqlParam;
+}
+
+/// Value bound to a statement placeholder by the database driver.
+///
+/// Postgres receives each value with its own type; MySQL and SQLite have no
+/// zone-aware or UUID types, so those are sent as UTC `DATETIME` values and
+/// hyphenated text respectively.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SqlParam {
+    Null,
+    Int(i64),
+    Float(f64),
+    Text(String),
+    Bool(bool),
+    Bytes(Vec<u8>),
+    Timestamp(chrono::NaiveDateTime),
+    TimestampTz(chrono::DateTime<chrono::Utc>),
+    Date(chrono::NaiveDate),
+    Uuid(uuid::Uuid),
+    Json(serde_json::Value),
+}
+
+impl ToSql for SqlParam {
+    fn to_sql(&self) -> SqlParam {
+        self.clone()
+    }
+}
+
+/// Quote a string as a SQL literal, doubling embedded single quotes
+pub fn quote_sql_string(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+/// Quote a string as a SQL literal using the backend's escaping rules.
+///
+/// MySQL treats backslashes as escapes inside string literals, so they are
+/// doubled along with quotes. Postgres strings containing backslashes are
+/// emitted as `E''` literals so they are read the same way regardless of
+/// `standard_conforming_strings`.
+pub fn quote_sql_string_for(value: &str, database_type: &DatabaseType) -> String {
+    match database_type {
+        DatabaseType::MySql => format!(
+            "'{}'",
+            value
+                .replace('\\', "\\\\")
+                .replace('\'', "''")
+                .replace('\0', "\\0")
+        ),
+        DatabaseType::Postgres if value.contains('\\') => {
+            format!("E'{}'", value.replace('\\', "\\\\").replace('\'', "''"))
+        }
+        _ => quote_sql_string(value),
+    }
+}
+
+macro_rules! impl_to_sql_integer {
+    ($($ty:ty),*) => {
+        $(
+            impl ToSql for $ty {
+                fn to_sql(&self) -> SqlParam {
+                    SqlParam::Int(i64::from(*self))
+                }
+            }
+        )*
+    };
+}
+
+impl_to_sql_integer!(i16, i32, i64, u16, u32);
+
+/// Values above `i64::MAX` are bound as their decimal text
+impl ToSql for u64 {
+    fn to_sql(&self) -> SqlParam {
+        i64::try_from(*self)
+            .map(SqlParam::Int)
+            .unwrap_or_else(|_| SqlParam::Text(self.to_string()))
+    }
+}
+
+/// NaN and infinities are bound as NULL
+impl ToSql for f64 {
+    fn to_sql(&self) -> SqlParam {
+        if self.is_finite() {
+            SqlParam::Float(*self)
+        } else {
+            SqlParam::Null
+        }
+    }
+}
+
+impl ToSql for f32 {
+    fn to_sql(&self) -> SqlParam {
+        (*self as f64).to_sql()
+    }
+}
+
+impl ToSql for String {
+    fn to_sql(&self) -> SqlParam {
+        SqlParam::Text(self.clone())
+    }
+}
+
+impl ToSql for str {
+    fn to_sql(&self) -> SqlParam {
+        SqlParam::Text(self.to_string())
+    }
+}
+
+impl ToSql for bool {
+    fn to_sql(&self) -> SqlParam {
+        SqlParam::Bool(*self)
+    }
+}
+
+impl<T: ToSql> ToSql for Option<T> {
+    fn to_sql(&self) -> SqlParam {
+        match self {
+            Some(value) => value.to_sql(),
+            None => SqlParam::Null,
+        }
+    }
+}
+
+impl<T: ToSql + ?Sized> ToSql for &T {
+    fn to_sql(&self) -> SqlParam {
+        (**self).to_sql()
+    }
+}
+
+impl ToSql for [u8] {
+    fn to_sql(&self) -> SqlParam {
+        SqlParam::Bytes(self.to_vec())
+    }
+}
+
+impl ToSql for Vec<u8> {
+    fn to_sql(&self) -> SqlParam {
+        SqlParam::Bytes(self.clone())
+    }
+}
+
+impl ToSql for chrono::NaiveDateTime {
+    fn to_sql(&self) -> SqlParam {
+        SqlParam::Timestamp(*self)
+    }
+}
+
+impl ToSql for chrono::NaiveDate {
+    fn to_sql(&self) -> SqlParam {
+        SqlParam::Date(*self)
+    }
+}
+
+impl<Tz> ToSql for chrono::DateTime<Tz>
+where
+    Tz: chrono::TimeZone,
+    Tz::Offset: Send + Sync,
+{
+    fn to_sql(&self) -> SqlParam {
+        SqlParam::TimestampTz(self.with_timezone(&chrono::Utc))
+    }
+}
+
+impl ToSql for uuid::Uuid {
+    fn to_sql(&self) -> SqlParam {
+        SqlParam::Uuid(*self)
+    }
+}
+
+impl ToSql for serde_json::Value {
+    fn to_sql(&self) -> SqlParam {
+        SqlParam::Json(self.clone())
+    }
+}
+
+/// Outcome of a statement run with `execute_result`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ExecuteResult {
+    /// Rows changed by the statement
+    pub rows_affected: u64,
+    /// Key generated by an INSERT, on backends that report it
+    pub last_insert_id: Option<i64>,
+}
+
+/// Typed column value returned by `query_rows`
+#[derive(Debug, Clone, PartialEq)]
+pub enum SqlValue {
+    Null,
+    Int(i64),
+    Float(f64),
+    Text(String),
+    Bool(bool),
+    Bytes(Vec<u8>),
+}
+
+impl SqlValue {
+    pub fn is_null(&self) -> bool {
+        matches!(self, SqlValue::Null)
+    }
+
+    /// String form used by the JSON query output: `None` for NULL and
+    /// base64 for binary data
+    pub fn to_text(&self) -> Option<String> {
+        match self {
+            SqlValue::Null => None,
+            SqlValue::Int(i) => Some(i.to_string()),
+            SqlValue::Float(f) => Some(f.to_string()),
+            SqlValue::Text(s) => Some(s.clone()),
+            SqlValue::Bool(b) => Some(b.to_string()),
+            SqlValue::Bytes(bytes) => Some(encode_binary(bytes)),
+        }
+    }
+}
+
+/// Row of typed column values, in select-list order
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Row {
+    columns: Vec<(String, SqlValue)>,
+}
+
+impl Row {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append a column
+    pub fn push(&mut self, name: impl Into<String>, value: SqlValue) {
+        self.columns.push((name.into(), value));
+    }
+
+    /// Value of the named column.
+    ///
+    /// Joins can select several columns with the same name (`SELECT a.id,
+    /// b.id`); the first one is returned with a warning, use `get_index` or
+    /// `get_all` to reach the others.
+    pub fn get(&self, name: &str) -> Option<&SqlValue> {
+        let matches = self.get_all(name);
+        if matches.len() > 1 {
+            tracing::warn!(
+                column = name,
+                count = matches.len(),
+                "ambiguous column name, using the first"
+            );
+        }
+        matches.first().copied()
+    }
+
+    /// Values of every column with this name, in select-list order
+    pub fn get_all(&self, name: &str) -> Vec<&SqlValue> {
+        self.columns
+            .iter()
+            .filter(|(column, _)| column == name)
+            .map(|(_, value)| value)
+            .collect()
+    }
+
+    /// Value at a select-list position
+    pub fn get_index(&self, index: usize) -> Option<&SqlValue> {
+        self.columns.get(index).map(|(_, value)| value)
+    }
+
+    /// Column names in select-list order
+    pub fn columns(&self) -> impl Iterator<Item = &str> {
+        self.columns.iter().map(|(name, _)| name.as_str())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &SqlValue)> {
+        self.columns
+            .iter()
+            .map(|(name, value)| (name.as_str(), value))
+    }
+
+    pub fn len(&self) -> usize {
+        self.columns.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.columns.is_empty()
+    }
+
+    /// Column name to string value map used by the JSON query output; of
+    /// several columns with the same name only the first is kept
+    pub fn to_text_map(&self) -> HashMap<String, Option<String>> {
+        let mut map = HashMap::with_capacity(self.columns.len());
+        for (name, value) in self.iter() {
+            map.entry(name.to_string())
+                .or_insert_with(|| value.to_text());
+        }
+        map
+    }
+}
+
+impl std::ops::Index<usize> for Row {
+    type Output = SqlValue;
+
+    fn index(&self, index: usize) -> &SqlValue {
+        &self.columns[index].1
+    }
+}
+
+impl IntoIterator for Row {
+    type Item = (String, SqlValue);
+    type IntoIter = std::vec::IntoIter<(String, SqlValue)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.columns.into_iter()
+    }
+}
+
+/// Conversion of a column value into a Rust type, used by `query_scalar`
+pub trait FromSqlValue: Sized {
+    fn from_sql_value(value: SqlValue) -> DbResult<Self>;
+}
+
+fn unexpected(value: &SqlValue, target: &str) -> String {
+    match value {
+        SqlValue::Null => format!("Unexpected NULL where {} was expected", target),
+        value => format!("Cannot convert {:?} to {}", value, target),
+    }
+}
+
+impl FromSqlValue for i64 {
+    fn from_sql_value(value: SqlValue) -> DbResult<Self> {
+        match value {
+            SqlValue::Int(i) => Ok(i),
+            SqlValue::Bool(b) => Ok(b as i64),
+            SqlValue::Float(f) if f.fract() == 0.0 => Ok(f as i64),
+            // Decimal aggregates (e.g. Postgres NUMERIC) arrive as text
+            SqlValue::Text(ref s) => s.trim().parse().map_err(|_| unexpected(&value, "i64")),
+            value => Err(unexpected(&value, "i64")),
+        }
+    }
+}
+
+impl FromSqlValue for i32 {
+    fn from_sql_value(value: SqlValue) -> DbResult<Self> {
+        let i = i64::from_sql_value(value)?;
+        i.try_into()
+            .map_err(|_| format!("Value {} does not fit in i32", i))
+    }
+}
+
+impl FromSqlValue for f64 {
+    fn from_sql_value(value: SqlValue) -> DbResult<Self> {
+        match value {
+            SqlValue::Float(f) => Ok(f),
+            SqlValue::Int(i) => Ok(i as f64),
+            SqlValue::Text(ref s) => s.trim().parse().map_err(|_| unexpected(&value, "f64")),
+            value => Err(unexpected(&value, "f64")),
+        }
+    }
+}
+
+impl FromSqlValue for bool {
+    fn from_sql_value(value: SqlValue) -> DbResult<Self> {
+        match value {
+            SqlValue::Bool(b) => Ok(b),
+            SqlValue::Int(i) => Ok(i != 0),
+            SqlValue::Text(ref s) => match s.trim().to_ascii_lowercase().as_str() {
+                "t" | "true" | "1" => Ok(true),
+                "f" | "false" | "0" => Ok(false),
+                _ => Err(unexpected(&value, "bool")),
+            },
+            value => Err(unexpected(&value, "bool")),
+        }
+    }
+}
+
+impl FromSqlValue for String {
+    fn from_sql_value(value: SqlValue) -> DbResult<Self> {
+        match value {
+            SqlValue::Text(s) => Ok(s),
+            value => value.to_text().ok_or_else(|| unexpected(&value, "String")),
+        }
+    }
+}
+
+impl FromSqlValue for Vec<u8> {
+    fn from_sql_value(value: SqlValue) -> DbResult<Self> {
+        match value {
+            SqlValue::Bytes(bytes) => Ok(bytes),
+            SqlValue::Text(s) => Ok(s.into_bytes()),
+            value => Err(unexpected(&value, "Vec<u8>")),
+        }
+    }
+}
+
+impl<T: FromSqlValue> FromSqlValue for Option<T> {
+    fn from_sql_value(value: SqlValue) -> DbResult<Self> {
+        match value {
+            SqlValue::Null => Ok(None),
+            value => T::from_sql_value(value).map(Some),
+        }
+    }
+}
+
+impl FromSqlValue for SqlValue {
+    fn from_sql_value(value: SqlValue) -> DbResult<Self> {
+        Ok(value)
+    }
+}
+
+/// Serialize rows as the JSON query output
+pub(crate) fn rows_to_json(rows: &[Row]) -> DbResult<String> {
+    let maps: Vec<_> = rows.iter().map(Row::to_text_map).collect();
+    serde_json::to_string(&maps).map_err(|e| e.to_string())
+}
+
+/// Serialize the first row as the JSON `query_one` output
+pub(crate) fn first_row_to_json(rows: &[Row]) -> DbResult<String> {
+    match rows.first() {
+        Some(row) => serde_json::to_string(&row.to_text_map()).map_err(|e| e.to_string()),
+        None => Err("No rows found".to_string()),
+    }
+}
+
+/// Encode binary column data for the JSON query output
+pub fn encode_binary(bytes: &[u8]) -> String {
+    use base64::Engine;
+    base64::engine::general_purpose::STANDARD.encode(bytes)
+}
+
+/// Decode binary column data produced by [`encode_binary`]
+pub fn decode_binary(encoded: &str) -> DbResult<Vec<u8>> {
+    use base64::Engine;
+    base64::engine::general_purpose::STANDARD
+        .decode(encoded)
+        .map_err(|e| format!("Failed to decode binary column: {}", e))
+}
+
+/// Match positional placeholders to `params` for binding by the driver.
+///
+/// Postgres statements use `$n`, which is left in place; `?` is not a
+/// placeholder there, so the `?`, `?|` and `?&` JSON operators pass through.
+/// MySQL and SQLite bind `?` in order, and `$n` is rewritten to `?` for them.
+/// Placeholders inside quoted literals, quoted identifiers and comments are
+/// ignored. Returns the statement to prepare and the values in binding order;
+/// every parameter must be used.
+pub fn prepare_params(
+    sql: &str,
+    params: &[&dyn ToSql],
+    database_type: &DatabaseType,
+) -> DbResult<(String, Vec<SqlParam>)> {
+    let chars: Vec<char> = sql.chars().collect();
+    let mut output = String::with_capacity(sql.len());
+    let mut bound = Vec::with_capacity(params.len());
+    let mut next_index = 0;
+    let mut used = vec![false; params.len()];
+    let mut i = 0;
+    while i < chars.len() {
+        if let Some(end) = skip_ignored(&chars, i, database_type) {
+            output.extend(&chars[i..end]);
+            i = end;
+            continue;
+        }
+        let c = chars[i];
+        match c {
+            '?' if *database_type != DatabaseType::Postgres => {
+                let param = params.get(next_index).ok_or_else(|| {
+                    format!(
+                        "Missing parameter for placeholder #{} (got {} parameters)",
+                        next_index + 1,
+                        params.len()
+                    )
+                })?;
+                bound.push(param.to_sql());
+                output.push('?');
+                used[next_index] = true;
+                next_index += 1;
+                i += 1;
+            }
+            '$' if chars.get(i + 1).is_some_and(|c| c.is_ascii_digit()) => {
+                let start = i + 1;
+                let mut end = start;
+                while end < chars.len() && chars[end].is_ascii_digit() {
+                    end += 1;
+                }
+                let number: String = chars[start..end].iter().collect();
+                let index: usize = number
+                    .parse()
+                    .map_err(|_| format!("Invalid placeholder ${}", number))?;
+                let param = index
+                    .checked_sub(1)
+                    .and_then(|index| params.get(index))
+                    .ok_or_else(|| {
+                        format!(
+                            "Missing parameter for placeholder ${} (got {} parameters)",
+                            index,
+                            params.len()
+                        )
+                    })?;
+                used[index - 1] = true;
+                if *database_type == DatabaseType::Postgres {
+                    output.extend(&chars[i..end]);
+                } else {
+                    bound.push(param.to_sql());
+                    output.push('?');
+                }
+                i = end;
+            }
+            _ => {
+                output.push(c);
+                i += 1;
+            }
+        }
+    }
+    if used.iter().any(|used| !used) {
+        return Err(format!(
+            "Statement uses fewer placeholders than the {} supplied parameters",
+            params.len()
+        ));
+    }
+    if *database_type == DatabaseType::Postgres {
+        bound = params.iter().map(|param| param.to_sql()).collect();
+    }
+    Ok((output, bound))
+}
+
+/// Number `?` placeholders as Postgres `$1`, `$2`, ..., skipping quoted
+/// literals, quoted identifiers and comments
+pub(crate) fn number_placeholders(sql: &str) -> String {
+    let chars: Vec<char> = sql.chars().collect();
+    let mut output = String::with_capacity(sql.len());
+    let mut number = 0;
+    let mut i = 0;
+    while i < chars.len() {
+        if let Some(end) = skip_ignored(&chars, i, &DatabaseType::Postgres) {
+            output.extend(&chars[i..end]);
+            i = end;
+            continue;
+        }
+        if chars[i] == '?' {
+            number += 1;
+            output.push_str(&format!("${}", number));
+        } else {
+            output.push(chars[i]);
+        }
+        i += 1;
+    }
+    output
+}
+
+/// Rewrite `:name` placeholders into the backend's positional style.
+///
+/// Postgres gets `$n` (a repeated name reuses its number), other backends get
+/// `?` with the parameter repeated. Returns the rewritten statement and the
+/// parameters in placeholder order, ready for the `_with` methods.
+/// Occurrences inside quoted literals, quoted identifiers and comments, and
+/// Postgres `::type` casts, are left untouched.
+pub fn rewrite_named_params<'a>(
+    sql: &str,
+    params: &[(&str, &'a dyn ToSql)],
+    database_type: &DatabaseType,
+) -> DbResult<(String, Vec<&'a dyn ToSql>)> {
+    let chars: Vec<char> = sql.chars().collect();
+    let mut output = String::with_capacity(sql.len());
+    let mut ordered: Vec<&'a dyn ToSql> = Vec::new();
+    let mut numbered: Vec<&str> = Vec::new();
+    let mut used = vec![false; params.len()];
+    let mut i = 0;
+    while i < chars.len() {
+        if let Some(end) = skip_ignored(&chars, i, database_type) {
+            output.extend(&chars[i..end]);
+            i = end;
+            continue;
+        }
+        let c = chars[i];
+        match c {
+            ':' if chars.get(i + 1) == Some(&':') => {
+                output.push_str("::");
+                i += 2;
+            }
+            ':' if chars
+                .get(i + 1)
+                .is_some_and(|c| c.is_ascii_alphabetic() || *c == '_') =>
+            {
+                let start = i + 1;
+                let mut end = start;
+                while end < chars.len() && (chars[end].is_ascii_alphanumeric() || chars[end] == '_')
+                {
+                    end += 1;
+                }
+                let name: String = chars[start..end].iter().collect();
+                let index = params
+                    .iter()
+                    .position(|(param, _)| *param == name)
+                    .ok_or_else(|| format!("Missing value for named parameter :{}", name))?;
+                used[index] = true;
+                let (param_name, value) = params[index];
+                if *database_type == DatabaseType::Postgres {
+                    let number = match numbered.iter().position(|n| *n == param_name) {
+                        Some(number) => number + 1,
+                        None => {
+                            numbered.push(param_name);
+                            ordered.push(value);
+                            numbered.len()
+                        }
+                    };
+                    output.push_str(&format!("${}", number));
+                } else {
+                    ordered.push(value);
+                    output.push('?');
+                }
+                i = end;
+            }
+            _ => {
+                output.push(c);
+                i += 1;
+            }
+        }
+    }
+    if let Some(index) = used.iter().position(|used| !used) {
+        return Err(format!(
+            "Named parameter :{} is not used by the statement",
+            params[index].0
+        ));
+    }
+    Ok((output, ordered))
+}
+
+/// The index just past the quoted literal, quoted identifier or comment
+/// starting at `start`, or `None` if none starts there.
+///
+/// Comments are `--` and `/* */` everywhere plus `#` on MySQL, whose string
+/// literals also take backslash escapes; Postgres adds `$tag$` quoting.
+fn skip_ignored(chars: &[char], start: usize, database_type: &DatabaseType) -> Option<usize> {
+    let next = chars.get(start + 1).copied();
+    match chars[start] {
+        '\'' | '"' => Some(skip_quoted(
+            chars,
+            start,
+            *database_type == DatabaseType::MySql,
+        )),
+        '`' => Some(skip_quoted(chars, start, false)),
+        '-' if next == Some('-') => Some(line_end(chars, start)),
+        '#' if *database_type == DatabaseType::MySql => Some(line_end(chars, start)),
+        '/' if next == Some('*') => Some(
+            chars[start + 2..]
+                .windows(2)
+                .position(|pair| pair == ['*', '/'])
+                .map_or(chars.len(), |p| start + 2 + p + 2),
+        ),
+        '$' if *database_type == DatabaseType::Postgres => skip_dollar_quoted(chars, start),
+        _ => None,
+    }
+}
+
+/// Return the index just past the quoted section starting at `start`
+fn skip_quoted(chars: &[char], start: usize, backslash_escapes: bool) -> usize {
+    let quote = chars[start];
+    let mut i = start + 1;
+    while i < chars.len() {
+        if backslash_escapes && chars[i] == '\\' {
+            i += 2;
+            continue;
+        }
+        if chars[i] == quote {
+            if chars.get(i + 1) == Some(&quote) {
+                i += 2;
+                continue;
+            }
+            return i + 1;
+        }
+        i += 1;
+    }
+    chars.len()
+}
+
+/// Return the index of the newline ending the comment starting at `start`
+fn line_end(chars: &[char], start: usize) -> usize {
+    chars[start..]
+        .iter()
+        .position(|c| *c == '\n')
+        .map_or(chars.len(), |p| start + p)
+}
+
+/// Return the index just past a Postgres `$tag$ ... $tag$` string starting at
+/// `start`, or `None` if `start` does not open one (e.g. a `$1` placeholder)
+fn skip_dollar_quoted(chars: &[char], start: usize) -> Option<usize> {
+    let mut end = start + 1;
+    if chars
+        .get(end)
+        .is_some_and(|c| c.is_ascii_alphabetic() || *c == '_')
+    {
+        while end < chars.len() && (chars[end].is_ascii_alphanumeric() || chars[end] == '_') {
+            end += 1;
+        }
+    }
+    if chars.get(end) != Some(&'$') {
+        return None;
+    }
+    let tag = &chars[start..=end];
+    let body = end + 1;
+    Some(
+        chars[body..]
+            .windows(tag.len())
+            .position(|window| window == tag)
+            .map_or(chars.len(), |p| body + p + tag.len()),
+    )
+}
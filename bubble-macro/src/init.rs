@@ -4,11 +4,22 @@ use proc_macro::TokenStream;
 pub(crate) struct BubbleConfig {
     pub(crate) port: u16,
     pub(crate) host: String,
+    /// Whether `port` or `host` was given explicitly
+    pub(crate) tcp_address_set: bool,
+    pub(crate) unix_socket: String,
     pub(crate) workers: usize,
     pub(crate) db_type: String,
     pub(crate) db_url: String,
     pub(crate) log_level: String,
+    pub(crate) log_format: String,
     pub(crate) config_file: String,
+    pub(crate) config_format: String,
+    pub(crate) tls_cert: String,
+    pub(crate) tls_key: String,
+    pub(crate) runtime: String,
+    pub(crate) runtime_flavor: String,
+    pub(crate) metrics_port: Option<u16>,
+    pub(crate) shutdown_timeout: Option<std::time::Duration>,
 }
 
 impl Default for BubbleConfig {
@@ -16,11 +27,47 @@ impl Default for BubbleConfig {
         Self {
             port: 3000,
             host: "127.0.0.1".to_string(),
+            tcp_address_set: false,
+            unix_socket: "".to_string(),
             workers: 0, // 0 means use Tokio default
             db_type: "".to_string(),
             db_url: "".to_string(),
             log_level: "info".to_string(),
-            config_file: "config.toml".to_string(),
+            log_format: "text".to_string(),
+            config_file: "".to_string(),
+            config_format: "".to_string(),
+            tls_cert: "".to_string(),
+            tls_key: "".to_string(),
+            runtime: "".to_string(),
+            runtime_flavor: "multi_thread".to_string(),
+            metrics_port: None,
+            shutdown_timeout: None,
+        }
+    }
+}
+
+impl BubbleConfig {
+    /// Parser for the config file: `config_format` when set, otherwise
+    /// detected from the file extension (TOML unless `.yaml`/`.yml`/`.json`)
+    pub(crate) fn resolved_config_format(&self) -> Result<&'static str, String> {
+        let format = if self.config_format.is_empty() {
+            std::path::Path::new(&self.config_file)
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .unwrap_or("toml")
+                .to_ascii_lowercase()
+        } else {
+            self.config_format.clone()
+        };
+        match format.as_str() {
+            "yaml" | "yml" => Ok("yaml"),
+            "json" => Ok("json"),
+            "toml" => Ok("toml"),
+            _ if self.config_format.is_empty() => Ok("toml"),
+            other => Err(format!(
+                "#[bubble] unknown config_format '{}', expected \"toml\", \"yaml\" or \"json\"",
+                other
+            )),
         }
     }
 }
@@ -40,11 +87,16 @@ pub(crate) fn parse_bubble_config(attr: TokenStream) -> BubbleConfig {
             let value = kv.next().unwrap_or("").trim().trim_matches('"');
             match key {
                 "port" => {
+                    config.tcp_address_set = true;
                     if let Ok(port) = value.parse() {
                         config.port = port;
                     }
                 }
-                "host" => config.host = value.to_string(),
+                "host" => {
+                    config.tcp_address_set = true;
+                    config.host = value.to_string();
+                }
+                "unix_socket" => config.unix_socket = value.to_string(),
                 "workers" => {
                     if let Ok(workers) = value.parse() {
                         config.workers = workers;
@@ -53,7 +105,15 @@ pub(crate) fn parse_bubble_config(attr: TokenStream) -> BubbleConfig {
                 "db_type" => config.db_type = value.to_string(),
                 "db_url" => config.db_url = value.to_string(),
                 "log_level" => config.log_level = value.to_string(),
+                "log_format" => config.log_format = value.to_lowercase(),
                 "config_file" => config.config_file = value.to_string(),
+                "config_format" => config.config_format = value.to_lowercase(),
+                "tls_cert" => config.tls_cert = value.to_string(),
+                "tls_key" => config.tls_key = value.to_string(),
+                "runtime" => config.runtime = value.replace(' ', ""),
+                "runtime_flavor" => config.runtime_flavor = value.to_lowercase(),
+                "metrics_port" => config.metrics_port = value.parse().ok(),
+                "shutdown_timeout" => config.shutdown_timeout = parse_duration(value),
                 _ => {}
             }
         }
@@ -61,51 +121,43 @@ pub(crate) fn parse_bubble_config(attr: TokenStream) -> BubbleConfig {
     config
 }
 
-/// Helper function to initialize logging
-fn init_logging(level_str: &str) {
-    let level = match level_str.to_lowercase().as_str() {
-        "error" => log::LevelFilter::Error,
-        "warn" => log::LevelFilter::Warn,
-        "info" => log::LevelFilter::Info,
-        "debug" => log::LevelFilter::Debug,
-        "trace" => log::LevelFilter::Trace,
-        _ => log::LevelFilter::Info,
+/// Parse a byte size such as `1048576`, `512KB`, `8MB` or `1GB` (1024-based)
+pub(crate) fn parse_byte_size(value: &str) -> Option<usize> {
+    let value = value.trim().to_ascii_uppercase();
+    let (number, multiplier) = if let Some(number) = value.strip_suffix("GB") {
+        (number, 1024 * 1024 * 1024)
+    } else if let Some(number) = value.strip_suffix("MB") {
+        (number, 1024 * 1024)
+    } else if let Some(number) = value.strip_suffix("KB") {
+        (number, 1024)
+    } else {
+        (value.strip_suffix('B').unwrap_or(&value), 1)
     };
-    env_logger::Builder::from_default_env()
-        .filter_level(level)
-        .format_timestamp(Some(env_logger::TimestampPrecision::Millis))
-        .format_module_path(false)
-        .init();
-    log::info!("Logging initialized with level: {}", level_str);
-}
-
-/// Helper function to initialize database connection
-async fn init_database(db_type: &str, db_url: &str) -> Result<(), String> {
-    // This would be implemented based on your database setup
-    // For now, just log the configuration
-    log::info!(
-        "Database connection configured: type={}, url={}",
-        db_type,
-        db_url
-    );
-    Ok(())
+    number
+        .trim()
+        .replace('_', "")
+        .parse::<usize>()
+        .ok()?
+        .checked_mul(multiplier)
 }
 
-/// Helper function to load configuration file
-fn load_config_file(file_path: &str) -> Result<(), String> {
-    use std::fs;
-    match fs::read_to_string(file_path) {
-        Ok(content) => {
-            log::debug!("Configuration file content:\n{}", content);
-            Ok(())
-        }
-        Err(err) => Err(format!("Failed to read config file: {}", err)),
-    }
-}
-
-/// Helper function to parse command line arguments
-fn parse_command_line_args(args: &[String]) {
-    if args.len() > 1 {
-        log::info!("Command line arguments: {:?}", &args[1..]);
-    }
+/// Parse a duration such as `500ms`, `60s`, `5m` or `1h`; a bare number is seconds
+pub(crate) fn parse_duration(value: &str) -> Option<std::time::Duration> {
+    let value = value.trim().to_ascii_lowercase();
+    let (number, millis) = if let Some(number) = value.strip_suffix("ms") {
+        (number, 1)
+    } else if let Some(number) = value.strip_suffix('h') {
+        (number, 60 * 60 * 1000)
+    } else if let Some(number) = value.strip_suffix('m') {
+        (number, 60 * 1000)
+    } else {
+        (value.strip_suffix('s').unwrap_or(&value), 1000)
+    };
+    let millis = number
+        .trim()
+        .replace('_', "")
+        .parse::<u64>()
+        .ok()?
+        .checked_mul(millis)?;
+    Some(std::time::Duration::from_millis(millis))
 }
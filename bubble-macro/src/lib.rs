@@ -1,10 +1,9 @@
 mod init;
-mod types;
 use proc_macro::TokenStream;
 use quote::quote;
 use syn::parse_macro_input;
 
-use crate::init::parse_bubble_config;
+use crate::init::{parse_bubble_config, parse_byte_size, parse_duration};
 
 // ======================================================= Root =======================================================
 /// Bubble Application Entry Point Macro
@@ -47,7 +46,9 @@ use crate::init::parse_bubble_config;
 ///
 /// ## Network Configuration
 ///
-/// - `port`: Server port number (default: `3000`)
+/// - `port`: Server port number (default: `3000`). It is logged and passed
+///   on for the application to bind; the generated `main` opens no listener
+///   on it
 ///   ```rust
 ///   #[bubble(port = 8080)]
 ///   async fn main() -> Result<()> { Ok(()) }
@@ -59,17 +60,50 @@ use crate::init::parse_bubble_config;
 ///   async fn main() -> Result<()> { Ok(()) }
 ///   ```
 ///
+/// - `unix_socket`: Bind to this Unix domain socket instead of a TCP port,
+///   e.g. behind nginx on the same host (Unix only, cannot be combined with
+///   `port` or `host`). A socket file left by a previous run is replaced,
+///   one still accepting connections stops startup with exit code 2, and the
+///   file is removed on shutdown. The listener is available to the main body
+///   as `unix_listener: std::os::unix::net::UnixListener`
+///   ```rust
+///   #[bubble(unix_socket = "/tmp/app.sock")]
+///   async fn main() -> Result<()> { Ok(()) }
+///   ```
+///
 /// ## Concurrency Configuration
 ///
-/// - `workers`: Number of Tokio worker threads (default: `0` = auto-detect)
+/// - `workers`: Number of Tokio worker threads (default: `0` = auto-detect);
+///   values above four times the available CPUs are clamped with a warning
 ///   ```rust
 ///   #[bubble(workers = 4)]  // Use 4 worker threads
 ///   async fn main() -> Result<()> { Ok(()) }
 ///   ```
 ///
+/// - `runtime_flavor`: `"multi_thread"` (default) or `"current_thread"` for a
+///   single-threaded runtime suited to CLI tools and tests; `workers` is
+///   rejected with `"current_thread"`
+///   ```rust
+///   #[bubble(runtime_flavor = "current_thread")]
+///   async fn main() -> Result<()> { Ok(()) }
+///   ```
+///
+/// - `runtime`: Function called with `&mut tokio::runtime::Builder` after the
+///   defaults are applied and before the runtime is built, for tuning such as
+///   `thread_stack_size` or `max_blocking_threads`
+///   ```rust
+///   fn configure(builder: &mut tokio::runtime::Builder) {
+///       builder.thread_stack_size(4 * 1024 * 1024).thread_name("app-worker");
+///   }
+///
+///   #[bubble(runtime = configure)]
+///   async fn main() -> Result<()> { Ok(()) }
+///   ```
+///
 /// ## Database Configuration
 ///
-/// - `db_type`: Database type (`"mysql"`, `"postgres"`, `"sqlite"`, `"redis"`)
+/// - `db_type`: Database type (`"mysql"`, `"postgres"`, `"sqlite"`, `"redis"`);
+///   detected from the `db_url` scheme when omitted
 /// - `db_url`: Database connection URL
 ///   ```rust
 ///   #[bubble(
@@ -88,11 +122,79 @@ use crate::init::parse_bubble_config;
 ///   async fn main() -> Result<()> { Ok(()) }
 ///   ```
 ///
+/// - `log_format`: Log output format (`"text"`, `"json"`) (default: `"text"`).
+///   `"json"` emits one object per line with `timestamp`, `level`, `target`
+///   and `message` fields, suitable for log aggregation
+///   ```rust
+///   #[bubble(log_format = "json")]
+///   async fn main() -> Result<()> { Ok(()) }
+///   ```
+///
+/// ## TLS Configuration
+///
+/// - `tls_cert` / `tls_key`: PEM certificate chain and private key files.
+///   This only loads the configuration: nothing is served over HTTPS, since
+///   the generated `main` opens no HTTP listener. When both are given the
+///   files are read at startup into a `rustls::ServerConfig`, available to
+///   the main body as `tls_config: Arc<rustls::ServerConfig>` for the server
+///   `main` starts itself; a missing or malformed file aborts with exit
+///   code 5
+///   ```rust
+///   #[bubble(port = 8443, tls_cert = "cert.pem", tls_key = "key.pem")]
+///   async fn main() -> Result<()> { Ok(()) }
+///   ```
+///
+/// ## Metrics
+///
+/// - `metrics_port`: Serve Prometheus metrics at `/metrics` on this port of
+///   `host` (default: unset, no listener). Exports uptime and Tokio runtime
+///   gauges (worker threads, alive tasks) only; there are no HTTP request
+///   counters, as the generated `main` serves no HTTP itself (see
+///   `bubble::runtime::serve_metrics`)
+///   ```rust
+///   #[bubble(metrics_port = 9000)]
+///   async fn main() -> Result<()> { Ok(()) }
+///   ```
+///
+/// ## Shutdown
+///
+/// - `shutdown_timeout`: How long to wait after Ctrl+C for `main` to return
+///   and the `on_shutdown` hooks to finish, e.g. `"30s"` or `"500ms"`
+///   (default: unset, `main` is dropped at once and only the hooks run).
+///   `main` should await `shutdown_signal()` to stop accepting work; when
+///   the timeout elapses the process exits with a warning. The time taken to
+///   drain is logged
+///   ```rust
+///   #[bubble(shutdown_timeout = "30s")]
+///   async fn main() -> Result<()> {
+///       on_shutdown(|| async { log::info!("flushing") });
+///       shutdown_signal().await;
+///       Ok(())
+///   }
+///   ```
+///
 /// ## Configuration Files
 ///
-/// - `config_file`: Path to configuration file (default: `"config.toml"`)
+/// - `config_file`: Path to a configuration file (default: none, nothing is
+///   read). The parsed file is available to the body of `main` as
+///   `app_config: Option<serde_json::Value>`. A missing file is skipped and an
+///   unreadable one (e.g. permission denied) logs a warning; both leave
+///   `app_config` as `None`. A malformed file stops startup with exit code 6,
+///   reporting the line and column of the parse error.
+/// - `config_format`: `"toml"`, `"yaml"` or `"json"` (default: detected from
+///   the file extension, `.yaml`/`.yml` and `.json`, otherwise TOML). Every
+///   format parses to the same `app_config` value.
 ///   ```rust
 ///   #[bubble(config_file = "app.toml")]
+///   async fn main() -> Result<()> {
+///       let name = app_config
+///           .as_ref()
+///           .and_then(|config| config.get("name"))
+///           .and_then(|name| name.as_str());
+///       Ok(())
+///   }
+///
+///   #[bubble(config_file = "config.yaml")]
 ///   async fn main() -> Result<()> { Ok(()) }
 ///   ```
 ///
@@ -132,7 +234,7 @@ use crate::init::parse_bubble_config;
 ///
 /// 2. **Infrastructure Setup**:
 ///    - Logging system is initialized with the specified level
-///    - Configuration file is loaded (if exists)
+///    - Configuration file is loaded (if `config_file` is set and it exists)
 ///    - Command-line arguments are parsed
 ///    - Database connection is established (if configured)
 ///
@@ -145,7 +247,10 @@ use crate::init::parse_bubble_config;
 ///    - Runs concurrently with signal monitoring
 ///
 /// 5. **Shutdown**:
-///    - On Ctrl+C: graceful shutdown with completion message
+///    - On Ctrl+C: `shutdown_signal()` resolves, then `main` gets up to
+///      `shutdown_timeout` to return and the `on_shutdown` hooks run
+///    - With a database configured, registered connections are closed
+///      (`bubble::db::close_databases`) once in-flight queries finish
 ///    - On error: error logging with non-zero exit code
 ///    - On success: clean exit with zero exit code
 ///
@@ -188,19 +293,20 @@ use crate::init::parse_bubble_config;
 ///
 /// # Configuration File Format
 ///
-/// When using a configuration file (default: `config.toml`), it should follow
-/// this structure:
+/// When `config_file` is set, the file may hold any structure; the body of
+/// `main` reads it through `app_config`. For example:
 ///
 /// ```toml
 /// # config.toml
-/// port = 8080
-/// host = "0.0.0.0"
-/// workers = 4
-/// db_type = "postgres"
-/// db_url = "postgres://localhost:5432/appdb"
-/// log_level = "info"
+/// name = "inventory"
+///
+/// [cache]
+/// ttl_seconds = 300
 /// ```
 ///
+/// Attribute values such as `port` and `db_url` are fixed at compile time and
+/// are not overridden by the file.
+///
 /// # Integration with Other Macros
 ///
 /// The `#[bubble]` macro can be combined with other macros from this crate:
@@ -241,7 +347,8 @@ use crate::init::parse_bubble_config;
 /// - **Main Function Only**: Can only be applied to the `main` function
 /// - **Async Required**: The `main` function must be `async`
 /// - **Return Type**: Must return `Result<()>` or compatible error type
-/// - **Dependencies**: Requires `tokio`, `env_logger`, and `log` crates
+/// - **Dependencies**: Requires the `bubble`, `tokio` and `log` crates; logging
+///   and the runtime are set up by `bubble::runtime`
 /// - **Platform**: Works on all platforms supported by Tokio
 ///
 /// # Migration from Manual Setup
@@ -312,9 +419,14 @@ use crate::init::parse_bubble_config;
 ///   limitations or invalid worker thread count
 /// - **Database connection errors**: Verify database is running and credentials
 ///   are correct
-/// - **Permission denied**: Check port permissions (ports < 1024 require root)
-/// - **Missing dependencies**: Ensure `tokio`, `env_logger`, `log` are in
-///   `Cargo.toml`
+/// - **Bind failures**: `port` and `host` are not bound by the generated
+///   `main`; the listeners it does open (`metrics_port`, `unix_socket`) stop
+///   startup with exit code 2 when the address is already in use, 3 when
+///   permission is denied (ports < 1024 require root) and 4 when the host
+///   address is not available (see `bubble::runtime::bind_exit_code`)
+/// - **Missing dependencies**: Ensure `bubble`, `tokio`, `log` are in
+///   `Cargo.toml`, plus `rustls` and `rustls-pemfile` when TLS is enabled;
+///   configuration files are parsed by `bubble::runtime::load_config_file`
 ///
 #[proc_macro_attribute]
 pub fn bubble(attr: TokenStream, item: TokenStream) -> TokenStream {
@@ -339,17 +451,242 @@ pub fn bubble(attr: TokenStream, item: TokenStream) -> TokenStream {
         .into();
     }
     let vis = &input_fn.vis;
-    let inputs = &input_fn.sig.inputs;
     let output = &input_fn.sig.output;
     let block = &input_fn.block;
     let attrs = &input_fn.attrs;
     let port = config.port;
     let host = &config.host;
     let workers = config.workers;
-    let db_type = &config.db_type;
+    // Resolve the database type from `db_type`, or from the `db_url` scheme when omitted
+    let db_type = if config.db_type.is_empty() && config.db_url.is_empty() {
+        Ok(String::new())
+    } else if config.db_type.is_empty() {
+        bubble_db::DatabaseType::from_scheme(&config.db_url).map(|t| t.as_str().to_string())
+    } else {
+        config
+            .db_type
+            .parse::<bubble_db::DatabaseType>()
+            .map(|t| t.as_str().to_string())
+    };
+    let db_type = match db_type {
+        Ok(db_type) => db_type,
+        Err(err) => {
+            return syn::Error::new(proc_macro2::Span::call_site(), err)
+                .to_compile_error()
+                .into();
+        }
+    };
     let db_url = &config.db_url;
+    // Without a timeout `main` is dropped at once and only the hooks run
+    let drain = match config.shutdown_timeout {
+        None => quote! {
+            ::bubble::web::shutdown::run_shutdown_hooks().await;
+        },
+        Some(timeout) => {
+            let timeout_ms = timeout.as_millis() as u64;
+            quote! {
+                let shutdown_timeout = std::time::Duration::from_millis(#timeout_ms);
+                let drain_started = std::time::Instant::now();
+                let drained = tokio::time::timeout(shutdown_timeout, async {
+                    if let Err(err) = (&mut main).await {
+                        log::error!("Application failed while shutting down: {}", err);
+                    }
+                    ::bubble::web::shutdown::run_shutdown_hooks().await;
+                })
+                .await;
+                match drained {
+                    Ok(()) => log::info!("Drained in {:?}", drain_started.elapsed()),
+                    Err(_) => log::warn!(
+                        "Shutdown timed out after {:?}, forcing exit with work still in flight",
+                        shutdown_timeout
+                    ),
+                }
+            }
+        }
+    };
+    // Registered connections are closed on the way out, after in-flight
+    // queries finish, so the server sees a clean disconnect
+    let close_databases = if db_type.is_empty() || db_url.is_empty() {
+        quote! {}
+    } else {
+        quote! {
+            log::info!("Closing database connections");
+            if let Err(err) = ::bubble::db::close_databases().await {
+                log::warn!("Failed to close database connections: {}", err);
+            }
+        }
+    };
     let log_level = &config.log_level;
+    let log_format = &config.log_format;
     let config_file = &config.config_file;
+    let load_config = if config_file.is_empty() {
+        quote! {}
+    } else {
+        let config_format = match config.resolved_config_format() {
+            Ok(format) => format,
+            Err(err) => {
+                return syn::Error::new(proc_macro2::Span::call_site(), err)
+                    .to_compile_error()
+                    .into();
+            }
+        };
+        quote! {
+            #[allow(unused_variables)]
+            let app_config = match ::bubble::runtime::load_config_file(#config_file, #config_format) {
+                Ok(Some(value)) => {
+                    log::info!("Loaded configuration from {}", #config_file);
+                    Some(value)
+                }
+                Ok(None) => None,
+                Err(err) => {
+                    log::error!("{}", err);
+                    eprintln!("error: {}", err);
+                    std::process::exit(6);
+                }
+            };
+        }
+    };
+    if !config.unix_socket.is_empty() && config.tcp_address_set {
+        return syn::Error::new(
+            proc_macro2::Span::call_site(),
+            "#[bubble] unix_socket cannot be combined with port or host",
+        )
+        .to_compile_error()
+        .into();
+    }
+    let unix_socket = &config.unix_socket;
+    let (bind_address, remove_unix_socket) = if unix_socket.is_empty() {
+        (
+            quote! {
+                log::info!("Configuration: port={}, host={}, workers={}",
+                    #port, #host, #workers);
+            },
+            quote! {},
+        )
+    } else {
+        (
+            quote! {
+                #[cfg(not(unix))]
+                compile_error!("#[bubble] unix_socket is only supported on Unix platforms");
+                log::info!("Configuration: unix_socket={}, workers={}", #unix_socket, #workers);
+                #[cfg(unix)]
+                #[allow(unused_variables)]
+                let unix_listener = bind_unix_socket(#unix_socket);
+            },
+            quote! {
+                #[cfg(unix)]
+                match std::fs::remove_file(#unix_socket) {
+                    Ok(()) => log::debug!("Removed unix socket {}", #unix_socket),
+                    Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+                    Err(err) => log::warn!("Failed to remove unix socket {}: {}", #unix_socket, err),
+                }
+            },
+        )
+    };
+    if config.tls_cert.is_empty() != config.tls_key.is_empty() {
+        return syn::Error::new(
+            proc_macro2::Span::call_site(),
+            "#[bubble] requires both tls_cert and tls_key to enable TLS",
+        )
+        .to_compile_error()
+        .into();
+    }
+    let current_thread = match config.runtime_flavor.as_str() {
+        "multi_thread" => false,
+        "current_thread" if workers > 0 => {
+            return syn::Error::new(
+                proc_macro2::Span::call_site(),
+                "#[bubble] `workers` cannot be used with runtime_flavor = \"current_thread\"",
+            )
+            .to_compile_error()
+            .into();
+        }
+        "current_thread" => true,
+        other => {
+            return syn::Error::new(
+                proc_macro2::Span::call_site(),
+                format!(
+                    "#[bubble] unknown runtime_flavor '{}', expected \"multi_thread\" or \"current_thread\"",
+                    other
+                ),
+            )
+            .to_compile_error()
+            .into();
+        }
+    };
+    let configure_runtime = if config.runtime.is_empty() {
+        quote! {}
+    } else {
+        match syn::parse_str::<syn::Path>(&config.runtime) {
+            Ok(path) => quote! { #path(&mut rt_builder); },
+            Err(_) => {
+                return syn::Error::new(
+                    proc_macro2::Span::call_site(),
+                    format!(
+                        "#[bubble] runtime must be a path to a function, got '{}'",
+                        config.runtime
+                    ),
+                )
+                .to_compile_error()
+                .into();
+            }
+        }
+    };
+    let metrics_server = match config.metrics_port {
+        None => quote! {},
+        Some(metrics_port) => quote! {
+            let metrics_listener = match tokio::net::TcpListener::bind((#host, #metrics_port)).await {
+                Ok(listener) => listener,
+                Err(err) => ::bubble::runtime::exit_on_bind_error(#host, #metrics_port, &err),
+            };
+            log::info!("Metrics endpoint: http://{}:{}/metrics", #host, #metrics_port);
+            tokio::spawn(::bubble::runtime::serve_metrics(metrics_listener));
+        },
+    };
+    let tls_setup = if config.tls_cert.is_empty() {
+        quote! {}
+    } else {
+        let tls_cert = &config.tls_cert;
+        let tls_key = &config.tls_key;
+        quote! {
+            fn load_tls_config(
+                cert_path: &str,
+                key_path: &str,
+            ) -> Result<rustls::ServerConfig, String> {
+                let cert_file = std::fs::File::open(cert_path).map_err(|err| {
+                    format!("Failed to open TLS certificate {}: {}", cert_path, err)
+                })?;
+                let certs = rustls_pemfile::certs(&mut std::io::BufReader::new(cert_file))
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|err| format!("Malformed TLS certificate {}: {}", cert_path, err))?;
+                if certs.is_empty() {
+                    return Err(format!("No certificates found in {}", cert_path));
+                }
+                let key_file = std::fs::File::open(key_path).map_err(|err| {
+                    format!("Failed to open TLS private key {}: {}", key_path, err)
+                })?;
+                let key = rustls_pemfile::private_key(&mut std::io::BufReader::new(key_file))
+                    .map_err(|err| format!("Malformed TLS private key {}: {}", key_path, err))?
+                    .ok_or_else(|| format!("No private key found in {}", key_path))?;
+                rustls::ServerConfig::builder()
+                    .with_no_client_auth()
+                    .with_single_cert(certs, key)
+                    .map_err(|err| format!("Invalid TLS certificate/key pair: {}", err))
+            }
+            #[allow(unused_variables)]
+            let tls_config = match load_tls_config(#tls_cert, #tls_key) {
+                Ok(tls_config) => {
+                    log::info!("Loaded TLS configuration from {}", #tls_cert);
+                    std::sync::Arc::new(tls_config)
+                }
+                Err(err) => {
+                    log::error!("{}", err);
+                    eprintln!("error: {}", err);
+                    std::process::exit(5);
+                }
+            };
+        }
+    };
     // Generate the expanded code with full integration
     let expanded = quote! {
         #(#attrs)*
@@ -363,23 +700,6 @@ pub fn bubble(attr: TokenStream, item: TokenStream) -> TokenStream {
         #vis fn main() #output {
             // Create the actual main function that will be called by tokio
             async fn inner_main() #output {
-                // Helper function to initialize logging
-                fn init_logging(level_str: &str) {
-                    let level = match level_str.to_lowercase().as_str() {
-                        "error" => log::LevelFilter::Error,
-                        "warn" => log::LevelFilter::Warn,
-                        "info" => log::LevelFilter::Info,
-                        "debug" => log::LevelFilter::Debug,
-                        "trace" => log::LevelFilter::Trace,
-                        _ => log::LevelFilter::Info,
-                    };
-                    env_logger::Builder::from_default_env()
-                        .filter_level(level)
-                        .format_timestamp(Some(env_logger::TimestampPrecision::Millis))
-                        .format_module_path(false)
-                        .init();
-                    log::info!("Logging initialized with level: {}", level_str);
-                }
                 async fn init_database(db_type: &str, db_url: &str) -> Result<(), String> {
                     log::info!(
                         "Database connection configured: type={}, url={}",
@@ -388,14 +708,37 @@ pub fn bubble(attr: TokenStream, item: TokenStream) -> TokenStream {
                     );
                     Ok(())
                 }
-                fn load_config_file(file_path: &str) -> Result<(), String> {
-                    use std::fs;
-                    match fs::read_to_string(file_path) {
-                        Ok(content) => {
-                            log::debug!("Configuration file content:\n{}", content);
-                            Ok(())
+                // A socket file whose server is gone is replaced; a live one is
+                // left alone
+                #[cfg(unix)]
+                #[allow(dead_code)]
+                fn bind_unix_socket(path: &str) -> std::os::unix::net::UnixListener {
+                    let socket = std::path::Path::new(path);
+                    let fail = |message: String, code: i32| -> ! {
+                        log::error!("{}", message);
+                        eprintln!("error: {}", message);
+                        std::process::exit(code);
+                    };
+                    if socket.exists() {
+                        if std::os::unix::net::UnixStream::connect(socket).is_ok() {
+                            fail(
+                                format!("Unix socket {} is already in use by another process", path),
+                                2,
+                            );
+                        }
+                        if let Err(err) = std::fs::remove_file(socket) {
+                            fail(format!("Failed to remove stale unix socket {}: {}", path, err), 1);
                         }
-                        Err(err) => Err(format!("Failed to read config file: {}", err)),
+                    }
+                    match std::os::unix::net::UnixListener::bind(socket) {
+                        Ok(listener) => {
+                            log::info!("Listening on unix socket {}", path);
+                            listener
+                        }
+                        Err(err) => fail(
+                            format!("Failed to bind unix socket {}: {}", path, err),
+                            ::bubble::runtime::bind_exit_code(&err),
+                        ),
                     }
                 }
                 fn parse_command_line_args(args: &[String]) {
@@ -403,40 +746,27 @@ pub fn bubble(attr: TokenStream, item: TokenStream) -> TokenStream {
                         log::info!("Command line arguments: {:?}", &args[1..]);
                     }
                 }
-                init_logging(#log_level);
                 log::info!("Starting Bubble Application");
-                log::info!("Configuration: port={}, host={}, workers={}",
-                    #port, #host, #workers);
+                #bind_address
+                #metrics_server
+                #tls_setup
                 if !#db_type.is_empty() && !#db_url.is_empty() {
                     log::info!("Initializing {} database: {}", #db_type, #db_url);
                     init_database(#db_type, #db_url).await
                         .expect("Failed to initialize database");
                 }
-                if std::path::Path::new(#config_file).exists() {
-                    log::info!("Loading configuration from {}", #config_file);
-                    load_config_file(#config_file)
-                        .expect("Failed to load configuration file");
-                }
+                #load_config
                 let args: Vec<String> = std::env::args().collect();
                 parse_command_line_args(&args);
                 log::info!("Executing user application");
                 #block
             }
-            let mut rt_builder = tokio::runtime::Builder::new_multi_thread()
-                .enable_all()
-                .on_thread_start(|| {
-                    log::debug!("Tokio worker thread started");
-                })
-                .on_thread_stop(|| {
-                    log::debug!("Tokio worker thread stopped");
-                });
-            let rt = if #workers > 0 {
-                rt_builder.worker_threads(#workers)
-            } else {
-                &mut rt_builder
-            }
-            .build()
-            .expect("Failed to create Tokio runtime");
+            ::bubble::runtime::init_logging(#log_level, #log_format);
+            let mut rt_builder = ::bubble::runtime::runtime_builder(#current_thread, #workers);
+            #configure_runtime
+            let rt = rt_builder
+                .build()
+                .expect("Failed to create Tokio runtime");
             let (shutdown_tx, mut shutdown_rx) = tokio::sync::oneshot::channel::<()>();
             rt.spawn(async move {
                 match tokio::signal::ctrl_c().await {
@@ -450,15 +780,22 @@ pub fn bubble(attr: TokenStream, item: TokenStream) -> TokenStream {
                 }
             });
             let result = rt.block_on(async {
-                tokio::select! {
+                let main = inner_main();
+                tokio::pin!(main);
+                let result = tokio::select! {
                     _ = &mut shutdown_rx => {
                         log::info!("Shutting down gracefully...");
+                        ::bubble::web::shutdown::begin_shutdown();
+                        #drain
                         Err("Application interrupted by user".into())
                     }
-                    res = inner_main() => {
+                    res = &mut main => {
                         res
                     }
-                }
+                };
+                #close_databases
+                #remove_unix_socket
+                result
             });
             match result {
                 Ok(_) => {
@@ -575,6 +912,71 @@ pub fn options(attr: TokenStream, item: TokenStream) -> TokenStream {
     generate_route_macro("OPTIONS", attr, item)
 }
 
+/// WebSocket endpoint macro
+///
+/// Marks an async method of a `#[controller]` impl block as a WebSocket
+/// endpoint. `routes()` registers it as a GET route with
+/// `RouteKind::WebSocket`, kept apart from plain HTTP routes, whose
+/// `websocket` handler receives the upgraded connection.
+///
+/// # Examples
+/// ```
+/// #[controller]
+/// impl ChatController {
+///     #[websocket("/ws/chat")]
+///     async fn chat(&self, mut socket: WebSocket) {
+///         while let Some(message) = socket.recv().await {
+///             let _ = socket.send(message).await;
+///         }
+///     }
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn websocket(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let path = if attr.is_empty() {
+        "/".to_string()
+    } else {
+        attr.to_string()
+            .trim_matches(|c| c == '"' || c == ' ')
+            .to_string()
+    };
+    let input_fn = parse_macro_input!(item as syn::ItemFn);
+    let socket_params = input_fn
+        .sig
+        .inputs
+        .iter()
+        .filter(|arg| matches!(arg, syn::FnArg::Typed(_)))
+        .count();
+    if socket_params != 1 {
+        return syn::Error::new_spanned(
+            &input_fn.sig,
+            "A #[websocket] handler must take exactly one WebSocket parameter",
+        )
+        .to_compile_error()
+        .into();
+    }
+    if input_fn.sig.asyncness.is_none() {
+        return syn::Error::new_spanned(&input_fn.sig, "A #[websocket] handler must be async")
+            .to_compile_error()
+            .into();
+    }
+    let fn_name = &input_fn.sig.ident;
+    let vis = &input_fn.vis;
+    let inputs = &input_fn.sig.inputs;
+    let output = &input_fn.sig.output;
+    let asyncness = &input_fn.sig.asyncness;
+    let block = &input_fn.block;
+    let attrs = &input_fn.attrs;
+
+    let expanded = quote! {
+        #(#attrs)*
+        #[doc = concat!("WEBSOCKET Handler - Path: ", #path)]
+        #vis #asyncness fn #fn_name(#inputs) #output #block
+    };
+
+    expanded.into()
+}
+
 /// Generic route macro that can specify any HTTP method
 ///
 /// # Examples
@@ -591,46 +993,7 @@ pub fn options(attr: TokenStream, item: TokenStream) -> TokenStream {
 /// ```
 #[proc_macro_attribute]
 pub fn route(attr: TokenStream, item: TokenStream) -> TokenStream {
-    let attr_str = attr.to_string();
-    let parts: Vec<&str> = attr_str.split(',').map(|s| s.trim()).collect();
-
-    let (method, path) = if parts.len() >= 2 {
-        let method_part = parts[0];
-        let path_part = parts[1];
-
-        // Extract method
-        let method = if method_part.contains("method") {
-            method_part
-                .split('=')
-                .nth(1)
-                .unwrap_or("GET")
-                .trim_matches(|c| c == '"' || c == ' ')
-                .to_string()
-        } else {
-            method_part.to_string()
-        };
-
-        // Extract path
-        let path = if path_part.contains("path") {
-            path_part
-                .split('=')
-                .nth(1)
-                .unwrap_or("/")
-                .trim_matches(|c| c == '"' || c == ' ')
-                .to_string()
-        } else {
-            path_part.trim_matches('"').to_string()
-        };
-
-        (method, path)
-    } else if parts.len() == 1 {
-        // If there's only one parameter, assume it's the path and method defaults to GET
-        let path = parts[0].trim_matches('"').to_string();
-        ("GET".to_string(), path)
-    } else {
-        ("GET".to_string(), "/".to_string())
-    };
-
+    let (method, path) = parse_route_args(&attr.to_string());
     generate_custom_route_macro(&method, &path, item)
 }
 
@@ -638,7 +1001,38 @@ pub fn route(attr: TokenStream, item: TokenStream) -> TokenStream {
 
 /// Controller macro
 ///
-/// Marks a struct as a controller with a base path
+/// Marks a struct as a controller with a base path. Applied to the
+/// controller's `impl` block, it also generates `routes(self) -> Vec<Route>`
+/// collecting the methods annotated with HTTP method macros or `#[route]`;
+/// a `#[route]` method name is mapped with `HttpMethod::from`, so `TRACE`,
+/// `CONNECT` and custom verbs are routed like the others. Each route's
+/// path is the base path joined with the method path, and its dispatch
+/// closure calls the method on the controller instance: `Request`
+/// parameters receive the request, `#[request_body]` parameters are
+/// deserialized from the body according to its `Content-Type` (JSON or
+/// `application/x-www-form-urlencoded`), `#[form]` parameters always from a
+/// URL-encoded form, and other parameters are parsed from the path parameter
+/// (or query parameter) of the same name. Arguments of the extractor types
+/// `Path<T>` (the route's single path parameter), `Query<T>` and `Json<T>`
+/// are built with `FromRequest::from_request`. Bodies that fail to deserialize
+/// answer 400; adding `#[validate]` to a body parameter runs its
+/// `#[derive(Validate)]` rules, answering 422 on failure. Return values must
+/// implement `IntoResponse`: a `String` answers 200 text, a `serde_json::Value`
+/// 200 JSON, `(201, body)` sets the status, and an `Err` is rendered by the
+/// error handlers. A method's
+/// `#[body_limit("16MB")]` sets the route's `max_body_size`, which
+/// `BodyLimits::for_route` applies in place of the server-wide limit, e.g.
+/// for upload endpoints.
+///
+/// A panicking handler answers 500 (`INTERNAL_ERROR`) and the panic is
+/// logged with the request ID; call `set_catch_panics(false)` to let panics
+/// unwind instead, e.g. to fail fast in development.
+///
+/// `#[cache(ttl = "60s")]` on a GET, HEAD or OPTIONS route caches its
+/// successful responses, keyed by method, path and query, and serves them
+/// with `metadata.cached` set until the TTL expires. The TTL accepts `ms`,
+/// `s`, `m` and `h` suffixes; entries live in an in-memory LRU unless
+/// another store is installed with `set_response_cache`.
 ///
 /// # Examples
 /// ```
@@ -646,59 +1040,724 @@ pub fn route(attr: TokenStream, item: TokenStream) -> TokenStream {
 /// struct UserController {
 ///     service_name: String,
 /// }
+///
+/// #[controller]
+/// impl UserController {
+///     #[get("/:id")]
+///     fn get_user(&self, id: i64) -> String {
+///         format!("User ID: {}", id)
+///     }
+///
+///     #[post("/login")]
+///     fn login(&self, #[form] credentials: LoginForm) -> String {
+///         format!("Welcome, {}", credentials.username)
+///     }
+///
+///     #[put("/:id")]
+///     fn rename(&self, Path(id): Path<i64>, Json(body): Json<Rename>) -> String {
+///         format!("User {} is now {}", id, body.name)
+///     }
+/// }
+///
+/// app.mount(UserController::new(...).routes());
 /// ```
 #[proc_macro_attribute]
 pub fn controller(attr: TokenStream, item: TokenStream) -> TokenStream {
     let base_path = if attr.is_empty() {
-        "/".to_string()
+        None
     } else {
-        attr.to_string().trim_matches('"').to_string()
+        Some(attr.to_string().trim_matches('"').to_string())
     };
 
+    if let Ok(item_impl) = syn::parse::<syn::ItemImpl>(item.clone()) {
+        return controller_routes(base_path, item_impl);
+    }
+
+    let base_path = base_path.unwrap_or_else(|| "/".to_string());
     let input = parse_macro_input!(item as syn::ItemStruct);
     let struct_name = &input.ident;
     let fields = &input.fields;
     let attrs = &input.attrs;
     let vis = &input.vis;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
 
     let expanded = quote! {
         #(#attrs)*
         #[doc = concat!("Controller - Base Path: ", #base_path)]
         #vis struct #struct_name #fields
+
+        impl #impl_generics #struct_name #ty_generics #where_clause {
+            /// Base path of the controller's routes
+            pub const BASE_PATH: &'static str = #base_path;
+        }
     };
 
     expanded.into()
 }
 
-// =============================== Helper Functions ===============================
+/// HTTP method macros recognised by `#[controller]` on an `impl` block
+const CONTROLLER_ROUTE_METHODS: &[(&str, &str)] = &[
+    ("get", "GET"),
+    ("post", "POST"),
+    ("put", "PUT"),
+    ("delete", "DELETE"),
+    ("patch", "PATCH"),
+    ("head", "HEAD"),
+    ("options", "OPTIONS"),
+];
 
-/// Generate standard HTTP method macros
-fn generate_route_macro(method: &str, attr: TokenStream, item: TokenStream) -> TokenStream {
-    let path = if attr.is_empty() {
-        "/".to_string()
+/// Route entry for a `#[websocket]` method of a controller `impl` block
+fn controller_websocket_route(
+    method: &syn::ImplItemFn,
+    base: &proc_macro2::TokenStream,
+    path: &str,
+) -> proc_macro2::TokenStream {
+    let fn_name = &method.sig.ident;
+    let handler_name = fn_name.to_string();
+    let has_receiver = matches!(method.sig.inputs.first(), Some(syn::FnArg::Receiver(_)));
+    let call = if has_receiver {
+        quote! { controller.#fn_name(socket).await }
     } else {
-        attr.to_string()
-            .trim_matches(|c| c == '"' || c == ' ')
-            .to_string()
+        quote! { Self::#fn_name(socket).await }
     };
+    quote! {{
+        let controller = controller.clone();
+        let base = #base.trim_end_matches('/');
+        let path = #path.trim_start_matches('/');
+        ::bubble::web::Route {
+            method: ::bubble::web::HttpMethod::GET,
+            path: match (base.is_empty(), path.is_empty()) {
+                (true, true) => "/".to_string(),
+                (false, true) => base.to_string(),
+                _ => format!("{}/{}", base, path),
+            },
+            handler: #handler_name.to_string(),
+            middleware: Vec::new(),
+            kind: ::bubble::web::RouteKind::WebSocket,
+            dispatch: None,
+            websocket: Some(std::sync::Arc::new(move |socket: ::bubble::web::WebSocket| {
+                let controller = controller.clone();
+                Box::pin(async move {
+                    let _ = &controller;
+                    #call
+                })
+            })),
+            max_body_size: None,
+        }
+    }}
+}
 
-    let input_fn = parse_macro_input!(item as syn::ItemFn);
-    let fn_name = &input_fn.sig.ident;
-    let vis = &input_fn.vis;
-    let inputs = &input_fn.sig.inputs;
-    let output = &input_fn.sig.output;
-    let block = &input_fn.block;
-    let attrs = &input_fn.attrs;
+/// Generate `routes()` for a controller `impl` block
+fn controller_routes(base_path: Option<String>, mut item_impl: syn::ItemImpl) -> TokenStream {
+    let base = match base_path {
+        Some(path) => quote! { #path },
+        None => quote! { Self::BASE_PATH },
+    };
+    let mut routes = Vec::new();
+    for impl_item in &mut item_impl.items {
+        let syn::ImplItem::Fn(method) = impl_item else {
+            continue;
+        };
+        if let Some(attr) = method
+            .attrs
+            .iter()
+            .find(|attr| attr.path().is_ident("websocket"))
+        {
+            let path = match &attr.meta {
+                syn::Meta::List(list) => list.tokens.to_string(),
+                _ => String::new(),
+            };
+            let path = path.trim_matches(|c| c == '"' || c == ' ').to_string();
+            let path = if path.is_empty() {
+                "/".to_string()
+            } else {
+                path
+            };
+            routes.push(controller_websocket_route(method, &base, &path));
+            continue;
+        }
+        let route = method.attrs.iter().find_map(|attr| {
+            let name = attr.path().get_ident()?.to_string();
+            let args = match &attr.meta {
+                syn::Meta::List(list) => list.tokens.to_string(),
+                _ => String::new(),
+            };
+            if name == "route" {
+                let (http_method, path) = parse_route_args(&args);
+                let method_name = http_method.to_ascii_uppercase();
+                return Some((
+                    quote! { ::bubble::web::HttpMethod::from(#http_method) },
+                    path,
+                    method_name,
+                ));
+            }
+            let (_, method_name) = CONTROLLER_ROUTE_METHODS
+                .iter()
+                .find(|(macro_name, _)| *macro_name == name)?;
+            let http_method = syn::Ident::new(method_name, proc_macro2::Span::call_site());
+            let path = args.trim_matches(|c| c == '"' || c == ' ').to_string();
+            let path = if path.is_empty() {
+                "/".to_string()
+            } else {
+                path
+            };
+            Some((
+                quote! { ::bubble::web::HttpMethod::#http_method },
+                path,
+                method_name.to_string(),
+            ))
+        });
+        let Some((http_method, path, method_name)) = route else {
+            continue;
+        };
+        if method.sig.asyncness.is_some() {
+            return syn::Error::new_spanned(
+                &method.sig,
+                "#[controller] routes do not support async handlers",
+            )
+            .to_compile_error()
+            .into();
+        }
+
+        let mut max_body_size = None;
+        let mut body_limit_attr = None;
+        method.attrs.retain(|attr| {
+            if !attr.path().is_ident("body_limit") {
+                return true;
+            }
+            let value = match &attr.meta {
+                syn::Meta::List(list) => list.tokens.to_string(),
+                _ => String::new(),
+            };
+            max_body_size = parse_byte_size(value.trim_matches('"'));
+            body_limit_attr = Some(attr.clone());
+            false
+        });
+        if let (Some(attr), None) = (&body_limit_attr, max_body_size) {
+            return syn::Error::new_spanned(
+                attr,
+                "#[body_limit] expects a size such as 16777216 or \"16MB\"",
+            )
+            .to_compile_error()
+            .into();
+        }
+        let max_body_size = match max_body_size {
+            Some(size) => quote! { Some(#size) },
+            None => quote! { None },
+        };
+
+        let mut cache_ttl = None;
+        let mut cache_attr = None;
+        method.attrs.retain(|attr| {
+            if !attr.path().is_ident("cache") {
+                return true;
+            }
+            let value = match &attr.meta {
+                syn::Meta::List(list) => list.tokens.to_string(),
+                _ => String::new(),
+            };
+            cache_ttl = value
+                .trim()
+                .strip_prefix("ttl")
+                .and_then(|rest| rest.trim().strip_prefix('='))
+                .and_then(|ttl| parse_duration(ttl.trim().trim_matches('"')));
+            cache_attr = Some(attr.clone());
+            false
+        });
+        if let Some(attr) = &cache_attr {
+            if cache_ttl.is_none() {
+                return syn::Error::new_spanned(
+                    attr,
+                    "#[cache] expects a ttl such as #[cache(ttl = \"60s\")]",
+                )
+                .to_compile_error()
+                .into();
+            }
+            if !matches!(method_name.as_str(), "GET" | "HEAD" | "OPTIONS") {
+                return syn::Error::new_spanned(
+                    attr,
+                    format!(
+                        "#[cache] is only supported on GET, HEAD and OPTIONS routes, not {}",
+                        method_name
+                    ),
+                )
+                .to_compile_error()
+                .into();
+            }
+        }
+
+        let fn_name = method.sig.ident.clone();
+        let handler_name = fn_name.to_string();
+        let mut args = Vec::new();
+        let mut has_receiver = false;
+        for input in &mut method.sig.inputs {
+            let pat_type = match input {
+                syn::FnArg::Receiver(_) => {
+                    has_receiver = true;
+                    continue;
+                }
+                syn::FnArg::Typed(pat_type) => pat_type,
+            };
+            let (is_ref, ty) = match pat_type.ty.as_ref() {
+                syn::Type::Reference(reference) => (true, reference.elem.as_ref()),
+                other => (false, other),
+            };
+            if let Some(mut body) = take_body_binding(&mut pat_type.attrs) {
+                let validate = pat_type.attrs.len();
+                pat_type
+                    .attrs
+                    .retain(|attr| !attr.path().is_ident("validate"));
+                if pat_type.attrs.len() != validate {
+                    body = quote! {{
+                        let body = #body;
+                        ::bubble::web::Validate::validate(&body)?;
+                        body
+                    }};
+                }
+                args.push(if is_ref {
+                    quote! { &#body }
+                } else {
+                    body
+                });
+                continue;
+            }
+            let is_request = matches!(
+                ty,
+                syn::Type::Path(type_path)
+                    if type_path.path.segments.last().is_some_and(|s| s.ident == "Request")
+            );
+            if is_request {
+                args.push(if is_ref {
+                    quote! { req }
+                } else {
+                    quote! { req.clone() }
+                });
+                continue;
+            }
+            let is_extractor = matches!(
+                ty,
+                syn::Type::Path(type_path)
+                    if type_path.path.segments.last().is_some_and(|s| {
+                        s.ident == "Path" || s.ident == "Query" || s.ident == "Json"
+                    })
+            );
+            if is_extractor {
+                let extract = quote! { <#ty as ::bubble::web::FromRequest>::from_request(req)? };
+                args.push(if is_ref {
+                    quote! { &#extract }
+                } else {
+                    extract
+                });
+                continue;
+            }
+            let syn::Pat::Ident(pat_ident) = pat_type.pat.as_ref() else {
+                return syn::Error::new_spanned(
+                    &pat_type.pat,
+                    "#[controller] route parameters must be plain identifiers",
+                )
+                .to_compile_error()
+                .into();
+            };
+            let param_name = pat_ident.ident.to_string();
+            args.push(quote! {{
+                let raw = req
+                    .path_params
+                    .get(#param_name)
+                    .or_else(|| req.query_params.get(#param_name))
+                    .ok_or_else(|| {
+                        ::bubble::web::Error::new("BAD_REQUEST", format!("Missing parameter '{}'", #param_name))
+                    })?;
+                raw.parse().map_err(|_| {
+                    ::bubble::web::Error::new("BAD_REQUEST", format!("Invalid parameter '{}'", #param_name))
+                })?
+            }});
+        }
+        let call = if has_receiver {
+            quote! { controller.#fn_name(#(#args),*) }
+        } else {
+            quote! { Self::#fn_name(#(#args),*) }
+        };
+        let respond = match cache_ttl {
+            Some(ttl) => {
+                let ttl_ms = ttl.as_millis() as u64;
+                quote! {
+                    ::bubble::web::cached_response(req, std::time::Duration::from_millis(#ttl_ms), || {
+                        ::bubble::web::IntoResponse::into_response(#call)
+                    })
+                }
+            }
+            None => quote! { ::bubble::web::IntoResponse::into_response(#call) },
+        };
+
+        routes.push(quote! {{
+            let controller = controller.clone();
+            let base = #base.trim_end_matches('/');
+            let path = #path.trim_start_matches('/');
+            ::bubble::web::Route {
+                method: #http_method,
+                path: match (base.is_empty(), path.is_empty()) {
+                    (true, true) => "/".to_string(),
+                    (false, true) => base.to_string(),
+                    _ => format!("{}/{}", base, path),
+                },
+                handler: #handler_name.to_string(),
+                middleware: Vec::new(),
+                kind: ::bubble::web::RouteKind::Http,
+                dispatch: Some(std::sync::Arc::new(move |req: &::bubble::web::Request| {
+                    let _ = &controller;
+                    ::bubble::web::catch_handler_panic(req, || #respond)
+                })),
+                websocket: None,
+                max_body_size: #max_body_size,
+            }
+        }});
+    }
 
+    let self_ty = &item_impl.self_ty;
+    let (impl_generics, _, where_clause) = item_impl.generics.split_for_impl();
     let expanded = quote! {
-        #(#attrs)*
-        #[doc = concat!(#method, " Request Handler - Path: ", #path)]
-        #vis fn #fn_name(#inputs) #output #block
+        #item_impl
+
+        impl #impl_generics #self_ty #where_clause {
+            /// Routes for the controller's handler methods
+            pub fn routes(self) -> Vec<::bubble::web::Route> {
+                let controller = std::sync::Arc::new(self);
+                vec![#(#routes),*]
+            }
+        }
     };
 
     expanded.into()
 }
 
+/// Remove `#[request_body]` / `#[form]` from a controller route parameter,
+/// returning the expression that deserializes the body for it
+fn take_body_binding(attrs: &mut Vec<syn::Attribute>) -> Option<proc_macro2::TokenStream> {
+    let mut binding = None;
+    attrs.retain(|attr| {
+        if attr.path().is_ident("request_body") {
+            binding = Some(quote! { req.body_as()? });
+            false
+        } else if attr.path().is_ident("form") {
+            binding = Some(quote! { req.form()? });
+            false
+        } else {
+            true
+        }
+    });
+    binding
+}
+
+// =============================== Helper Functions ===============================
+
+/// Find the handler parameter holding the `Request`
+fn find_request_param(sig: &syn::Signature) -> Option<syn::Ident> {
+    sig.inputs.iter().find_map(|arg| {
+        let syn::FnArg::Typed(pat_type) = arg else {
+            return None;
+        };
+        let ty = match pat_type.ty.as_ref() {
+            syn::Type::Reference(reference) => reference.elem.as_ref(),
+            other => other,
+        };
+        let is_request = matches!(
+            ty,
+            syn::Type::Path(type_path)
+                if type_path.path.segments.last().is_some_and(|s| s.ident == "Request")
+        );
+        match pat_type.pat.as_ref() {
+            syn::Pat::Ident(pat_ident) if is_request => Some(pat_ident.ident.clone()),
+            _ => None,
+        }
+    })
+}
+
+/// How an ORM field is converted to and from its column value
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ColumnKind {
+    /// Converted with `FromStr` on read and `ToSql` on write
+    Plain,
+    /// Converted through its serde string representation
+    Enum,
+    /// Stored as its serde JSON document in a text column
+    Json,
+    /// `Option<T>`, mapped to and from SQL NULL
+    Optional,
+}
+
+/// Remove a `pk = "a, b"` option from the `#[orm]` attribute string and
+/// return its columns; the quoted list would otherwise be split on its comma
+fn take_pk(attr_str: &mut String) -> Result<Option<Vec<String>>, String> {
+    let mut search = 0;
+    let start = loop {
+        let Some(offset) = attr_str[search..].find("pk") else {
+            return Ok(None);
+        };
+        let start = search + offset;
+        let before = attr_str[..start].trim_end();
+        let after = attr_str[start + 2..].trim_start();
+        if (before.is_empty() || before.ends_with(',')) && after.starts_with('=') {
+            break start;
+        }
+        search = start + 2;
+    };
+    let open = attr_str[start..]
+        .find('"')
+        .map(|i| start + i)
+        .ok_or("pk expects a quoted column list, e.g. pk = \"user_id, role_id\"")?;
+    let close = attr_str[open + 1..]
+        .find('"')
+        .map(|i| open + 1 + i)
+        .ok_or("unterminated string in pk = \"...\"")?;
+    let columns = attr_str[open + 1..close]
+        .split(',')
+        .map(|column| column.trim().to_string())
+        .filter(|column| !column.is_empty())
+        .collect();
+    attr_str.replace_range(start..=close, "");
+    Ok(Some(columns))
+}
+
+/// Remove a `finders(...)` option from the `#[orm]` attribute string and
+/// return its column groups: a bare field name is a single-column finder,
+/// a quoted `"a, b"` list is a multi-column one.
+fn take_finders(attr_str: &mut String) -> Result<Vec<Vec<String>>, String> {
+    let Some(start) = attr_str.find("finders") else {
+        return Ok(Vec::new());
+    };
+    let open = attr_str[start..]
+        .find('(')
+        .map(|i| start + i)
+        .ok_or("finders expects a parenthesized list, e.g. finders(email)")?;
+    let close = attr_str[open..]
+        .find(')')
+        .map(|i| open + i)
+        .ok_or("unclosed finders(...) list")?;
+    let inner = attr_str[open + 1..close].to_string();
+    attr_str.replace_range(start..=close, "");
+
+    let mut finders = Vec::new();
+    let mut rest = inner.trim();
+    while !rest.is_empty() {
+        let (item, remaining) = if let Some(quoted) = rest.strip_prefix('"') {
+            let end = quoted
+                .find('"')
+                .ok_or("unterminated string in finders(...)")?;
+            (&quoted[..end], &quoted[end + 1..])
+        } else {
+            let end = rest.find(',').unwrap_or(rest.len());
+            (&rest[..end], &rest[end..])
+        };
+        let columns: Vec<String> = item
+            .split(',')
+            .map(|column| column.trim().to_string())
+            .filter(|column| !column.is_empty())
+            .collect();
+        if !columns.is_empty() {
+            finders.push(columns);
+        }
+        rest = remaining.trim_start().trim_start_matches(',').trim_start();
+    }
+    Ok(finders)
+}
+
+/// Determine the column kind of an ORM field.
+///
+/// `#[column(json)]` stores any serde type as a JSON document,
+/// `#[column(enum)]` (or `enum = "text"`) forces serde string mapping and
+/// `#[column(enum = "json")]` stores the full serde JSON, which also covers
+/// variants carrying data. Without an attribute, `Option<T>` maps to NULL and
+/// every other type is converted with `FromStr`/`ToSql`.
+fn column_kind(field: &syn::Field) -> syn::Result<ColumnKind> {
+    for attr in &field.attrs {
+        if !attr.path().is_ident("column") {
+            continue;
+        }
+        let mut kind = None;
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("json") {
+                kind = Some(ColumnKind::Json);
+                Ok(())
+            } else if meta.path.is_ident("enum") {
+                kind = Some(ColumnKind::Enum);
+                if meta.input.peek(syn::Token![=]) {
+                    let storage: syn::LitStr = meta.value()?.parse()?;
+                    kind = match storage.value().as_str() {
+                        "text" => Some(ColumnKind::Enum),
+                        "json" => Some(ColumnKind::Json),
+                        _ => {
+                            return Err(syn::Error::new_spanned(
+                                storage,
+                                "enum storage must be \"text\" or \"json\"",
+                            ));
+                        }
+                    };
+                }
+                Ok(())
+            } else {
+                Err(meta.error("unsupported column option"))
+            }
+        })?;
+        if let Some(kind) = kind {
+            return Ok(kind);
+        }
+    }
+    if let syn::Type::Path(type_path) = &field.ty
+        && let Some(segment) = type_path.path.segments.last()
+        && segment.ident == "Option"
+        && matches!(segment.arguments, syn::PathArguments::AngleBracketed(_))
+    {
+        return Ok(ColumnKind::Optional);
+    }
+    Ok(ColumnKind::Plain)
+}
+
+/// Last path segment of a type, unwrapping `Option<T>` to `T`
+fn column_type_name(ty: &syn::Type) -> String {
+    let syn::Type::Path(type_path) = ty else {
+        return String::new();
+    };
+    let Some(segment) = type_path.path.segments.last() else {
+        return String::new();
+    };
+    if segment.ident == "Option"
+        && let syn::PathArguments::AngleBracketed(args) = &segment.arguments
+        && let Some(syn::GenericArgument::Type(inner)) = args.args.first()
+    {
+        return column_type_name(inner);
+    }
+    segment.ident.to_string()
+}
+
+/// Column definition used in generated `CREATE TABLE` DDL.
+///
+/// A field named `id` becomes the auto-increment primary key; `Option<T>`
+/// fields are nullable and every other column is `NOT NULL`.
+fn column_definition(name: &str, ty: &syn::Type, kind: &ColumnKind, db_type: &str) -> String {
+    let type_name = column_type_name(ty);
+    if name == "id" {
+        let big = type_name == "i64" || type_name == "u64";
+        let primary_key = match db_type {
+            "postgres" if big => "BIGSERIAL PRIMARY KEY",
+            "postgres" => "SERIAL PRIMARY KEY",
+            "mysql" if big => "BIGINT AUTO_INCREMENT PRIMARY KEY",
+            "mysql" => "INT AUTO_INCREMENT PRIMARY KEY",
+            "sqlite" => "INTEGER PRIMARY KEY AUTOINCREMENT",
+            _ if big => "BIGINT PRIMARY KEY",
+            _ => "INTEGER PRIMARY KEY",
+        };
+        return format!("{} {}", name, primary_key);
+    }
+    let text = if db_type == "mysql" {
+        "VARCHAR(255)"
+    } else {
+        "TEXT"
+    };
+    let sql_type = match (type_name.as_str(), db_type) {
+        _ if matches!(kind, ColumnKind::Enum) => text,
+        _ if matches!(kind, ColumnKind::Json) => match db_type {
+            "postgres" => "JSONB",
+            "mysql" => "JSON",
+            _ => "TEXT",
+        },
+        ("i64" | "u64" | "isize" | "usize" | "u32", "sqlite") => "INTEGER",
+        ("i64" | "u64" | "isize" | "usize" | "u32", _) => "BIGINT",
+        ("i8" | "i16" | "i32" | "u8" | "u16", _) => "INTEGER",
+        ("f32" | "f64", "postgres") => "DOUBLE PRECISION",
+        ("f32" | "f64", "sqlite") => "REAL",
+        ("f32" | "f64", _) => "DOUBLE",
+        ("bool", _) => "BOOLEAN",
+        ("NaiveDateTime", "postgres") => "TIMESTAMP",
+        ("NaiveDateTime" | "DateTime", "mysql") => "DATETIME",
+        ("NaiveDate", "postgres" | "mysql") => "DATE",
+        ("DateTime", "postgres") => "TIMESTAMPTZ",
+        ("Uuid", "postgres") => "UUID",
+        ("Uuid", "mysql") => "CHAR(36)",
+        ("Vec", "postgres") => "BYTEA",
+        ("Vec", _) => "BLOB",
+        _ => text,
+    };
+    let optional_json = matches!(kind, ColumnKind::Json)
+        && matches!(ty, syn::Type::Path(type_path)
+            if type_path.path.segments.last().is_some_and(|segment| segment.ident == "Option"));
+    let nullable = if matches!(kind, ColumnKind::Optional) || optional_json {
+        ""
+    } else {
+        " NOT NULL"
+    };
+    format!("{} {}{}", name, sql_type, nullable)
+}
+
+/// Generate standard HTTP method macros
+fn generate_route_macro(method: &str, attr: TokenStream, item: TokenStream) -> TokenStream {
+    let path = if attr.is_empty() {
+        "/".to_string()
+    } else {
+        attr.to_string()
+            .trim_matches(|c| c == '"' || c == ' ')
+            .to_string()
+    };
+
+    let input_fn = parse_macro_input!(item as syn::ItemFn);
+    let fn_name = &input_fn.sig.ident;
+    let vis = &input_fn.vis;
+    let inputs = &input_fn.sig.inputs;
+    let output = &input_fn.sig.output;
+    let block = &input_fn.block;
+    let attrs = &input_fn.attrs;
+
+    let expanded = quote! {
+        #(#attrs)*
+        #[doc = concat!(#method, " Request Handler - Path: ", #path)]
+        #vis fn #fn_name(#inputs) #output #block
+    };
+
+    expanded.into()
+}
+
+/// Parse `#[route]` arguments, either `method = "..", path = ".."` or
+/// positional `"METHOD", "/path"`, into the method name and path
+fn parse_route_args(attr_str: &str) -> (String, String) {
+    let parts: Vec<&str> = attr_str.split(',').map(|s| s.trim()).collect();
+
+    if parts.len() >= 2 {
+        let method_part = parts[0];
+        let path_part = parts[1];
+
+        // Extract method
+        let method = if method_part.starts_with("method") {
+            method_part
+                .split('=')
+                .nth(1)
+                .unwrap_or("GET")
+                .trim_matches(|c| c == '"' || c == ' ')
+                .to_string()
+        } else {
+            method_part.trim_matches('"').to_string()
+        };
+
+        // Extract path
+        let path = if path_part.starts_with("path") {
+            path_part
+                .split('=')
+                .nth(1)
+                .unwrap_or("/")
+                .trim_matches(|c| c == '"' || c == ' ')
+                .to_string()
+        } else {
+            path_part.trim_matches('"').to_string()
+        };
+
+        (method, path)
+    } else if parts.len() == 1 {
+        // If there's only one parameter, assume it's the path and method defaults to GET
+        let path = parts[0].trim_matches('"').to_string();
+        ("GET".to_string(), path)
+    } else {
+        ("GET".to_string(), "/".to_string())
+    }
+}
+
 /// Generate custom HTTP method macros
 fn generate_custom_route_macro(method: &str, path: &str, item: TokenStream) -> TokenStream {
     let input_fn = parse_macro_input!(item as syn::ItemFn);
@@ -753,17 +1812,47 @@ pub fn middleware(_attr: TokenStream, item: TokenStream) -> TokenStream {
 
 /// Error handler macro
 ///
-/// Marks a function as an error handler
+/// Declares a function `fn(Error) -> Response` as an error handler and
+/// generates an `ErrorHandler` constant named after it in upper case, to be
+/// registered with `ErrorHandlers`. `ErrorHandlers::dispatch` runs a route
+/// and its middleware; when either returns an error, the handler registered
+/// for its code is used, then one for its HTTP status, then the catch-all.
+/// Unmatched errors, including every error when no handler is registered,
+/// get the default JSON body `{"code", "message", "details"}` with the
+/// status derived from the code (`Response::error`).
+///
+/// - `#[error_handler]`: catch-all handler
+/// - `#[error_handler(status = 404)]`: errors whose `Error::status()` is 404
+/// - `#[error_handler(code = "VALIDATION_FAILED")]`: errors with that code
 ///
 /// # Examples
 /// ```
-/// #[error_handler]
-/// fn handle_error(err: Error) -> Response {
+/// #[error_handler(status = 404)]
+/// fn handle_not_found(err: Error) -> Response {
 ///     Response::error(err)
 /// }
+///
+/// let error_handlers = ErrorHandlers::new().register(HANDLE_NOT_FOUND);
+/// let response = error_handlers.dispatch(&route, &mut request, &[&request_id]);
 /// ```
 #[proc_macro_attribute]
-pub fn error_handler(_attr: TokenStream, item: TokenStream) -> TokenStream {
+pub fn error_handler(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let mut matcher = quote! { ::bubble::web::ErrorMatcher::Any };
+    let parser = syn::meta::parser(|meta| {
+        if meta.path.is_ident("status") {
+            let status: syn::LitInt = meta.value()?.parse()?;
+            let status: u16 = status.base10_parse()?;
+            matcher = quote! { ::bubble::web::ErrorMatcher::Status(#status) };
+            Ok(())
+        } else if meta.path.is_ident("code") {
+            let code: syn::LitStr = meta.value()?.parse()?;
+            matcher = quote! { ::bubble::web::ErrorMatcher::Code(#code) };
+            Ok(())
+        } else {
+            Err(meta.error("expected `status = ..` or `code = \"..\"`"))
+        }
+    });
+    parse_macro_input!(attr with parser);
     let input_fn = parse_macro_input!(item as syn::ItemFn);
     let fn_name = &input_fn.sig.ident;
     let vis = &input_fn.vis;
@@ -771,16 +1860,158 @@ pub fn error_handler(_attr: TokenStream, item: TokenStream) -> TokenStream {
     let output = &input_fn.sig.output;
     let block = &input_fn.block;
     let attrs = &input_fn.attrs;
+    let handler_const = syn::Ident::new(&fn_name.to_string().to_uppercase(), fn_name.span());
 
     let expanded = quote! {
         #(#attrs)*
         #[doc = "Error Handler"]
         #vis fn #fn_name(#inputs) #output #block
+
+        #[doc = concat!("Registration of the `", stringify!(#fn_name), "` error handler")]
+        #vis const #handler_const: ::bubble::web::ErrorHandler = ::bubble::web::ErrorHandler {
+            matcher: #matcher,
+            handler: #fn_name,
+        };
     };
 
     expanded.into()
 }
 
+/// Role guard macro
+///
+/// Rejects the request with a `FORBIDDEN` error unless the authenticated
+/// user (`Context::auth`, populated by `JwtMiddleware`) has the given role.
+/// The handler must take the `Request` as a parameter and return a
+/// `Result` whose error type can be built from `Error`.
+///
+/// # Examples
+/// ```
+/// #[delete("/users/:id")]
+/// #[requires_role("admin")]
+/// fn delete_user(req: &Request, id: i64) -> Result<String, Error> {
+///     Ok(format!("User {} deleted", id))
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn requires_role(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let role = attr
+        .to_string()
+        .trim_matches(|c| c == '"' || c == ' ')
+        .to_string();
+    if role.is_empty() {
+        return syn::Error::new(
+            proc_macro2::Span::call_site(),
+            "#[requires_role] expects a role name, e.g. #[requires_role(\"admin\")]",
+        )
+        .to_compile_error()
+        .into();
+    }
+    let input_fn = parse_macro_input!(item as syn::ItemFn);
+    let check = quote! { auth.has_role(#role) };
+    let message = format!("Role '{}' is required", role);
+    guard_handler(input_fn, check, &message, "requires_role")
+}
+
+/// Authorization guard macro
+///
+/// Generalizes `#[requires_role]`: rejects the request with a `FORBIDDEN`
+/// error unless `Context::auth` carries the given role or permission.
+/// Composes with the HTTP method macros on the same function.
+///
+/// # Examples
+/// ```
+/// #[delete("/users/:id")]
+/// #[requires(permission = "users.delete")]
+/// fn delete_user(req: &Request, id: i64) -> Result<String, Error> {
+///     Ok(format!("User {} deleted", id))
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn requires(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let mut requirement: Option<(String, String)> = None;
+    let parser = syn::meta::parser(|meta| {
+        let kind = if meta.path.is_ident("role") {
+            "role"
+        } else if meta.path.is_ident("permission") {
+            "permission"
+        } else {
+            return Err(meta.error("expected `role` or `permission`"));
+        };
+        if requirement.is_some() {
+            return Err(meta.error("#[requires] takes a single role or permission"));
+        }
+        let value: syn::LitStr = meta.value()?.parse()?;
+        requirement = Some((kind.to_string(), value.value()));
+        Ok(())
+    });
+    parse_macro_input!(attr with parser);
+    let (kind, name) = match requirement {
+        Some(requirement) => requirement,
+        None => {
+            return syn::Error::new(
+                proc_macro2::Span::call_site(),
+                "#[requires] expects a role or permission, e.g. #[requires(role = \"admin\")]",
+            )
+            .to_compile_error()
+            .into();
+        }
+    };
+    let input_fn = parse_macro_input!(item as syn::ItemFn);
+    let check = if kind == "role" {
+        quote! { auth.has_role(#name) }
+    } else {
+        quote! { auth.has_permission(#name) }
+    };
+    let message = if kind == "role" {
+        format!("Role '{}' is required", name)
+    } else {
+        format!("Permission '{}' is required", name)
+    };
+    guard_handler(input_fn, check, &message, "requires")
+}
+
+/// Prepend an authorization check on `Context::auth` to a handler body
+fn guard_handler(
+    mut input_fn: syn::ItemFn,
+    check: proc_macro2::TokenStream,
+    message: &str,
+    macro_name: &str,
+) -> TokenStream {
+    let request = match find_request_param(&input_fn.sig) {
+        Some(ident) => ident,
+        None => {
+            return syn::Error::new_spanned(
+                &input_fn.sig,
+                format!(
+                    "#[{}] requires the handler to take a `Request` parameter",
+                    macro_name
+                ),
+            )
+            .to_compile_error()
+            .into();
+        }
+    };
+    let block = &input_fn.block;
+    let guarded: syn::Block = syn::parse_quote! {{
+        let __authorized = #request
+            .context
+            .auth
+            .as_ref()
+            .map_or(false, |auth| #check);
+        if !__authorized {
+            return Err(::bubble::web::Error {
+                code: "FORBIDDEN".to_string(),
+                message: #message.to_string(),
+                details: None,
+            }
+            .into());
+        }
+        #block
+    }};
+    *input_fn.block = guarded;
+    quote! { #input_fn }.into()
+}
+
 // =============================== Parameter Binding Macros ===============================
 
 /// Path parameter macro
@@ -806,8 +2037,7 @@ pub fn path_param(attr: TokenStream, item: TokenStream) -> TokenStream {
         #[doc = "Path Parameter: {}"]
         {}
     "#,
-        param_name,
-        item.to_string()
+        param_name, item
     );
     expanded.parse().unwrap()
 }
@@ -835,15 +2065,16 @@ pub fn query_param(attr: TokenStream, item: TokenStream) -> TokenStream {
         #[doc = "Query Parameter: {}"]
         {}
     "#,
-        param_name,
-        item.to_string()
+        param_name, item
     );
     expanded.parse().unwrap()
 }
 
 /// Request body macro
 ///
-/// Binds a function parameter to the request body
+/// Binds a function parameter to the request body. Inside a `#[controller]`
+/// impl the body is deserialized as JSON or as a URL-encoded form depending
+/// on the request's `Content-Type`.
 ///
 /// # Examples
 /// ```
@@ -859,11 +2090,234 @@ pub fn request_body(_attr: TokenStream, item: TokenStream) -> TokenStream {
         #[doc = "Request Body"]
         {}
     "#,
-        item.to_string()
+        item
     );
     expanded.parse().unwrap()
 }
 
+/// Validation derive macro
+///
+/// Implements `Validate` for a request body struct from `#[validate(..)]`
+/// field attributes. Every rule is checked and all failures are collected
+/// into one `VALIDATION_FAILED` error (422) whose `details` map each failing
+/// field to its messages. `Option` fields are only checked when `Some`.
+///
+/// Supported rules:
+/// - `email`: a plausible `local@domain.tld` address
+/// - `range(min = .., max = ..)`: numeric bounds, either may be omitted
+/// - `length(min = .., max = ..)`: characters for strings, elements otherwise
+///
+/// In a `#[controller]` impl, mark a `#[request_body]`/`#[form]` parameter
+/// with `#[validate]` to validate it right after deserialization.
+///
+/// # Examples
+/// ```
+/// #[derive(Deserialize, Validate)]
+/// struct CreateUserRequest {
+///     #[validate(email, length(max = 255))]
+///     email: String,
+///     #[validate(range(min = 1, max = 120))]
+///     age: u32,
+/// }
+///
+/// #[post("/users")]
+/// fn create_user(&self, #[request_body] #[validate] user: CreateUserRequest) -> String {
+///     format!("Creating user: {}", user.email)
+/// }
+/// ```
+#[proc_macro_derive(Validate, attributes(validate))]
+pub fn derive_validate(item: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(item as syn::DeriveInput);
+    let syn::Data::Struct(data) = &input.data else {
+        return syn::Error::new_spanned(&input, "#[derive(Validate)] only supports structs")
+            .to_compile_error()
+            .into();
+    };
+    let mut checks = Vec::new();
+    for field in &data.fields {
+        let Some(ident) = &field.ident else {
+            continue;
+        };
+        let field_name = ident.to_string();
+        for attr in &field.attrs {
+            if !attr.path().is_ident("validate") {
+                continue;
+            }
+            let mut rules = Vec::new();
+            let parsed = attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("email") {
+                    rules.push(validate_email_check(&field_name));
+                    return Ok(());
+                }
+                let is_range = meta.path.is_ident("range");
+                if !is_range && !meta.path.is_ident("length") {
+                    return Err(meta.error("unsupported validation rule"));
+                }
+                let (mut min, mut max) = (None, None);
+                meta.parse_nested_meta(|bound| {
+                    let value: syn::Lit = bound.value()?.parse()?;
+                    let value = match &value {
+                        syn::Lit::Int(int) => int.base10_parse::<f64>()?,
+                        syn::Lit::Float(float) => float.base10_parse::<f64>()?,
+                        _ => return Err(bound.error("expected a number")),
+                    };
+                    if bound.path.is_ident("min") {
+                        min = Some(value);
+                    } else if bound.path.is_ident("max") {
+                        max = Some(value);
+                    } else {
+                        return Err(bound.error("expected `min` or `max`"));
+                    }
+                    Ok(())
+                })?;
+                rules.push(if is_range {
+                    validate_range_check(&field_name, min, max)
+                } else {
+                    validate_length_check(&field_name, &field.ty, min, max)
+                });
+                Ok(())
+            });
+            if let Err(err) = parsed {
+                return err.to_compile_error().into();
+            }
+            let is_option = matches!(
+                &field.ty,
+                syn::Type::Path(type_path)
+                    if type_path.path.segments.last().is_some_and(|s| s.ident == "Option")
+            );
+            checks.push(if is_option {
+                quote! {
+                    if let Some(value) = &self.#ident {
+                        #(#rules)*
+                    }
+                }
+            } else {
+                quote! {{
+                    let value = &self.#ident;
+                    #(#rules)*
+                }}
+            });
+        }
+    }
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let expanded = quote! {
+        impl #impl_generics ::bubble::web::Validate for #name #ty_generics #where_clause {
+            fn validate(&self) -> Result<(), ::bubble::web::Error> {
+                let mut failures: Vec<(&'static str, String)> = Vec::new();
+                #(#checks)*
+                if failures.is_empty() {
+                    return Ok(());
+                }
+                let mut details: std::collections::HashMap<String, String> =
+                    std::collections::HashMap::new();
+                for (field, message) in &failures {
+                    details
+                        .entry(field.to_string())
+                        .and_modify(|messages| {
+                            messages.push_str("; ");
+                            messages.push_str(message);
+                        })
+                        .or_insert_with(|| message.clone());
+                }
+                Err(::bubble::web::Error {
+                    code: "VALIDATION_FAILED".to_string(),
+                    message: format!("Validation failed for {} field(s)", details.len()),
+                    details: Some(details),
+                })
+            }
+        }
+    };
+    expanded.into()
+}
+
+/// Check that `value` looks like an email address
+fn validate_email_check(field: &str) -> proc_macro2::TokenStream {
+    quote! {
+        let text: &str = &**value;
+        let valid = match text.split_once('@') {
+            Some((local, domain)) => {
+                !local.is_empty()
+                    && !domain.contains('@')
+                    && domain.contains('.')
+                    && !domain.starts_with('.')
+                    && !domain.ends_with('.')
+                    && !text.contains(char::is_whitespace)
+            }
+            None => false,
+        };
+        if !valid {
+            failures.push((#field, "must be a valid email address".to_string()));
+        }
+    }
+}
+
+/// Check that numeric `value` lies within `min..=max`
+fn validate_range_check(
+    field: &str,
+    min: Option<f64>,
+    max: Option<f64>,
+) -> proc_macro2::TokenStream {
+    let (message, min_check, max_check) = bound_checks(min, max, "must be");
+    quote! {
+        let number = *value as f64;
+        if #min_check || #max_check {
+            failures.push((#field, #message.to_string()));
+        }
+    }
+}
+
+/// Check the character count of strings or element count of collections
+fn validate_length_check(
+    field: &str,
+    ty: &syn::Type,
+    min: Option<f64>,
+    max: Option<f64>,
+) -> proc_macro2::TokenStream {
+    let is_string = match ty {
+        syn::Type::Reference(reference) => {
+            matches!(reference.elem.as_ref(), syn::Type::Path(p) if p.path.is_ident("str"))
+        }
+        other => column_type_name(other) == "String",
+    };
+    let count = if is_string {
+        quote! { value.chars().count() }
+    } else {
+        quote! { value.len() }
+    };
+    let (message, min_check, max_check) = bound_checks(min, max, "length must be");
+    quote! {
+        let number = #count as f64;
+        if #min_check || #max_check {
+            failures.push((#field, #message.to_string()));
+        }
+    }
+}
+
+/// Failure message and comparisons of `number` against optional bounds
+fn bound_checks(
+    min: Option<f64>,
+    max: Option<f64>,
+    subject: &str,
+) -> (String, proc_macro2::TokenStream, proc_macro2::TokenStream) {
+    let message = match (min, max) {
+        (Some(min), Some(max)) => format!("{} between {} and {}", subject, min, max),
+        (Some(min), None) => format!("{} at least {}", subject, min),
+        (None, Some(max)) => format!("{} at most {}", subject, max),
+        (None, None) => String::new(),
+    };
+    let min_check = match min {
+        Some(min) => quote! { number < #min },
+        None => quote! { false },
+    };
+    let max_check = match max {
+        Some(max) => quote! { number > #max },
+        None => quote! { false },
+    };
+    (message, min_check, max_check)
+}
+
 // ======================================================= DB =======================================================
 /// ORM (Object-Relational Mapping) Macro
 ///
@@ -876,6 +2330,15 @@ pub fn request_body(_attr: TokenStream, item: TokenStream) -> TokenStream {
 /// The macro supports the following optional parameters:
 /// - `table`: Specifies the database table name (optional, defaults to lowercase plural of struct name)
 /// - `db_type`: Specifies the database type (optional, defaults to "generic")
+/// - `connection`: Name of a connection registered with `bubble_db::register_database`
+///   (optional, defaults to `crate::DATABASE_CONNECTION`)
+/// - `pk`: A composite primary key, e.g. `pk = "user_id, role_id"` (optional, see
+///   "Composite Keys" below; defaults to the `id` field)
+/// - `soft_delete`: `soft_delete = true` makes deletes set `deleted_at` instead of
+///   removing the row (optional, see "Soft Delete" below)
+/// - `finders`: Columns to generate finders for; a quoted list generates a
+///   multi-column finder, e.g. `finders(email, "name, org_id")` generates
+///   `find_by_email(email: &str)` and `find_by_name_and_org_id(name: &str, org_id: i64)`
 ///   - Supported values: `"mysql"`, `"postgres"`, `"sqlite"`, `"redis"`, `"generic"`
 ///   - SQL syntax is automatically adapted for different database types
 ///
@@ -884,21 +2347,172 @@ pub fn request_body(_attr: TokenStream, item: TokenStream) -> TokenStream {
 /// The macro automatically generates the following methods for the struct:
 /// 1. **Instance Methods**:
 ///    - `insert(&self) -> DbResult<Self>` - Inserts the current instance into the database
+///    - `to_params(&self) -> DbResult<Vec<Box<dyn ToSql>>>` - Field values bound to the
+///      insert/update placeholders; fails if an enum or JSON field does not serialize
+///    - `insert_in_tx(&self, tx) -> DbResult<Self>` / `update_in_tx(&self, id, tx) -> DbResult<Self>` -
+///      Run the write on the given transaction instead of the global connection
 /// 2. **Static Methods**:
+///    - `delete_in_tx(id, tx) -> DbResult<u64>` - Deletes the record on the given transaction
+///    - `insert_many(records: &[Self]) -> DbResult<u64>` - Inserts many records with multi-row
+///      `INSERT` statements whose values are bound by the driver, chunked to stay under
+///      the backend's limit on bound parameters
 ///    - `find_by_id(id: i64) -> DbResult<Self>` - Finds a record by its ID
+///    - `try_find_by_id(id: i64) -> DbResult<Option<Self>>` - Like `find_by_id`, but a
+///      missing record is `Ok(None)` so only real failures are errors; prefer it
+///      over matching on `find_by_id`'s "No rows found" error
+///    - `exists(id: i64) -> DbResult<bool>` - Whether a record with the ID exists,
+///      without loading it
+///    - `first() -> DbResult<Option<Self>>` - The record with the lowest key, or `None`
+///      if the table is empty
+///    - `query_page(cursor: &Cursor) -> DbResult<Page<Self>>` - Keyset pagination by `id`
+///      (`WHERE id > ? ORDER BY id LIMIT ?`, or `<`/`DESC` for a descending cursor); the
+///      page's `next_cursor` reads the following page, e.g.
+///      `User::query_page(&Cursor::after(last_id).limit(50))`
 ///    - `update(&self, id: i64) -> DbResult<Self>` - Updates the record with the given ID
 ///    - `delete(id: i64) -> DbResult<Self>` - Deletes the record with the given ID
+///    - `delete_where(condition: &str, params: &[&dyn ToSql]) -> DbResult<u64>` - Deletes
+///      every row matching the condition, with placeholders (`$n` on Postgres, `?` or `$n`
+///      elsewhere) bound to `params`
+///    - `filter(filter: &Filter) -> DbResult<Vec<Self>>` - Records matching a `bubble_db::Filter`,
+///      e.g. `Filter::new().between("created_at", start, end)` for date ranges
+///    - `find_by_<cols>(..) -> DbResult<Option<Self>>` / `find_all_by_<cols>(..) -> DbResult<Vec<Self>>` -
+///      Parameterized finders generated for each entry of `finders(...)`
+///    - `create_table_sql() -> String` - `CREATE TABLE IF NOT EXISTS` DDL for the
+///      configured `db_type`, with `id` as the auto-increment primary key
+///    - `create_table(conn) -> DbResult<u64>` - Executes `create_table_sql()` on `conn`
+///    - `truncate(confirm: bool) -> DbResult<u64>` - Removes all rows (`TRUNCATE` on
+///      MySQL/Postgres, `DELETE FROM` otherwise); refuses to run unless `confirm` is `true`
 ///    - `all() -> DbResult<Vec<Self>>` - Retrieves all records from the table
+///    - `stream_all() -> BoxStream<DbResult<Self>>` - Yields all records lazily via
+///      `query_stream`, for tables too large to load at once
 ///    - `query(sql: &str) -> DbResult<Vec<Self>>` - Executes a custom SQL query
+///    - `query_with(sql: &str, params: &[&dyn ToSql]) -> DbResult<Vec<Self>>` - Executes a
+///      custom SQL query with placeholders (`$n` on Postgres, `?` or `$n` elsewhere) bound
+///      to `params` by the driver; use it instead of
+///      formatting user input into `sql`, e.g.
+///      `User::query_with("SELECT * FROM users WHERE name = ?", &[&name])`
+///    - `query_named(sql: &str, params: &[(&str, &dyn ToSql)]) -> DbResult<Vec<Self>>` -
+///      Executes a custom SQL query with `:name` placeholders bound by name
 ///    - `execute(sql: &str) -> DbResult<u64>` - Executes a custom SQL command
+///    - `execute_with(sql: &str, params: &[&dyn ToSql]) -> DbResult<u64>` - Executes a
+///      custom SQL command with bound parameters
+///    - `query_scalar::<T>(sql: &str, params: &[&dyn ToSql]) -> DbResult<T>` - First column
+///      of the first row converted to `T`, e.g. `User::query_scalar::<f64>("SELECT AVG(age) FROM users", &[])`
 ///    - `count() -> DbResult<i64>` - Counts the number of records in the table
 ///    - `where_clause(condition: &str) -> DbResult<Vec<Self>>` - Queries with WHERE condition
 ///
+/// # Composite Keys
+///
+/// With `pk = "a, b"` the table's primary key is `(a, b)` instead of `id`. The
+/// `id`-based `find_by_id`, `try_find_by_id`, `exists` and `query_page` are not generated;
+/// instead these take a tuple of the key values, bound as parameters:
+/// - `find_by_ids(key) -> DbResult<Option<Self>>`
+/// - `update(&self, key) -> DbResult<Self>` / `update_in_tx(&self, key, tx)`
+/// - `delete(key) -> DbResult<Self>` / `delete_in_tx(key, tx) -> DbResult<u64>`
+///
+/// `first` and `stream_all` order by the key columns.
+///
+/// ```rust
+/// #[orm(table = "user_roles", pk = "user_id, role_id")]
+/// struct UserRole {
+///     user_id: i64,
+///     role_id: i64,
+///     granted_by: String,
+/// }
+///
+/// let role = UserRole::find_by_ids((7, 2)).await?;
+/// UserRole::delete((7, 2)).await?;
+/// ```
+///
+/// # Soft Delete
+///
+/// With `#[orm(soft_delete = true)]` the struct must have an `Option` field named
+/// `deleted_at`. `delete`, `delete_in_tx` and `delete_where` then set it to
+/// `CURRENT_TIMESTAMP` instead of removing rows, and `find_by_id`, `all`,
+/// `stream_all`, `filter`, `count` and the finders skip rows where it is set.
+/// Two extra methods are generated:
+/// - `force_delete(id: i64) -> DbResult<u64>` - Removes the row permanently
+/// - `with_trashed() -> DbResult<Vec<Self>>` - All rows, including soft-deleted ones
+///
+/// ```rust
+/// #[orm(table = "posts", soft_delete = true)]
+/// struct Post {
+///     id: i64,
+///     title: String,
+///     deleted_at: Option<chrono::NaiveDateTime>,
+/// }
+/// ```
+///
+/// # Enum Fields
+///
+/// Fields marked `#[column(enum)]` are stored as their serde string
+/// representation: serialized on write and deserialized on read. Without the
+/// attribute a field is converted with `FromStr`/`ToSql` like any other
+/// column type. Enum fields still need `Default` (e.g. via `#[default]` on a
+/// variant), and a value that fails to serialize fails the write.
+///
+/// The storage representation is chosen with `#[column(enum = "...")]`:
+/// - `"text"` (the default): the serde string of a unit variant, e.g. `active`,
+///   in a `TEXT` (`VARCHAR(255)` on MySQL) column
+/// - `"json"`: the serde JSON document, e.g. `{"Suspended":{"days":3}}`, in a
+///   JSON column (see below); needed for variants that carry data
+///
+/// # JSON Fields
+///
+/// Fields marked `#[column(json)]` hold any serde type, such as a nested
+/// struct or a map. They are written as a JSON string and parsed with
+/// `serde_json::from_str` on read. The column is `JSONB` on Postgres, `JSON`
+/// on MySQL and `TEXT` elsewhere. An `Option<T>` JSON field is nullable, and
+/// `None` is stored as SQL NULL.
+///
+/// ```rust
+/// #[derive(Debug, Default, Clone, serde::Serialize, serde::Deserialize)]
+/// struct Metadata {
+///     tags: Vec<String>,
+///     source: String,
+/// }
+///
+/// #[orm(table = "documents", db_type = "postgres")]
+/// struct Document {
+///     id: i64,
+///     #[column(json)]
+///     metadata: Metadata,
+/// }
+/// ```
+///
+/// ```rust
+/// #[derive(Debug, Default, Clone, serde::Serialize, serde::Deserialize)]
+/// #[serde(rename_all = "lowercase")]
+/// enum Status {
+///     #[default]
+///     Active,
+///     Disabled,
+/// }
+///
+/// #[orm(table = "users")]
+/// struct User {
+///     id: i64,
+///     #[column(enum)]
+///     status: Status,
+/// }
+/// ```
+///
 /// # Database Integration
 ///
 /// The macro relies on a global database connection available through `crate::DATABASE_CONNECTION`.
 /// Before using ORM methods, you must initialize the database connection using `init_database_connection()`.
 ///
+/// Models declared with `connection = "name"` use the connection registered under
+/// that name instead, so one application can map models to several databases:
+///
+/// ```rust,ignore
+/// bubble_db::register_database("primary", bubble_db::connect(&primary_config).await?);
+/// bubble_db::register_database("cache", bubble_db::connect(&cache_config).await?);
+///
+/// #[orm(table = "users", db_type = "postgres", connection = "primary")]
+/// struct User { id: i64, name: String }
+/// ```
+///
 /// # Serialization
 ///
 /// The struct automatically implements:
@@ -965,23 +2579,38 @@ pub fn request_body(_attr: TokenStream, item: TokenStream) -> TokenStream {
 ///
 /// # Database-Specific Features
 ///
-/// - **PostgreSQL**: Uses `RETURNING *` clause for INSERT and UPDATE operations
-/// - **MySQL/SQLite**: Uses standard SQL syntax with `?` placeholders
+/// - **PostgreSQL**: Uses `RETURNING *` clause for INSERT and UPDATE operations and
+///   `$n` placeholders
+/// - **MySQL/SQLite**: Uses standard SQL syntax with `?` placeholders; `insert`
+///   leaves a default `id` to the auto-increment column and fills the returned
+///   record's `id` from `LAST_INSERT_ID()` / `last_insert_rowid()`
 /// - **Redis**: Supports basic key-value operations (limited ORM functionality)
 /// - **Generic**: Uses standard SQL syntax compatible with most databases
 ///
 /// # Error Handling
 ///
 /// All methods return `crate::DbResult<T>` which is an alias for `Result<T, String>`.
-/// Errors are propagated as strings for simplicity.
+/// Database errors name the failed operation and the SQL that ran, e.g.
+/// `insert into users failed: <driver error> (sql: INSERT INTO ...)`; the
+/// same context can be added to hand-written calls with `bubble_db::DbResultExt`.
 ///
 /// # Limitations
 ///
-/// - Field types must implement `Default`, `FromStr`, and serde traits
+/// - Field types must implement `Default`, `FromStr`, `Clone`, `ToSql`, and serde traits
 /// - Primitive types (i64, String, f64, etc.) are supported out of the box
+/// - `Option<T>` fields read SQL NULL as `None` and write `None` as NULL
 /// - Complex types may require custom implementations
 /// - No support for complex queries (JOINs, subqueries) - use `query()` method instead
-/// - No support for database transactions within the macro
+/// - Transactions are driven through `bubble_db::with_transaction` and the `*_in_tx` methods:
+///   ```rust
+///   bubble_db::with_transaction(&*DATABASE_CONNECTION, |tx| {
+///       Box::pin(async move {
+///           from.update_in_tx(from.id, tx).await?;
+///           to.update_in_tx(to.id, tx).await
+///       })
+///   })
+///   .await?;
+///   ```
 ///
 /// # Performance Considerations
 ///
@@ -1006,10 +2635,28 @@ pub fn request_body(_attr: TokenStream, item: TokenStream) -> TokenStream {
 /// ```
 #[proc_macro_attribute]
 pub fn orm(attr: TokenStream, item: TokenStream) -> TokenStream {
-    let attr_str = attr.to_string();
+    let mut attr_str = attr.to_string();
+    let finders = match take_finders(&mut attr_str) {
+        Ok(finders) => finders,
+        Err(err) => {
+            return syn::Error::new(proc_macro2::Span::call_site(), err)
+                .to_compile_error()
+                .into();
+        }
+    };
+    let pk = match take_pk(&mut attr_str) {
+        Ok(pk) => pk.unwrap_or_else(|| vec!["id".to_string()]),
+        Err(err) => {
+            return syn::Error::new(proc_macro2::Span::call_site(), err)
+                .to_compile_error()
+                .into();
+        }
+    };
     let attrs: Vec<&str> = attr_str.split(',').map(|s| s.trim()).collect();
     let mut table_name = String::new();
     let mut db_type = String::from("generic");
+    let mut connection: Option<String> = None;
+    let mut soft_delete = false;
     for attr in attrs {
         if attr.starts_with("table") {
             table_name = attr
@@ -1025,31 +2672,208 @@ pub fn orm(attr: TokenStream, item: TokenStream) -> TokenStream {
                 .unwrap_or("generic")
                 .trim_matches(|c| c == '"' || c == ' ')
                 .to_string();
+        } else if attr.starts_with("connection") {
+            connection = Some(
+                attr.split('=')
+                    .nth(1)
+                    .unwrap_or("")
+                    .trim_matches(|c| c == '"' || c == ' ')
+                    .to_string(),
+            );
+        } else if attr.starts_with("soft_delete") {
+            soft_delete = attr
+                .split('=')
+                .nth(1)
+                .map(|value| value.trim() == "true")
+                .unwrap_or(true);
         }
     }
-    let input = parse_macro_input!(item as syn::ItemStruct);
-    let struct_name = &input.ident;
+    // Models bound to a named connection look it up in the registry on each call
+    let (db, stream_rows) = match &connection {
+        Some(name) if name.is_empty() => {
+            return syn::Error::new(
+                proc_macro2::Span::call_site(),
+                "#[orm] `connection` must name a registered database",
+            )
+            .to_compile_error()
+            .into();
+        }
+        Some(name) => (
+            quote! { bubble_db::database(#name)? },
+            quote! {
+                match bubble_db::database(#name) {
+                    Ok(conn) => bubble_db::shared_query_stream(conn, sql),
+                    Err(e) => bubble_db::futures::stream::once(async move { Err(e) }).boxed(),
+                }
+            },
+        ),
+        None => (
+            quote! { crate::DATABASE_CONNECTION },
+            quote! { crate::DATABASE_CONNECTION.query_stream(&sql, &[]) },
+        ),
+    };
+    // Normalize aliases such as "postgresql" to the canonical type name
+    if !db_type.eq_ignore_ascii_case("generic") {
+        db_type = match db_type.parse::<bubble_db::DatabaseType>() {
+            Ok(database_type) => database_type.as_str().to_string(),
+            Err(err) => {
+                return syn::Error::new(proc_macro2::Span::call_site(), err)
+                    .to_compile_error()
+                    .into();
+            }
+        };
+    }
+    let mut input = parse_macro_input!(item as syn::ItemStruct);
+    match &input.fields {
+        syn::Fields::Named(fields_named) if fields_named.named.is_empty() => {
+            return syn::Error::new_spanned(&input.ident, "#[orm] requires at least one field")
+                .to_compile_error()
+                .into();
+        }
+        syn::Fields::Named(_) => {}
+        _ => {
+            return syn::Error::new_spanned(
+                &input.ident,
+                "#[orm] only supports structs with named fields",
+            )
+            .to_compile_error()
+            .into();
+        }
+    }
+    let struct_name = input.ident.clone();
     if table_name.is_empty() {
         table_name = format!("{}s", struct_name.to_string().to_lowercase());
     }
-    let field_idents: Vec<syn::Ident> = if let syn::Fields::Named(fields_named) = &input.fields {
-        fields_named
-            .named
+    let mut fields: Vec<(syn::Ident, ColumnKind)> = Vec::new();
+    let mut column_definitions: Vec<String> = Vec::new();
+    let mut field_types: std::collections::HashMap<String, (syn::Type, ColumnKind)> =
+        std::collections::HashMap::new();
+    if let syn::Fields::Named(fields_named) = &mut input.fields {
+        for field in fields_named.named.iter_mut() {
+            let kind = match column_kind(field) {
+                Ok(kind) => kind,
+                Err(err) => return err.to_compile_error().into(),
+            };
+            field.attrs.retain(|attr| !attr.path().is_ident("column"));
+            if let Some(ident) = field.ident.clone() {
+                column_definitions.push(column_definition(
+                    &ident.to_string(),
+                    &field.ty,
+                    &kind,
+                    &db_type,
+                ));
+                field_types.insert(ident.to_string(), (field.ty.clone(), kind));
+                fields.push((ident, kind));
+            }
+        }
+    }
+    let field_idents: Vec<syn::Ident> = fields.iter().map(|(ident, _)| ident.clone()).collect();
+    // A composite key replaces the `id`-based lookups with methods taking a tuple
+    let is_composite = pk.len() > 1;
+    if !is_composite && pk.first().map(String::as_str) != Some("id") {
+        return syn::Error::new(
+            proc_macro2::Span::call_site(),
+            "#[orm] `pk` must list at least two columns; single-column keys use the `id` field",
+        )
+        .to_compile_error()
+        .into();
+    }
+    if is_composite {
+        column_definitions.push(format!("PRIMARY KEY ({})", pk.join(", ")));
+    }
+    let order_by = pk.join(", ");
+    if soft_delete
+        && !fields
             .iter()
-            .filter_map(|f| f.ident.clone())
-            .collect()
+            .any(|(ident, kind)| ident == "deleted_at" && *kind == ColumnKind::Optional)
+    {
+        return syn::Error::new_spanned(
+            &input.ident,
+            "#[orm(soft_delete = true)] requires an `Option` field named `deleted_at`",
+        )
+        .to_compile_error()
+        .into();
+    }
+    // Soft-deleted rows are hidden from reads by appending these conditions
+    let (live_and, live_where) = if soft_delete {
+        (" AND deleted_at IS NULL", " WHERE deleted_at IS NULL")
     } else {
-        Vec::new()
+        ("", "")
     };
+    // Operation names used in error messages
+    let insert_op = format!("insert into {}", table_name);
+    let select_op = format!("select from {}", table_name);
+    let update_op = format!("update {}", table_name);
+    let delete_op = format!("delete from {}", table_name);
+    let truncate_op = format!("truncate {}", table_name);
+    let count_op = format!("count {}", table_name);
+    let execute_op = format!("execute on {}", table_name);
+    let create_op = format!("create table {}", table_name);
     let mut field_impls = Vec::new();
     let mut field_names_vec = Vec::new();
-    for ident in &field_idents {
+    let mut param_exprs = Vec::new();
+    for (ident, kind) in &fields {
         let field_name = ident.to_string();
-        field_impls.push(quote! {
-            if let Some(value) = row.get(#field_name) {
-                instance.#ident = value.parse().unwrap_or_default();
+        match kind {
+            ColumnKind::Plain => {
+                field_impls.push(quote! {
+                    if let Some(Some(value)) = row.get(#field_name) {
+                        instance.#ident = value.parse().unwrap_or_default();
+                    }
+                });
+                param_exprs.push(quote! {
+                    Box::new(self.#ident.clone()) as Box<dyn bubble_db::ToSql>
+                });
             }
-        });
+            ColumnKind::Enum => {
+                field_impls.push(quote! {
+                    if let Some(Some(value)) = row.get(#field_name) {
+                        instance.#ident = serde_json::from_value(
+                            serde_json::Value::String(value.clone()),
+                        )
+                        .map_err(|e| format!("Invalid value for {}: {}", #field_name, e))?;
+                    }
+                });
+                param_exprs.push(quote! {
+                    Box::new(match serde_json::to_value(&self.#ident)
+                        .map_err(|e| format!("Invalid value for {}: {}", #field_name, e))?
+                    {
+                        serde_json::Value::String(text) => text,
+                        other => other.to_string(),
+                    }) as Box<dyn bubble_db::ToSql>
+                });
+            }
+            ColumnKind::Json => {
+                field_impls.push(quote! {
+                    if let Some(Some(value)) = row.get(#field_name) {
+                        instance.#ident = serde_json::from_str(value)
+                            .map_err(|e| format!("Invalid value for {}: {}", #field_name, e))?;
+                    }
+                });
+                // A `None` field is written as SQL NULL rather than the text `null`
+                param_exprs.push(quote! {
+                    Box::new(match serde_json::to_value(&self.#ident)
+                        .map_err(|e| format!("Invalid value for {}: {}", #field_name, e))?
+                    {
+                        serde_json::Value::Null => None,
+                        value => Some(value),
+                    }) as Box<dyn bubble_db::ToSql>
+                });
+            }
+            ColumnKind::Optional => {
+                field_impls.push(quote! {
+                    instance.#ident = match row.get(#field_name) {
+                        Some(Some(value)) => Some(value.parse().map_err(|_| {
+                            format!("Invalid value for {}: {}", #field_name, value)
+                        })?),
+                        _ => None,
+                    };
+                });
+                param_exprs.push(quote! {
+                    Box::new(self.#ident.clone()) as Box<dyn bubble_db::ToSql>
+                });
+            }
+        }
         field_names_vec.push(quote! { #field_name });
     }
     let placeholders_count = field_idents.len();
@@ -1062,11 +2886,564 @@ pub fn orm(attr: TokenStream, item: TokenStream) -> TokenStream {
             }
         })
         .collect();
+    // MySQL and SQLite have no RETURNING, so the generated `id` is read back
+    // from the connection after the insert
+    let id_index = fields.iter().position(|(ident, _)| ident == "id");
+    let returns_generated_id = id_index.is_some() && (db_type == "mysql" || db_type == "sqlite");
+    let generated_id = match id_index {
+        Some(index) if returns_generated_id => {
+            let id_ty = &field_types["id"].0;
+            quote! {
+                let generated = self.id == <#id_ty as ::core::default::Default>::default();
+                let mut params = params;
+                if generated {
+                    params[#index] = Box::new(Option::<i64>::None);
+                }
+            }
+        }
+        _ => quote! {},
+    };
+    let assign_generated_id = quote! {
+        if generated {
+            record.id = id
+                .try_into()
+                .map_err(|_| format!("Generated id {} does not fit the id field", id))?;
+        }
+    };
+    let (insert_generated_id, insert_in_tx_generated_id) = if returns_generated_id {
+        (
+            quote! {
+                else if #db_type != "postgres" {
+                    let id = #db
+                        .execute_returning_id_with(&sql, &param_refs)
+                        .await
+                        .query_context(#insert_op, &sql)?;
+                    let mut record = self.snapshot()?;
+                    #assign_generated_id
+                    Ok(record)
+                }
+            },
+            quote! {
+                else if #db_type != "postgres" {
+                    let id = tx
+                        .execute_returning_id_with(&sql, &param_refs)
+                        .await
+                        .query_context(#insert_op, &sql)?;
+                    let mut record = self.snapshot()?;
+                    #assign_generated_id
+                    Ok(record)
+                }
+            },
+        )
+    } else {
+        (quote! {}, quote! {})
+    };
+    let delete_sql = if soft_delete {
+        quote! {
+            fn delete_sql(condition: &str) -> String {
+                format!(
+                    "UPDATE {} SET deleted_at = CURRENT_TIMESTAMP WHERE ({}) AND deleted_at IS NULL",
+                    #table_name,
+                    condition
+                )
+            }
+        }
+    } else {
+        quote! {
+            fn delete_sql(condition: &str) -> String {
+                format!("DELETE FROM {} WHERE {}", #table_name, condition)
+            }
+        }
+    };
+    let soft_delete_impls = if soft_delete {
+        quote! {
+            /// All rows, including soft-deleted ones
+            pub async fn with_trashed() -> crate::DbResult<Vec<Self>> {
+                let sql = format!("SELECT * FROM {}", #table_name);
+                Self::query(&sql).await
+            }
+        }
+    } else {
+        quote! {}
+    };
+    let id_placeholder = if db_type == "postgres" { "$1" } else { "?" };
+    let exists_sql = format!(
+        "SELECT 1 FROM {} WHERE id = {}{} LIMIT 1",
+        table_name, id_placeholder, live_and
+    );
+    let try_find_sql = format!(
+        "SELECT * FROM {} WHERE id = {}{}",
+        table_name, id_placeholder, live_and
+    );
+    let first_sql = format!(
+        "SELECT * FROM {}{} ORDER BY {} LIMIT 1",
+        table_name, live_where, order_by
+    );
+    let (page_start_sql, page_after_sql) = if db_type == "postgres" {
+        (
+            "LIMIT $1",
+            format!("id {{}} $1{} ORDER BY id {{}} LIMIT $2", live_and),
+        )
+    } else {
+        (
+            "LIMIT ?",
+            format!("id {{}} ?{} ORDER BY id {{}} LIMIT ?", live_and),
+        )
+    };
+    let key_methods = if is_composite {
+        let mut key_types = Vec::new();
+        for column in &pk {
+            match field_types.get(column) {
+                Some((ty, _)) => key_types.push(ty.clone()),
+                None => {
+                    return syn::Error::new(
+                        proc_macro2::Span::call_site(),
+                        format!("pk column '{}' is not a field of {}", column, struct_name),
+                    )
+                    .to_compile_error()
+                    .into();
+                }
+            }
+        }
+        let key_vars: Vec<syn::Ident> = (0..pk.len())
+            .map(|i| syn::Ident::new(&format!("__key{}", i), proc_macro2::Span::call_site()))
+            .collect();
+        let key_condition = |offset: usize| {
+            pk.iter()
+                .enumerate()
+                .map(|(i, column)| {
+                    if db_type == "postgres" {
+                        format!("{} = ${}", column, offset + i + 1)
+                    } else {
+                        format!("{} = ?", column)
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join(" AND ")
+        };
+        let condition = key_condition(0);
+        let find_sql = format!(
+            "SELECT * FROM {} WHERE {}{}",
+            table_name, condition, live_and
+        );
+        let set_clauses = field_idents
+            .iter()
+            .enumerate()
+            .map(|(i, ident)| {
+                if db_type == "postgres" {
+                    format!("{} = ${}", ident, i + 1)
+                } else {
+                    format!("{} = ?", ident)
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        let update_sql = format!(
+            "UPDATE {} SET {} WHERE {}{}",
+            table_name,
+            set_clauses,
+            key_condition(field_idents.len()),
+            if db_type == "postgres" {
+                " RETURNING *"
+            } else {
+                ""
+            }
+        );
+        let force_delete = if soft_delete {
+            quote! {
+                /// Permanently remove the row, bypassing soft delete
+                pub async fn force_delete(key: (#(#key_types),*)) -> crate::DbResult<u64> {
+                    let (#(#key_vars),*) = key;
+                    let params: Vec<&dyn bubble_db::ToSql> = vec![#(&#key_vars),*];
+                    let sql = format!("DELETE FROM {} WHERE {}", #table_name, #condition);
+                    #db
+                        .execute_with(&sql, &params)
+                        .await
+                        .query_context(#delete_op, &sql)
+                }
+            }
+        } else {
+            quote! {}
+        };
+        quote! {
+            pub async fn find_by_ids(key: (#(#key_types),*)) -> crate::DbResult<Option<Self>> {
+                let (#(#key_vars),*) = key;
+                let params: Vec<&dyn bubble_db::ToSql> = vec![#(&#key_vars),*];
+                let result = #db
+                    .query_with(#find_sql, &params)
+                    .await
+                    .query_context(#select_op, #find_sql)?;
+                Ok(Self::from_json_rows(&result)?.into_iter().next())
+            }
+            pub async fn update(&self, key: (#(#key_types),*)) -> crate::DbResult<Self> {
+                let (#(#key_vars),*) = key;
+                let params = self.to_params()?;
+                let mut param_refs: Vec<&dyn bubble_db::ToSql> =
+                    params.iter().map(|p| p.as_ref()).collect();
+                param_refs.extend([#(&#key_vars as &dyn bubble_db::ToSql),*]);
+                if #db_type == "postgres" {
+                    let result = #db
+                        .query_one_with(#update_sql, &param_refs)
+                        .await
+                        .query_context(#update_op, #update_sql)?;
+                    Self::from_json_row(&result)
+                } else {
+                    #db
+                        .execute_with(#update_sql, &param_refs)
+                        .await
+                        .query_context(#update_op, #update_sql)?;
+                    self.snapshot()
+                }
+            }
+            pub async fn update_in_tx(
+                &self,
+                key: (#(#key_types),*),
+                tx: &mut dyn bubble_db::Transaction,
+            ) -> crate::DbResult<Self> {
+                let (#(#key_vars),*) = key;
+                let params = self.to_params()?;
+                let mut param_refs: Vec<&dyn bubble_db::ToSql> =
+                    params.iter().map(|p| p.as_ref()).collect();
+                param_refs.extend([#(&#key_vars as &dyn bubble_db::ToSql),*]);
+                if #db_type == "postgres" {
+                    let result = tx
+                        .query_one_with(#update_sql, &param_refs)
+                        .await
+                        .query_context(#update_op, #update_sql)?;
+                    Self::from_json_row(&result)
+                } else {
+                    tx.execute_with(#update_sql, &param_refs)
+                        .await
+                        .query_context(#update_op, #update_sql)?;
+                    self.snapshot()
+                }
+            }
+            pub async fn delete(key: (#(#key_types),*)) -> crate::DbResult<Self> {
+                let (#(#key_vars),*) = key;
+                let params: Vec<&dyn bubble_db::ToSql> = vec![#(&#key_vars),*];
+                let result = #db
+                    .query_with(#find_sql, &params)
+                    .await
+                    .query_context(#select_op, #find_sql)?;
+                let record = Self::from_json_rows(&result)?
+                    .into_iter()
+                    .next()
+                    .ok_or_else(|| "No rows found".to_string())?;
+                let sql = Self::delete_sql(#condition);
+                #db
+                    .execute_with(&sql, &params)
+                    .await
+                    .query_context(#delete_op, &sql)?;
+                Ok(record)
+            }
+            pub async fn delete_in_tx(
+                key: (#(#key_types),*),
+                tx: &mut dyn bubble_db::Transaction,
+            ) -> crate::DbResult<u64> {
+                let (#(#key_vars),*) = key;
+                let params: Vec<&dyn bubble_db::ToSql> = vec![#(&#key_vars),*];
+                let sql = Self::delete_sql(#condition);
+                tx.execute_with(&sql, &params)
+                    .await
+                    .query_context(#delete_op, &sql)
+            }
+            #force_delete
+        }
+    } else {
+        let force_delete = if soft_delete {
+            quote! {
+                /// Permanently remove the row, bypassing soft delete
+                pub async fn force_delete(id: i64) -> crate::DbResult<u64> {
+                    let sql = format!("DELETE FROM {} WHERE id = {}", #table_name, id);
+                    #db
+                        .execute(&sql)
+                        .await
+                        .query_context(#delete_op, &sql)
+                }
+            }
+        } else {
+            quote! {}
+        };
+        quote! {
+            pub async fn find_by_id(id: i64) -> crate::DbResult<Self> {
+                Self::try_find_by_id(id)
+                    .await?
+                    .ok_or_else(|| format!("No rows found in {} with id {}", #table_name, id))
+            }
+            pub async fn try_find_by_id(id: i64) -> crate::DbResult<Option<Self>> {
+                let result = #db
+                    .query_with(#try_find_sql, &[&id])
+                    .await
+                    .query_context(#select_op, #try_find_sql)?;
+                Ok(Self::from_json_rows(&result)?.into_iter().next())
+            }
+            pub async fn exists(id: i64) -> crate::DbResult<bool> {
+                let rows = #db
+                    .query_rows_with(#exists_sql, &[&id])
+                    .await
+                    .query_context(#select_op, #exists_sql)?;
+                Ok(!rows.is_empty())
+            }
+            pub async fn query_page(
+                cursor: &bubble_db::Cursor,
+            ) -> crate::DbResult<bubble_db::Page<Self>> {
+                let (op, direction) = if cursor.descending { ("<", "DESC") } else { (">", "ASC") };
+                // One extra row tells whether another page follows
+                let limit = cursor.limit.max(1) as i64 + 1;
+                let (sql, result) = match cursor.last_id {
+                    Some(last_id) => {
+                        let sql = format!(
+                            "SELECT * FROM {} WHERE {}",
+                            #table_name,
+                            format!(#page_after_sql, op, direction)
+                        );
+                        let result = #db.query_with(&sql, &[&last_id, &limit]).await;
+                        (sql, result)
+                    }
+                    None => {
+                        let sql = format!(
+                            "SELECT * FROM {}{} ORDER BY id {} {}",
+                            #table_name,
+                            #live_where,
+                            direction,
+                            #page_start_sql
+                        );
+                        let result = #db.query_with(&sql, &[&limit]).await;
+                        (sql, result)
+                    }
+                };
+                let result = result.query_context(#select_op, &sql)?;
+                let mut rows: Vec<std::collections::HashMap<String, Option<String>>> =
+                    serde_json::from_str(&result).map_err(|e| e.to_string())?;
+                let has_more = rows.len() as i64 == limit;
+                rows.truncate(cursor.limit.max(1) as usize);
+                let next_cursor = match rows.last() {
+                    Some(row) if has_more => {
+                        let last_id: i64 = row
+                            .get("id")
+                            .and_then(|id| id.as_deref())
+                            .ok_or_else(|| format!("{} row has no id column", #table_name))?
+                            .parse()
+                            .map_err(|e| format!("Invalid id in {}: {}", #table_name, e))?;
+                        Some(cursor.after_id(last_id))
+                    }
+                    _ => None,
+                };
+                let mut items = Vec::with_capacity(rows.len());
+                for row in &rows {
+                    items.push(Self::from_db_row(row)?);
+                }
+                Ok(bubble_db::Page { items, next_cursor })
+            }
+            fn update_sql(id: i64) -> String {
+                let field_names: Vec<&str> = vec![
+                    #(stringify!(#field_idents)),*
+                ];
+                let set_clauses: Vec<String> = if #db_type == "postgres" {
+                    field_names.iter()
+                        .enumerate()
+                        .map(|(i, name)| format!("{} = ${}", name, i + 1))
+                        .collect()
+                } else {
+                    field_names.iter()
+                        .map(|name| format!("{} = ?", name))
+                        .collect()
+                };
+                let set_clauses_str = set_clauses.join(", ");
+                if #db_type == "postgres" {
+                    format!(
+                        "UPDATE {} SET {} WHERE id = {} RETURNING *",
+                        #table_name,
+                        set_clauses_str,
+                        id
+                    )
+                } else {
+                    format!(
+                        "UPDATE {} SET {} WHERE id = {}",
+                        #table_name,
+                        set_clauses_str,
+                        id
+                    )
+                }
+            }
+            pub async fn update(&self, id: i64) -> crate::DbResult<Self> {
+                let sql = Self::update_sql(id);
+                let params = self.to_params()?;
+                let param_refs: Vec<&dyn bubble_db::ToSql> =
+                    params.iter().map(|p| p.as_ref()).collect();
+                if #db_type == "postgres" {
+                    let result = #db
+                        .query_one_with(&sql, &param_refs)
+                        .await
+                        .query_context(#update_op, &sql)?;
+                    Self::from_json_row(&result)
+                } else {
+                    #db
+                        .execute_with(&sql, &param_refs)
+                        .await
+                        .query_context(#update_op, &sql)?;
+                    self.snapshot()
+                }
+            }
+            pub async fn update_in_tx(
+                &self,
+                id: i64,
+                tx: &mut dyn bubble_db::Transaction,
+            ) -> crate::DbResult<Self> {
+                let sql = Self::update_sql(id);
+                let params = self.to_params()?;
+                let param_refs: Vec<&dyn bubble_db::ToSql> =
+                    params.iter().map(|p| p.as_ref()).collect();
+                if #db_type == "postgres" {
+                    let result = tx
+                        .query_one_with(&sql, &param_refs)
+                        .await
+                        .query_context(#update_op, &sql)?;
+                    Self::from_json_row(&result)
+                } else {
+                    tx.execute_with(&sql, &param_refs)
+                        .await
+                        .query_context(#update_op, &sql)?;
+                    self.snapshot()
+                }
+            }
+            pub async fn delete(id: i64) -> crate::DbResult<Self> {
+                let record = Self::find_by_id(id).await?;
+                let sql = Self::delete_sql(&format!("id = {}", id));
+                #db
+                    .execute(&sql)
+                    .await
+                    .query_context(#delete_op, &sql)?;
+                Ok(record)
+            }
+            pub async fn delete_in_tx(
+                id: i64,
+                tx: &mut dyn bubble_db::Transaction,
+            ) -> crate::DbResult<u64> {
+                let sql = Self::delete_sql(&format!("id = {}", id));
+                tx.execute(&sql).await.query_context(#delete_op, &sql)
+            }
+            #force_delete
+        }
+    };
+    let create_table_sql = format!(
+        "CREATE TABLE IF NOT EXISTS {} ({})",
+        table_name,
+        column_definitions.join(", ")
+    );
+    let mut finder_impls = Vec::new();
+    for columns in &finders {
+        let mut args = Vec::new();
+        let mut bindings = Vec::new();
+        for (i, column) in columns.iter().enumerate() {
+            let Some((ty, kind)) = field_types.get(column) else {
+                return syn::Error::new(
+                    proc_macro2::Span::call_site(),
+                    format!(
+                        "finder column '{}' is not a field of {}",
+                        column, struct_name
+                    ),
+                )
+                .to_compile_error()
+                .into();
+            };
+            let arg = syn::Ident::new(column, proc_macro2::Span::call_site());
+            let value = syn::Ident::new(&format!("__value{}", i), proc_macro2::Span::call_site());
+            let is_string = matches!(
+                ty,
+                syn::Type::Path(type_path) if type_path.path.is_ident("String")
+            );
+            if is_string {
+                args.push(quote! { #arg: &str });
+                bindings.push(quote! { let #value = #arg; });
+            } else if *kind == ColumnKind::Json {
+                args.push(quote! { #arg: &#ty });
+                bindings.push(quote! {
+                    let #value = serde_json::to_value(#arg).map_err(|e| e.to_string())?;
+                });
+            } else if *kind == ColumnKind::Enum {
+                args.push(quote! { #arg: #ty });
+                bindings.push(quote! {
+                    let #value = match serde_json::to_value(&#arg).map_err(|e| e.to_string())? {
+                        serde_json::Value::String(text) => text,
+                        other => other.to_string(),
+                    };
+                });
+            } else {
+                args.push(quote! { #arg: #ty });
+                bindings.push(quote! { let #value = #arg; });
+            }
+        }
+        let values: Vec<syn::Ident> = (0..columns.len())
+            .map(|i| syn::Ident::new(&format!("__value{}", i), proc_macro2::Span::call_site()))
+            .collect();
+        let conditions: Vec<String> = columns
+            .iter()
+            .enumerate()
+            .map(|(i, column)| {
+                if db_type == "postgres" {
+                    format!("{} = ${}", column, i + 1)
+                } else {
+                    format!("{} = ?", column)
+                }
+            })
+            .collect();
+        let select_sql = format!(
+            "SELECT * FROM {} WHERE {}{}",
+            table_name,
+            conditions.join(" AND "),
+            live_and
+        );
+        let suffix = columns.join("_and_");
+        let find_one = syn::Ident::new(
+            &format!("find_by_{}", suffix),
+            proc_macro2::Span::call_site(),
+        );
+        let find_all = syn::Ident::new(
+            &format!("find_all_by_{}", suffix),
+            proc_macro2::Span::call_site(),
+        );
+        finder_impls.push(quote! {
+            pub async fn #find_one(#(#args),*) -> crate::DbResult<Option<Self>> {
+                #(#bindings)*
+                let params: Vec<&dyn bubble_db::ToSql> = vec![#(&#values),*];
+                let sql = format!("{} LIMIT 1", #select_sql);
+                let result = #db
+                    .query_with(&sql, &params)
+                    .await
+                    .query_context(#select_op, &sql)?;
+                Ok(Self::from_json_rows(&result)?.into_iter().next())
+            }
+            pub async fn #find_all(#(#args),*) -> crate::DbResult<Vec<Self>> {
+                #(#bindings)*
+                let params: Vec<&dyn bubble_db::ToSql> = vec![#(&#values),*];
+                let result = #db
+                    .query_with(#select_sql, &params)
+                    .await
+                    .query_context(#select_op, #select_sql)?;
+                Self::from_json_rows(&result)
+            }
+        });
+    }
+    // Maximum number of bound parameters per statement for each backend. Values
+    // are bound rather than inlined, so the statement text stays small and this
+    // is the only limit `insert_many` has to chunk for (mysql_async sends large
+    // values separately from the statement packet).
+    let max_params: usize = match db_type.as_str() {
+        "postgres" | "mysql" => 65535,
+        "sqlite" => 32766,
+        _ => 999,
+    };
     let expanded = quote! {
         #[derive(Default, serde::Serialize, serde::Deserialize)]
         #input
+        // Anonymous const scopes the `DbResultExt` import to the generated impl
+        const _: () = {
+        use bubble_db::DbResultExt as _;
         impl #struct_name {
-            fn from_db_row(row: &std::collections::HashMap<String, String>) -> crate::DbResult<Self> {
+            fn from_db_row(
+                row: &std::collections::HashMap<String, Option<String>>,
+            ) -> crate::DbResult<Self> {
                 let mut instance = Self::default();
                 #(#field_impls)*
                 Ok(instance)
@@ -1074,7 +3451,29 @@ pub fn orm(attr: TokenStream, item: TokenStream) -> TokenStream {
             fn from_json(json_str: &str) -> crate::DbResult<Self> {
                 serde_json::from_str(json_str).map_err(|e| e.to_string())
             }
-            pub async fn insert(&self) -> crate::DbResult<Self> {
+            fn from_json_row(json_str: &str) -> crate::DbResult<Self> {
+                let row: std::collections::HashMap<String, Option<String>> =
+                    serde_json::from_str(json_str).map_err(|e| e.to_string())?;
+                Self::from_db_row(&row)
+            }
+            fn to_params(&self) -> crate::DbResult<Vec<Box<dyn bubble_db::ToSql>>> {
+                Ok(vec![
+                    #(#param_exprs),*
+                ])
+            }
+            pub fn create_table_sql() -> String {
+                #create_table_sql.to_string()
+            }
+            pub async fn create_table<C>(conn: &C) -> crate::DbResult<u64>
+            where
+                C: bubble_db::DatabaseConnection + ?Sized,
+            {
+                let sql = Self::create_table_sql();
+                conn.execute(&sql)
+                    .await
+                    .query_context(#create_op, &sql)
+            }
+            fn insert_sql() -> String {
                 let field_names: Vec<&str> = vec![
                     #(stringify!(#field_idents)),*
                 ];
@@ -1083,7 +3482,7 @@ pub fn orm(attr: TokenStream, item: TokenStream) -> TokenStream {
                     #(#placeholders),*
                 ];
                 let placeholders_str = placeholders_vec.join(", ");
-                let sql = if #db_type == "postgres" {
+                if #db_type == "postgres" {
                     format!(
                         "INSERT INTO {} ({}) VALUES ({}) RETURNING *",
                         #table_name,
@@ -1097,86 +3496,248 @@ pub fn orm(attr: TokenStream, item: TokenStream) -> TokenStream {
                         fields_str,
                         placeholders_str
                     )
-                };
-                let result = crate::DATABASE_CONNECTION
-                    .query_one(&sql)
-                    .await?;
-                Self::from_json(&result)
+                }
             }
-            pub async fn find_by_id(id: i64) -> crate::DbResult<Self> {
-                let sql = format!("SELECT * FROM {} WHERE id = {}", #table_name, id);
-                let result = crate::DATABASE_CONNECTION.query_one(&sql).await?;
-                Self::from_json(&result)
+            fn snapshot(&self) -> crate::DbResult<Self> {
+                Self::from_json(&serde_json::to_string(self).map_err(|e| e.to_string())?)
             }
-            pub async fn update(&self, id: i64) -> crate::DbResult<Self> {
+            pub async fn insert(&self) -> crate::DbResult<Self> {
+                let sql = Self::insert_sql();
+                let params = self.to_params()?;
+                #generated_id
+                let param_refs: Vec<&dyn bubble_db::ToSql> =
+                    params.iter().map(|p| p.as_ref()).collect();
+                if #db_type == "postgres" {
+                    let result = #db
+                        .query_one_with(&sql, &param_refs)
+                        .await
+                        .query_context(#insert_op, &sql)?;
+                    Self::from_json_row(&result)
+                } #insert_generated_id else {
+                    #db
+                        .execute_with(&sql, &param_refs)
+                        .await
+                        .query_context(#insert_op, &sql)?;
+                    self.snapshot()
+                }
+            }
+            pub async fn insert_in_tx(
+                &self,
+                tx: &mut dyn bubble_db::Transaction,
+            ) -> crate::DbResult<Self> {
+                let sql = Self::insert_sql();
+                let params = self.to_params()?;
+                #generated_id
+                let param_refs: Vec<&dyn bubble_db::ToSql> =
+                    params.iter().map(|p| p.as_ref()).collect();
+                if #db_type == "postgres" {
+                    let result = tx
+                        .query_one_with(&sql, &param_refs)
+                        .await
+                        .query_context(#insert_op, &sql)?;
+                    Self::from_json_row(&result)
+                } #insert_in_tx_generated_id else {
+                    tx.execute_with(&sql, &param_refs)
+                        .await
+                        .query_context(#insert_op, &sql)?;
+                    self.snapshot()
+                }
+            }
+            pub async fn insert_many(records: &[Self]) -> crate::DbResult<u64> {
+                if records.is_empty() {
+                    return Ok(0);
+                }
                 let field_names: Vec<&str> = vec![
                     #(stringify!(#field_idents)),*
                 ];
-                let set_clauses: Vec<String> = if #db_type == "postgres" {
-                    field_names.iter()
-                        .enumerate()
-                        .map(|(i, name)| format!("{} = ${}", name, i + 1))
-                        .collect()
-                } else {
-                    field_names.iter()
-                        .map(|name| format!("{} = ?", name))
-                        .collect()
-                };
-                let set_clauses_str = set_clauses.join(", ");
-                let sql = if #db_type == "postgres" {
-                    format!(
-                        "UPDATE {} SET {} WHERE id = {} RETURNING *",
-                        #table_name,
-                        set_clauses_str,
-                        id
-                    )
-                } else {
-                    format!(
-                        "UPDATE {} SET {} WHERE id = {}",
+                let columns = field_names.len();
+                let rows_per_chunk = (#max_params / columns.max(1)).max(1);
+                let mut total = 0;
+                for chunk in records.chunks(rows_per_chunk) {
+                    let mut params: Vec<Box<dyn bubble_db::ToSql>> =
+                        Vec::with_capacity(chunk.len() * columns);
+                    let mut rows_sql = Vec::with_capacity(chunk.len());
+                    for record in chunk {
+                        let start = params.len();
+                        let placeholders: Vec<String> = (0..columns)
+                            .map(|i| {
+                                if #db_type == "postgres" {
+                                    format!("${}", start + i + 1)
+                                } else {
+                                    "?".to_string()
+                                }
+                            })
+                            .collect();
+                        rows_sql.push(format!("({})", placeholders.join(", ")));
+                        params.extend(record.to_params()?);
+                    }
+                    let sql = format!(
+                        "INSERT INTO {} ({}) VALUES {}",
                         #table_name,
-                        set_clauses_str,
-                        id
-                    )
-                };
-                let result = crate::DATABASE_CONNECTION
-                    .query_one(&sql)
-                    .await?;
-                Self::from_json(&result)
+                        field_names.join(", "),
+                        rows_sql.join(", ")
+                    );
+                    let param_refs: Vec<&dyn bubble_db::ToSql> =
+                        params.iter().map(|p| p.as_ref()).collect();
+                    total += #db
+                        .execute_with(&sql, &param_refs)
+                        .await
+                        .query_context(#insert_op, &sql)?;
+                }
+                Ok(total)
             }
-            pub async fn delete(id: i64) -> crate::DbResult<Self> {
-                let record = Self::find_by_id(id).await?;
-                let sql = format!("DELETE FROM {} WHERE id = {}", #table_name, id);
-                crate::DATABASE_CONNECTION.execute(&sql).await?;
-                Ok(record)
+            #key_methods
+            pub async fn first() -> crate::DbResult<Option<Self>> {
+                let result = #db
+                    .query_with(#first_sql, &[])
+                    .await
+                    .query_context(#select_op, #first_sql)?;
+                Ok(Self::from_json_rows(&result)?.into_iter().next())
+            }
+            #delete_sql
+            pub async fn delete_where(
+                condition: &str,
+                params: &[&dyn bubble_db::ToSql],
+            ) -> crate::DbResult<u64> {
+                let sql = Self::delete_sql(condition);
+                #db
+                    .execute_with(&sql, params)
+                    .await
+                    .query_context(#delete_op, &sql)
+            }
+            pub async fn filter(filter: &bubble_db::Filter) -> crate::DbResult<Vec<Self>> {
+                let (condition, params) = filter.to_condition(&#db.database_type())?;
+                let sql = format!(
+                    "SELECT * FROM {} WHERE ({}){}",
+                    #table_name,
+                    condition,
+                    #live_and
+                );
+                let result = #db
+                    .query_with(&sql, &params)
+                    .await
+                    .query_context(#select_op, &sql)?;
+                Self::from_json_rows(&result)
+            }
+            pub async fn truncate(confirm: bool) -> crate::DbResult<u64> {
+                if !confirm {
+                    return Err(format!(
+                        "Refusing to truncate {} without confirmation",
+                        #table_name
+                    ));
+                }
+                if #db_type == "mysql" || #db_type == "postgres" {
+                    let count = Self::count().await?;
+                    let sql = format!("TRUNCATE TABLE {}", #table_name);
+                    #db
+                        .execute(&sql)
+                        .await
+                        .query_context(#truncate_op, &sql)?;
+                    Ok(count.max(0) as u64)
+                } else {
+                    let sql = format!("DELETE FROM {}", #table_name);
+                    #db
+                        .execute(&sql)
+                        .await
+                        .query_context(#truncate_op, &sql)
+                }
             }
             pub async fn all() -> crate::DbResult<Vec<Self>> {
-                let sql = format!("SELECT * FROM {}", #table_name);
+                let sql = format!("SELECT * FROM {}{}", #table_name, #live_where);
                 Self::query(&sql).await
             }
+            #soft_delete_impls
+            pub fn stream_all()
+                -> bubble_db::futures::stream::BoxStream<'static, crate::DbResult<Self>>
+            {
+                use bubble_db::futures::StreamExt as _;
+                let sql = format!("SELECT * FROM {}{} ORDER BY {}", #table_name, #live_where, #order_by);
+                #stream_rows
+                    .map(|row| row.and_then(|row| Self::from_db_row(&row.to_text_map())))
+                    .boxed()
+            }
             pub async fn query(sql: &str) -> crate::DbResult<Vec<Self>> {
-                let result = crate::DATABASE_CONNECTION.query(sql).await?;
-                let items: Vec<std::collections::HashMap<String, String>> =
-                    serde_json::from_str(&result).map_err(|e| e.to_string())?;
+                let result = #db
+                    .query(sql)
+                    .await
+                    .query_context(#select_op, sql)?;
+                Self::from_json_rows(&result)
+            }
+            pub async fn query_with(
+                sql: &str,
+                params: &[&dyn bubble_db::ToSql],
+            ) -> crate::DbResult<Vec<Self>> {
+                let result = #db
+                    .query_with(sql, params)
+                    .await
+                    .query_context(#select_op, sql)?;
+                Self::from_json_rows(&result)
+            }
+            pub async fn query_named(
+                sql: &str,
+                params: &[(&str, &dyn bubble_db::ToSql)],
+            ) -> crate::DbResult<Vec<Self>> {
+                let result = #db
+                    .query_named(sql, params)
+                    .await
+                    .query_context(#select_op, sql)?;
+                Self::from_json_rows(&result)
+            }
+            fn from_json_rows(json_str: &str) -> crate::DbResult<Vec<Self>> {
+                let items: Vec<std::collections::HashMap<String, Option<String>>> =
+                    serde_json::from_str(json_str).map_err(|e| e.to_string())?;
                 let mut records = Vec::new();
                 for row in items {
                     records.push(Self::from_db_row(&row)?);
                 }
                 Ok(records)
             }
+            #(#finder_impls)*
             pub async fn execute(sql: &str) -> crate::DbResult<u64> {
-                crate::DATABASE_CONNECTION.execute(sql).await
+                #db
+                    .execute(sql)
+                    .await
+                    .query_context(#execute_op, sql)
+            }
+            pub async fn execute_with(
+                sql: &str,
+                params: &[&dyn bubble_db::ToSql],
+            ) -> crate::DbResult<u64> {
+                #db
+                    .execute_with(sql, params)
+                    .await
+                    .query_context(#execute_op, sql)
+            }
+            pub async fn query_scalar<T: bubble_db::FromSqlValue>(
+                sql: &str,
+                params: &[&dyn bubble_db::ToSql],
+            ) -> crate::DbResult<T> {
+                let value = #db
+                    .query_scalar_with(sql, params)
+                    .await
+                    .query_context(#select_op, sql)?;
+                T::from_sql_value(value)
             }
             pub async fn count() -> crate::DbResult<i64> {
-                let sql = format!("SELECT COUNT(*) as count FROM {}", #table_name);
-                let result = crate::DATABASE_CONNECTION.query_one(&sql).await?;
-                let data: std::collections::HashMap<String, String> =
+                let sql = format!(
+                    "SELECT COUNT(*) as count FROM {}{}",
+                    #table_name,
+                    #live_where
+                );
+                let result = #db
+                    .query_one(&sql)
+                    .await
+                    .query_context(#count_op, &sql)?;
+                let data: std::collections::HashMap<String, Option<String>> =
                     serde_json::from_str(&result).map_err(|e| e.to_string())?;
                 data.get("count")
-                    .unwrap_or(&"0".to_string())
-                    .parse()
+                    .and_then(|value| value.as_deref())
+                    .unwrap_or("0")
+                    .parse::<i64>()
                     .map_err(|e| e.to_string())
             }
         }
+        };
     };
     expanded.into()
 }
@@ -0,0 +1,108 @@
+use jsonwebtoken::errors::ErrorKind;
+use jsonwebtoken::{Algorithm, DecodingKey, Validation};
+use serde::{Deserialize, Serialize};
+
+use crate::types::{AppConfig, AuthInfo, Error, Middleware, Request, Response};
+
+/// JWT claims understood by the authentication middleware
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Claims {
+    /// Subject (user ID)
+    pub sub: String,
+    /// Expiration timestamp (seconds since epoch)
+    pub exp: u64,
+    /// User roles
+    #[serde(default)]
+    pub roles: Vec<String>,
+    /// User permissions
+    #[serde(default)]
+    pub permissions: Vec<String>,
+}
+
+/// JWT authentication middleware
+///
+/// Verifies the `Authorization: Bearer` token against the configured secret
+/// and populates `Context::auth` with the decoded claims. Invalid and expired
+/// tokens are rejected with a 401 error, as are missing tokens unless the
+/// middleware is `optional()`.
+#[derive(Clone)]
+pub struct JwtMiddleware {
+    key: DecodingKey,
+    validation: Validation,
+    optional: bool,
+}
+
+/// Authentication middleware populating `Context::auth` from a JWT
+pub type AuthMiddleware = JwtMiddleware;
+
+impl std::fmt::Debug for JwtMiddleware {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("JwtMiddleware")
+            .field("algorithms", &self.validation.algorithms)
+            .field("optional", &self.optional)
+            .finish()
+    }
+}
+
+impl JwtMiddleware {
+    /// Create a middleware verifying HS256 tokens with the given secret
+    pub fn new(secret: &str) -> Self {
+        Self {
+            key: DecodingKey::from_secret(secret.as_bytes()),
+            validation: Validation::new(Algorithm::HS256),
+            optional: false,
+        }
+    }
+
+    /// Let requests without a bearer token through unauthenticated.
+    /// Tokens that are present must still be valid.
+    pub fn optional(mut self) -> Self {
+        self.optional = true;
+        self
+    }
+
+    /// Create a middleware from the application's `jwt_secret`
+    pub fn from_config(config: &AppConfig) -> Self {
+        Self::new(&config.jwt_secret)
+    }
+
+    /// Allowed clock skew in seconds when checking expiration
+    pub fn leeway(mut self, seconds: u64) -> Self {
+        self.validation.leeway = seconds;
+        self
+    }
+
+    /// Decode and verify a token
+    pub fn verify(&self, token: &str) -> Result<AuthInfo, Error> {
+        let data = jsonwebtoken::decode::<Claims>(token, &self.key, &self.validation).map_err(
+            |e| match e.kind() {
+                ErrorKind::ExpiredSignature => Error::new("TOKEN_EXPIRED", "Token has expired"),
+                _ => Error::new("TOKEN_INVALID", "Invalid token")
+                    .with_detail("reason", e.to_string()),
+            },
+        )?;
+        Ok(AuthInfo {
+            user_id: data.claims.sub,
+            roles: data.claims.roles,
+            permissions: data.claims.permissions,
+            token: token.to_string(),
+        })
+    }
+}
+
+impl Middleware for JwtMiddleware {
+    fn pre_process(&self, request: &mut Request) -> Result<(), Error> {
+        let token = match request.bearer_token() {
+            Some(token) => token.to_string(),
+            None if self.optional => return Ok(()),
+            None => return Err(Error::new("UNAUTHORIZED", "Missing bearer token")),
+        };
+        let auth = self.verify(&token)?;
+        request.context.auth = Some(auth);
+        Ok(())
+    }
+
+    fn post_process(&self, _response: &mut Response) -> Result<(), Error> {
+        Ok(())
+    }
+}
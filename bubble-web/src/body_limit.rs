@@ -0,0 +1,165 @@
+use std::io::Read;
+
+use flate2::read::{DeflateDecoder, GzDecoder};
+
+use crate::types::{Error, Middleware, Request, Response, Route};
+
+/// Default maximum request body size (2 MiB)
+pub const DEFAULT_MAX_BODY_SIZE: usize = 2 * 1024 * 1024;
+
+/// Request body size limits
+///
+/// The request reader uses `read_body` (with `for_route` applied to the
+/// matched route) so an oversized body is rejected with 413 while it is read
+/// instead of after it is buffered; that is the only check of the received
+/// size. Multipart parsers call `check_part` for every part.
+///
+/// As middleware it decodes `Content-Encoding: gzip` and `deflate` bodies
+/// (see `decode_body`), applying `max_body_size` to the decompressed size so
+/// a small compressed body cannot expand without bound.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BodyLimits {
+    /// Maximum size of the whole body in bytes
+    pub max_body_size: usize,
+    /// Maximum size of a single multipart part in bytes
+    pub max_part_size: usize,
+}
+
+impl Default for BodyLimits {
+    fn default() -> Self {
+        Self {
+            max_body_size: DEFAULT_MAX_BODY_SIZE,
+            max_part_size: DEFAULT_MAX_BODY_SIZE,
+        }
+    }
+}
+
+impl BodyLimits {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the maximum body size
+    pub fn max_body_size(mut self, bytes: usize) -> Self {
+        self.max_body_size = bytes;
+        self
+    }
+
+    /// Set the maximum multipart part size
+    pub fn max_part_size(mut self, bytes: usize) -> Self {
+        self.max_part_size = bytes;
+        self
+    }
+
+    /// Limits for a matched route, applying its `max_body_size` override
+    pub fn for_route(&self, route: &Route) -> Self {
+        match route.max_body_size {
+            Some(max_body_size) => Self {
+                max_body_size,
+                ..*self
+            },
+            None => *self,
+        }
+    }
+
+    /// Read a body of at most `max_body_size` bytes.
+    ///
+    /// A declared `Content-Length` over the limit is rejected before reading;
+    /// otherwise at most one byte past the limit is read to detect overflow.
+    pub fn read_body<R: Read>(
+        &self,
+        reader: R,
+        content_length: Option<u64>,
+    ) -> Result<Vec<u8>, Error> {
+        if let Some(length) = content_length {
+            self.check_size(length)?;
+        }
+        let mut body = Vec::with_capacity(content_length.map_or(0, |length| length as usize));
+        reader
+            .take(self.max_body_size as u64 + 1)
+            .read_to_end(&mut body)
+            .map_err(|e| Error::new("BAD_REQUEST", format!("Failed to read body: {}", e)))?;
+        self.check_size(body.len() as u64)?;
+        Ok(body)
+    }
+
+    /// Decompress a body sent with `Content-Encoding: gzip` or `deflate`
+    /// in place, removing the header so handlers see the plain body.
+    ///
+    /// Decompression stops one byte past `max_body_size` and answers 413;
+    /// any other encoding answers 415.
+    pub fn decode_body(&self, request: &mut Request) -> Result<(), Error> {
+        let Some(header) = request.header("Content-Encoding").map(str::to_string) else {
+            return Ok(());
+        };
+        // Encodings are listed in the order they were applied
+        for encoding in header.split(',').rev() {
+            let encoding = encoding.trim().to_ascii_lowercase();
+            let body = std::mem::take(&mut request.body);
+            request.body = match encoding.as_str() {
+                "" | "identity" => body,
+                "gzip" | "x-gzip" => self.decompress(GzDecoder::new(body.as_slice()))?,
+                "deflate" => self.decompress(DeflateDecoder::new(body.as_slice()))?,
+                other => {
+                    return Err(Error::new(
+                        "UNSUPPORTED_MEDIA_TYPE",
+                        format!("Unsupported request Content-Encoding '{}'", other),
+                    ));
+                }
+            };
+        }
+        request.headers.remove("Content-Encoding");
+        request
+            .headers
+            .set("Content-Length", request.body.len().to_string());
+        Ok(())
+    }
+
+    fn decompress<R: Read>(&self, decoder: R) -> Result<Vec<u8>, Error> {
+        let mut body = Vec::new();
+        decoder
+            .take(self.max_body_size as u64 + 1)
+            .read_to_end(&mut body)
+            .map_err(|e| {
+                Error::new(
+                    "BAD_REQUEST",
+                    format!("Failed to decompress request body: {}", e),
+                )
+            })?;
+        self.check_size(body.len() as u64)?;
+        Ok(body)
+    }
+
+    /// Check one multipart part and the running total of all parts so far
+    pub fn check_part(&self, part_size: usize, total_size: usize) -> Result<(), Error> {
+        if part_size > self.max_part_size {
+            return Err(too_large("Multipart part", self.max_part_size));
+        }
+        self.check_size(total_size as u64)
+    }
+
+    fn check_size(&self, size: u64) -> Result<(), Error> {
+        if size > self.max_body_size as u64 {
+            return Err(too_large("Request body", self.max_body_size));
+        }
+        Ok(())
+    }
+}
+
+impl Middleware for BodyLimits {
+    fn pre_process(&self, request: &mut Request) -> Result<(), Error> {
+        self.decode_body(request)
+    }
+
+    fn post_process(&self, _response: &mut Response) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+fn too_large(what: &str, limit: usize) -> Error {
+    Error::new(
+        "PAYLOAD_TOO_LARGE",
+        format!("{} exceeds the limit of {} bytes", what, limit),
+    )
+    .with_detail("limit", limit.to_string())
+}
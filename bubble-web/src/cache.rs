@@ -0,0 +1,149 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use crate::types::{Error, Request, Response};
+
+/// Entries kept by the default in-memory cache
+pub const DEFAULT_CACHE_CAPACITY: usize = 1024;
+
+/// Storage for responses cached by `#[cache]` routes
+///
+/// Implementations must be safe to share between worker threads. The
+/// in-memory LRU is used by default; a shared store (e.g. Redis) can be
+/// installed with `set_response_cache` to share entries across instances.
+pub trait ResponseCacheStore: Send + Sync {
+    /// Cached response for `key`, or `None` when missing or expired
+    fn get(&self, key: &str) -> Option<Response>;
+    /// Cache `response` under `key` for `ttl`
+    fn put(&self, key: &str, response: Response, ttl: Duration);
+}
+
+#[derive(Debug)]
+struct CacheEntry {
+    response: Response,
+    expires_at: Instant,
+    last_used: u64,
+}
+
+#[derive(Debug, Default)]
+struct LruState {
+    entries: HashMap<String, CacheEntry>,
+    clock: u64,
+}
+
+/// In-process response cache evicting the least recently used entry when full
+#[derive(Debug)]
+pub struct InMemoryResponseCache {
+    capacity: usize,
+    state: Mutex<LruState>,
+}
+
+impl Default for InMemoryResponseCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_CACHE_CAPACITY)
+    }
+}
+
+impl InMemoryResponseCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            state: Mutex::new(LruState::default()),
+        }
+    }
+}
+
+impl ResponseCacheStore for InMemoryResponseCache {
+    fn get(&self, key: &str) -> Option<Response> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.clock += 1;
+        let clock = state.clock;
+        match state.entries.get_mut(key) {
+            Some(entry) if entry.expires_at <= Instant::now() => {
+                state.entries.remove(key);
+                None
+            }
+            Some(entry) => {
+                entry.last_used = clock;
+                Some(entry.response.clone())
+            }
+            None => None,
+        }
+    }
+
+    fn put(&self, key: &str, response: Response, ttl: Duration) {
+        let now = Instant::now();
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.clock += 1;
+        let clock = state.clock;
+        if !state.entries.contains_key(key) && state.entries.len() >= self.capacity {
+            state.entries.retain(|_, entry| entry.expires_at > now);
+            if state.entries.len() >= self.capacity {
+                let oldest = state
+                    .entries
+                    .iter()
+                    .min_by_key(|(_, entry)| entry.last_used)
+                    .map(|(key, _)| key.clone());
+                if let Some(oldest) = oldest {
+                    state.entries.remove(&oldest);
+                }
+            }
+        }
+        state.entries.insert(
+            key.to_string(),
+            CacheEntry {
+                response,
+                expires_at: now + ttl,
+                last_used: clock,
+            },
+        );
+    }
+}
+
+static RESPONSE_CACHE: OnceLock<Arc<dyn ResponseCacheStore>> = OnceLock::new();
+
+/// Install the store used by `#[cache]` routes; must be called before the
+/// first cached request, returns `false` if a store is already in use
+pub fn set_response_cache(store: Arc<dyn ResponseCacheStore>) -> bool {
+    RESPONSE_CACHE.set(store).is_ok()
+}
+
+/// Store used by `#[cache]` routes
+pub fn response_cache() -> &'static Arc<dyn ResponseCacheStore> {
+    RESPONSE_CACHE.get_or_init(|| Arc::new(InMemoryResponseCache::default()))
+}
+
+/// Cache key of a request: method, path and query parameters sorted by name
+pub fn cache_key(request: &Request) -> String {
+    let mut query: Vec<_> = request.query_params.iter().collect();
+    query.sort();
+    let query = query
+        .iter()
+        .map(|(name, value)| format!("{}={}", name, value))
+        .collect::<Vec<_>>()
+        .join("&");
+    format!("{} {}?{}", request.method, request.path, query)
+}
+
+/// Serve the cached response for `request` if it has not expired, otherwise
+/// run `handler` and cache a successful (2xx) response for `ttl`.
+///
+/// Responses served from the cache have `metadata.cached` set.
+pub fn cached_response(
+    request: &Request,
+    ttl: Duration,
+    handler: impl FnOnce() -> Result<Response, Error>,
+) -> Result<Response, Error> {
+    let key = cache_key(request);
+    let store = response_cache();
+    if let Some(mut response) = store.get(&key) {
+        response.metadata.cached = true;
+        return Ok(response);
+    }
+    let response = handler()?;
+    if (200..300).contains(&response.status.code) {
+        store.put(&key, response.clone(), ttl);
+    }
+    Ok(response)
+}
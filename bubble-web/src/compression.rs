@@ -0,0 +1,205 @@
+use std::io::Write;
+
+use flate2::Compression;
+use flate2::write::{DeflateEncoder, GzEncoder};
+
+use crate::types::{Error, Middleware, Request, Response, ResponseBody};
+
+/// Supported response compression algorithms
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompressionAlgorithm {
+    Gzip,
+    Deflate,
+}
+
+impl CompressionAlgorithm {
+    /// Token used in `Accept-Encoding` / `Content-Encoding` headers
+    pub fn token(&self) -> &'static str {
+        match self {
+            CompressionAlgorithm::Gzip => "gzip",
+            CompressionAlgorithm::Deflate => "deflate",
+        }
+    }
+}
+
+/// Response compression middleware
+///
+/// Compresses text, JSON and binary bodies when the client accepts one of the
+/// configured algorithms and the body is at least `min_size` bytes.
+#[derive(Debug, Clone)]
+pub struct CompressionMiddleware {
+    /// Algorithms in order of preference
+    pub algorithms: Vec<CompressionAlgorithm>,
+    /// Minimum body size in bytes before compression is applied
+    pub min_size: usize,
+    /// Compression level (0-9)
+    pub level: u32,
+    /// Content types that are already compressed and must be skipped
+    pub skip_content_types: Vec<String>,
+}
+
+impl Default for CompressionMiddleware {
+    fn default() -> Self {
+        Self {
+            algorithms: vec![CompressionAlgorithm::Gzip, CompressionAlgorithm::Deflate],
+            min_size: 1024,
+            level: 6,
+            skip_content_types: vec![
+                "image/".to_string(),
+                "video/".to_string(),
+                "audio/".to_string(),
+                "application/zip".to_string(),
+                "application/gzip".to_string(),
+                "application/x-gzip".to_string(),
+                "application/octet-stream".to_string(),
+            ],
+        }
+    }
+}
+
+impl CompressionMiddleware {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the minimum body size before compression is applied
+    pub fn min_size(mut self, min_size: usize) -> Self {
+        self.min_size = min_size;
+        self
+    }
+
+    /// Set the accepted algorithms in order of preference
+    pub fn algorithms(mut self, algorithms: Vec<CompressionAlgorithm>) -> Self {
+        self.algorithms = algorithms;
+        self
+    }
+
+    /// Set the compression level (clamped to 0-9)
+    pub fn level(mut self, level: u32) -> Self {
+        self.level = level.min(9);
+        self
+    }
+
+    /// Pick the algorithm the client weights highest, breaking ties by the
+    /// configured preference order. Encodings with `q=0` are refused, and a
+    /// `*` wildcard covers algorithms not listed explicitly.
+    fn negotiate(&self, accept_encoding: &str) -> Option<CompressionAlgorithm> {
+        let accepted: Vec<(String, f32)> = accept_encoding
+            .split(',')
+            .filter_map(|part| {
+                let mut pieces = part.split(';');
+                let token = pieces.next()?.trim().to_lowercase();
+                if token.is_empty() {
+                    return None;
+                }
+                let quality = pieces
+                    .filter_map(|param| param.trim().strip_prefix("q="))
+                    .find_map(|q| q.trim().parse::<f32>().ok())
+                    .unwrap_or(1.0);
+                Some((token, quality))
+            })
+            .collect();
+        let quality_of = |algorithm: CompressionAlgorithm| {
+            accepted
+                .iter()
+                .find(|(token, _)| token == algorithm.token())
+                .or_else(|| accepted.iter().find(|(token, _)| token == "*"))
+                .map(|(_, quality)| *quality)
+        };
+        let mut best: Option<(CompressionAlgorithm, f32)> = None;
+        for algorithm in self.algorithms.iter().copied() {
+            match quality_of(algorithm) {
+                Some(quality)
+                    if quality > 0.0
+                        && best.is_none_or(|(_, best_quality)| quality > best_quality) =>
+                {
+                    best = Some((algorithm, quality));
+                }
+                _ => {}
+            }
+        }
+        best.map(|(algorithm, _)| algorithm)
+    }
+
+    fn compress(&self, algorithm: CompressionAlgorithm, data: &[u8]) -> Result<Vec<u8>, Error> {
+        let level = Compression::new(self.level);
+        let result = match algorithm {
+            CompressionAlgorithm::Gzip => {
+                let mut encoder = GzEncoder::new(Vec::new(), level);
+                encoder.write_all(data).and_then(|_| encoder.finish())
+            }
+            CompressionAlgorithm::Deflate => {
+                let mut encoder = DeflateEncoder::new(Vec::new(), level);
+                encoder.write_all(data).and_then(|_| encoder.finish())
+            }
+        };
+        result.map_err(|e| Error {
+            code: "COMPRESSION_FAILED".to_string(),
+            message: e.to_string(),
+            details: None,
+        })
+    }
+}
+
+impl Middleware for CompressionMiddleware {
+    fn pre_process(&self, _request: &mut Request) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn post_process(&self, _response: &mut Response) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn post_process_with_request(
+        &self,
+        request: &Request,
+        response: &mut Response,
+    ) -> Result<(), Error> {
+        if response.headers.contains("Content-Encoding") {
+            return Ok(());
+        }
+        if let Some(content_type) = response.headers.get_one("Content-Type") {
+            let content_type = content_type.to_lowercase();
+            if self
+                .skip_content_types
+                .iter()
+                .any(|skip| content_type.starts_with(skip.as_str()))
+            {
+                return Ok(());
+            }
+        }
+        let algorithm = match request
+            .header("Accept-Encoding")
+            .and_then(|value| self.negotiate(value))
+        {
+            Some(algorithm) => algorithm,
+            None => return Ok(()),
+        };
+        let data = match &response.body {
+            ResponseBody::Text(text) => text.as_bytes().to_vec(),
+            ResponseBody::Json(value) => serde_json::to_vec(value).map_err(|e| Error {
+                code: "COMPRESSION_FAILED".to_string(),
+                message: e.to_string(),
+                details: None,
+            })?,
+            ResponseBody::Binary(bytes) => bytes.clone(),
+            ResponseBody::Empty => return Ok(()),
+        };
+        if data.len() < self.min_size {
+            return Ok(());
+        }
+        if let ResponseBody::Json(_) = response.body
+            && !response.headers.contains("Content-Type")
+        {
+            response.headers.set("Content-Type", "application/json");
+        }
+        let compressed = self.compress(algorithm, &data)?;
+        response.headers.set("Content-Encoding", algorithm.token());
+        response
+            .headers
+            .set("Content-Length", compressed.len().to_string());
+        response.headers.append("Vary", "Accept-Encoding");
+        response.body = ResponseBody::Binary(compressed);
+        Ok(())
+    }
+}
@@ -0,0 +1,175 @@
+use std::collections::HashMap;
+
+use crate::request_id::RequestIdMiddleware;
+use crate::types::{Error, HttpStatus, Middleware, Request, Response, ResponseBody, Route};
+
+/// Function turning a handler error into the response sent to the client
+pub type ErrorHandlerFn = fn(Error) -> Response;
+
+/// Which errors an error handler applies to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ErrorMatcher {
+    /// Errors with this `Error::code`
+    Code(&'static str),
+    /// Errors whose `Error::status()` is this HTTP status
+    Status(u16),
+    /// Every error not matched by a more specific handler
+    Any,
+}
+
+/// Error handler declared with `#[error_handler]`
+#[derive(Debug, Clone)]
+pub struct ErrorHandler {
+    pub matcher: ErrorMatcher,
+    pub handler: ErrorHandlerFn,
+}
+
+/// Registry of error handlers consulted when a route returns an error.
+///
+/// A handler registered for the error's code wins over one registered for
+/// its status, which wins over the catch-all; without any match the error
+/// is rendered by `default_error_response`.
+#[derive(Debug, Clone, Default)]
+pub struct ErrorHandlers {
+    by_code: HashMap<String, ErrorHandlerFn>,
+    by_status: HashMap<u16, ErrorHandlerFn>,
+    fallback: Option<ErrorHandlerFn>,
+}
+
+impl ErrorHandlers {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a handler generated by `#[error_handler]`
+    pub fn register(mut self, handler: ErrorHandler) -> Self {
+        match handler.matcher {
+            ErrorMatcher::Code(code) => {
+                self.by_code.insert(code.to_string(), handler.handler);
+            }
+            ErrorMatcher::Status(status) => {
+                self.by_status.insert(status, handler.handler);
+            }
+            ErrorMatcher::Any => self.fallback = Some(handler.handler),
+        }
+        self
+    }
+
+    /// Produce the response for `error`.
+    ///
+    /// A `Retry-After` detail on the error (as set by the rate limiter) is
+    /// also sent as the `Retry-After` header unless the handler set one.
+    pub fn handle(&self, error: Error) -> Response {
+        let handler = self
+            .by_code
+            .get(&error.code)
+            .or_else(|| self.by_status.get(&error.status()))
+            .copied()
+            .or(self.fallback);
+        let retry_after = error
+            .details
+            .as_ref()
+            .and_then(|details| details.get("Retry-After"))
+            .cloned();
+        let mut response = match handler {
+            Some(handler) => handler(error),
+            None => default_error_response(error),
+        };
+        if let Some(retry_after) = retry_after
+            && response.headers.get_one("Retry-After").is_none()
+        {
+            response.headers.set("Retry-After", retry_after);
+        }
+        response
+    }
+
+    /// Produce the response for a route's result, handling its error if any
+    pub fn respond(&self, result: Result<Response, Error>) -> Response {
+        result.unwrap_or_else(|error| self.handle(error))
+    }
+
+    /// Run a request through the middleware and the route's handler.
+    ///
+    /// Middleware `pre_process` runs in order before the handler and
+    /// `post_process_with_request` in reverse order after it. The first
+    /// `Err` from any of them stops the chain and is turned into the
+    /// response by `handle`. Everything runs inside the request's
+    /// `RequestIdMiddleware::span`.
+    pub fn dispatch(
+        &self,
+        route: &Route,
+        request: &mut Request,
+        middleware: &[&dyn Middleware],
+    ) -> Response {
+        let span = RequestIdMiddleware::span(request);
+        let _entered = span.enter();
+        let mut id_recorded = !request.context.request_id.is_empty();
+        let result: Result<Response, Error> = (|| {
+            for layer in middleware {
+                layer.pre_process(request)?;
+                if !id_recorded && !request.context.request_id.is_empty() {
+                    span.record("request_id", request.context.request_id.as_str());
+                    id_recorded = true;
+                }
+            }
+            let handler = route.dispatch.as_ref().ok_or_else(|| {
+                Error::new(
+                    "INTERNAL_ERROR",
+                    format!("Route {} has no handler", route.handler),
+                )
+            })?;
+            let mut response = handler(request)?;
+            for layer in middleware.iter().rev() {
+                layer.post_process_with_request(request, &mut response)?;
+            }
+            Ok(response)
+        })();
+        self.respond(result)
+    }
+}
+
+/// Render an error as a JSON body `{"code", "message", "details"}` with the
+/// status derived from its code
+pub fn default_error_response(error: Error) -> Response {
+    let status = error.status();
+    let mut response = Response::ok(ResponseBody::Json(serde_json::json!({
+        "code": error.code,
+        "message": error.message,
+        "details": error.details,
+    })));
+    response.status = HttpStatus {
+        code: status,
+        message: status_reason(status).to_string(),
+    };
+    response
+}
+
+/// Reason phrase of common statuses; other server errors fall back to
+/// "Internal Server Error" and anything else to an empty phrase
+pub(crate) fn status_reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        201 => "Created",
+        202 => "Accepted",
+        204 => "No Content",
+        301 => "Moved Permanently",
+        302 => "Found",
+        304 => "Not Modified",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        409 => "Conflict",
+        413 => "Payload Too Large",
+        415 => "Unsupported Media Type",
+        422 => "Unprocessable Entity",
+        429 => "Too Many Requests",
+        500 => "Internal Server Error",
+        502 => "Bad Gateway",
+        503 => "Service Unavailable",
+        504 => "Gateway Timeout",
+        _ if status >= 500 => "Internal Server Error",
+        _ => "",
+    }
+}
@@ -0,0 +1,92 @@
+use std::ops::{Deref, DerefMut};
+use std::str::FromStr;
+
+use serde::de::DeserializeOwned;
+
+use crate::types::{Error, Request};
+
+/// A value built from the request, such as a handler argument.
+///
+/// `#[controller]` routes call `from_request` for `Path`, `Query` and
+/// `Json` arguments; a failure is answered by the error handlers instead of
+/// running the handler.
+pub trait FromRequest: Sized {
+    fn from_request(req: &Request) -> Result<Self, Error>;
+}
+
+impl FromRequest for Request {
+    fn from_request(req: &Request) -> Result<Self, Error> {
+        Ok(req.clone())
+    }
+}
+
+/// The route's single path parameter, e.g. the `:id` of `/users/:id`,
+/// parsed with `FromStr`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Path<T>(pub T);
+
+impl<T: FromStr> FromRequest for Path<T> {
+    fn from_request(req: &Request) -> Result<Self, Error> {
+        let mut params = req.path_params.iter();
+        let (name, raw) = match (params.next(), params.next()) {
+            (Some(param), None) => param,
+            (None, _) => {
+                return Err(Error::new("BAD_REQUEST", "Route has no path parameter"));
+            }
+            (Some(_), Some(_)) => {
+                return Err(Error::new(
+                    "INTERNAL_ERROR",
+                    "Path<T> needs a route with one path parameter; take the parameters by name instead",
+                ));
+            }
+        };
+        raw.parse()
+            .map(Path)
+            .map_err(|_| Error::new("BAD_REQUEST", format!("Invalid parameter '{}'", name)))
+    }
+}
+
+/// Query parameters deserialized into `T`
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Query<T>(pub T);
+
+impl<T: DeserializeOwned> FromRequest for Query<T> {
+    fn from_request(req: &Request) -> Result<Self, Error> {
+        let query = serde_urlencoded::to_string(&req.query_params)
+            .map_err(|e| Error::new("BAD_REQUEST", format!("Invalid query: {}", e)))?;
+        serde_urlencoded::from_str(&query).map(Query).map_err(|e| {
+            Error::new("BAD_REQUEST", format!("Invalid query: {}", e))
+                .with_detail("reason", e.to_string())
+        })
+    }
+}
+
+/// JSON body deserialized into `T`, see `Request::json`
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Json<T>(pub T);
+
+impl<T: DeserializeOwned> FromRequest for Json<T> {
+    fn from_request(req: &Request) -> Result<Self, Error> {
+        req.json().map(Json)
+    }
+}
+
+macro_rules! impl_deref {
+    ($($extractor:ident),*) => {$(
+        impl<T> Deref for $extractor<T> {
+            type Target = T;
+
+            fn deref(&self) -> &T {
+                &self.0
+            }
+        }
+
+        impl<T> DerefMut for $extractor<T> {
+            fn deref_mut(&mut self) -> &mut T {
+                &mut self.0
+            }
+        }
+    )*};
+}
+
+impl_deref!(Path, Query, Json);
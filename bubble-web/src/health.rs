@@ -0,0 +1,164 @@
+use std::sync::{Arc, Mutex};
+
+use bubble_db::{DatabaseConnection, PingHealth};
+
+use crate::types::{HttpMethod, HttpStatus, Request, Response, ResponseBody, Route, RouteKind};
+
+/// Default liveness endpoint path
+pub const DEFAULT_LIVENESS_PATH: &str = "/healthz";
+
+/// Default readiness endpoint path
+pub const DEFAULT_READINESS_PATH: &str = "/readyz";
+
+/// Liveness and readiness endpoints
+///
+/// Liveness always answers 200 once the server is serving. Readiness pings
+/// the configured database and answers 503 when it is unreachable.
+///
+/// Route handlers are synchronous, so the readiness route answers with the
+/// result of the latest ping and starts the next one in the background; it
+/// answers 503 until the first ping completes. `readiness` runs a ping
+/// directly.
+#[derive(Clone)]
+pub struct HealthCheck {
+    /// Path of the liveness endpoint
+    pub liveness_path: String,
+    /// Path of the readiness endpoint
+    pub readiness_path: String,
+    /// Database pinged by the readiness endpoint
+    pub database: Option<Arc<dyn DatabaseConnection>>,
+    /// Result of the latest readiness ping
+    last_ping: Arc<Mutex<Option<PingHealth>>>,
+}
+
+impl std::fmt::Debug for HealthCheck {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HealthCheck")
+            .field("liveness_path", &self.liveness_path)
+            .field("readiness_path", &self.readiness_path)
+            .field("database", &self.database.is_some())
+            .finish()
+    }
+}
+
+impl Default for HealthCheck {
+    fn default() -> Self {
+        Self {
+            liveness_path: DEFAULT_LIVENESS_PATH.to_string(),
+            readiness_path: DEFAULT_READINESS_PATH.to_string(),
+            database: None,
+            last_ping: Arc::new(Mutex::new(None)),
+        }
+    }
+}
+
+impl HealthCheck {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Use a custom liveness path
+    pub fn liveness_path(mut self, path: impl Into<String>) -> Self {
+        self.liveness_path = path.into();
+        self
+    }
+
+    /// Use a custom readiness path
+    pub fn readiness_path(mut self, path: impl Into<String>) -> Self {
+        self.readiness_path = path.into();
+        self
+    }
+
+    /// Ping this database on readiness checks
+    pub fn database(mut self, database: Arc<dyn DatabaseConnection>) -> Self {
+        self.database = Some(database);
+        self
+    }
+
+    /// Ping the database and answer the readiness check, 200 when it is
+    /// reachable (or none is configured) and 503 otherwise
+    pub async fn readiness(&self) -> Response {
+        let Some(database) = self.database.as_deref() else {
+            return health_response(true, serde_json::json!({ "status": "ok" }));
+        };
+        let health = bubble_db::health_check(database).await;
+        if let Ok(mut last_ping) = self.last_ping.lock() {
+            *last_ping = Some(health.clone());
+        }
+        readiness_response(&health)
+    }
+
+    /// Answer from the latest ping and refresh it in the background
+    fn cached_readiness(&self) -> Response {
+        if self.database.is_none() {
+            return health_response(true, serde_json::json!({ "status": "ok" }));
+        }
+        match tokio::runtime::Handle::try_current() {
+            Ok(runtime) => {
+                let check = self.clone();
+                runtime.spawn(async move {
+                    check.readiness().await;
+                });
+            }
+            Err(err) => log::warn!("Readiness ping requires a Tokio runtime: {}", err),
+        }
+        let last_ping = self.last_ping.lock().ok().and_then(|last| last.clone());
+        match last_ping {
+            Some(health) => readiness_response(&health),
+            None => health_response(false, serde_json::json!({ "status": "starting" })),
+        }
+    }
+
+    /// Routes serving the liveness and readiness endpoints
+    pub fn routes(&self) -> Vec<Route> {
+        let check = self.clone();
+        vec![
+            Route {
+                method: HttpMethod::GET,
+                path: self.liveness_path.clone(),
+                handler: "liveness".to_string(),
+                middleware: Vec::new(),
+                kind: RouteKind::Http,
+                dispatch: Some(Arc::new(|_: &Request| {
+                    Ok(health_response(true, serde_json::json!({ "status": "ok" })))
+                })),
+                websocket: None,
+                max_body_size: None,
+            },
+            Route {
+                method: HttpMethod::GET,
+                path: self.readiness_path.clone(),
+                handler: "readiness".to_string(),
+                middleware: Vec::new(),
+                kind: RouteKind::Http,
+                dispatch: Some(Arc::new(move |_: &Request| Ok(check.cached_readiness()))),
+                websocket: None,
+                max_body_size: None,
+            },
+        ]
+    }
+}
+
+/// Readiness response for a database ping
+fn readiness_response(health: &PingHealth) -> Response {
+    health_response(
+        health.healthy,
+        serde_json::json!({
+            "status": if health.healthy { "ok" } else { "unavailable" },
+            "database": health,
+        }),
+    )
+}
+
+/// JSON health response, 200 when healthy and 503 otherwise
+fn health_response(healthy: bool, body: serde_json::Value) -> Response {
+    let mut response = Response::ok(ResponseBody::Json(body));
+    if !healthy {
+        response.status = HttpStatus {
+            code: 503,
+            message: "Service Unavailable".to_string(),
+        };
+    }
+    response.headers.set("Cache-Control", "no-store");
+    response
+}
@@ -0,0 +1,32 @@
+pub mod auth;
+pub mod body_limit;
+pub mod cache;
+pub mod compression;
+pub mod error_handler;
+pub mod extract;
+pub mod health;
+pub mod panic_handler;
+pub mod rate_limit;
+pub mod request_id;
+pub mod session;
+pub mod shutdown;
+pub mod types;
+
+pub use auth::{AuthMiddleware, Claims, JwtMiddleware};
+pub use body_limit::{BodyLimits, DEFAULT_MAX_BODY_SIZE};
+pub use cache::{InMemoryResponseCache, ResponseCacheStore, cached_response, set_response_cache};
+pub use compression::{CompressionAlgorithm, CompressionMiddleware};
+pub use error_handler::{ErrorHandler, ErrorHandlerFn, ErrorHandlers, ErrorMatcher};
+pub use extract::{FromRequest, Json, Path, Query};
+pub use health::HealthCheck;
+pub use panic_handler::{catch_handler_panic, set_catch_panics};
+pub use rate_limit::{InMemoryRateLimitStore, RateLimit, RateLimitMiddleware, RateLimitStore};
+pub use request_id::{DEFAULT_REQUEST_ID_HEADER, RequestIdMiddleware};
+pub use session::{InMemorySessionStore, RedisSessionStore, SessionMiddleware, SessionStore};
+pub use shutdown::{is_shutting_down, on_shutdown, shutdown_signal};
+pub use types::{
+    AppConfig, AuthInfo, Context, CookieOptions, CorsConfig, Error, HeaderMap, HttpMethod,
+    HttpStatus, IntoResponse, Middleware, Request, Response, ResponseBody, ResponseMetadata, Route,
+    RouteHandler, RouteKind, SameSite, Session, SessionHandle, Validate, WebSocket,
+    WebSocketHandler, WebSocketMessage, find_route,
+};
@@ -0,0 +1,46 @@
+use std::panic::{AssertUnwindSafe, catch_unwind};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::types::{Error, Request, Response};
+
+static CATCH_PANICS: AtomicBool = AtomicBool::new(true);
+
+/// Turn handler panic catching on or off (on by default).
+///
+/// With it off a panicking handler unwinds into the server, which is
+/// useful to fail fast during development.
+pub fn set_catch_panics(enabled: bool) {
+    CATCH_PANICS.store(enabled, Ordering::Relaxed);
+}
+
+/// Whether handler panics are currently caught
+pub fn catch_panics_enabled() -> bool {
+    CATCH_PANICS.load(Ordering::Relaxed)
+}
+
+/// Run a route handler, turning a panic into an `INTERNAL_ERROR` (500)
+/// logged with the request ID, so the connection gets a response instead
+/// of being dropped
+pub fn catch_handler_panic(
+    request: &Request,
+    handler: impl FnOnce() -> Result<Response, Error>,
+) -> Result<Response, Error> {
+    if !catch_panics_enabled() {
+        return handler();
+    }
+    catch_unwind(AssertUnwindSafe(handler)).unwrap_or_else(|payload| {
+        let message = payload
+            .downcast_ref::<&str>()
+            .map(|message| message.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "unknown panic".to_string());
+        tracing::error!(
+            request_id = %request.context.request_id,
+            method = %request.method,
+            path = %request.path,
+            "handler panicked: {}",
+            message
+        );
+        Err(Error::new("INTERNAL_ERROR", "Internal server error"))
+    })
+}
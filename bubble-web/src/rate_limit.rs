@@ -0,0 +1,212 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::types::{Error, Middleware, Request, Response};
+
+/// Token bucket parameters
+#[derive(Debug, Clone, Copy)]
+pub struct RateLimit {
+    /// Maximum number of tokens in a bucket (burst size)
+    pub capacity: u32,
+    /// Tokens added per second
+    pub refill_per_second: f64,
+}
+
+/// Storage for per-key token buckets
+///
+/// Implementations must be safe to share between worker threads. The
+/// in-memory store is used by default; a shared store (e.g. Redis) can be
+/// plugged in to enforce limits across instances.
+pub trait RateLimitStore: Send + Sync {
+    /// Take one token for `key`, returning the time to wait when the bucket is empty
+    fn acquire(&self, key: &str, limit: &RateLimit) -> Result<(), Duration>;
+}
+
+/// How often the in-memory store drops buckets that have refilled
+const SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+impl Bucket {
+    /// Tokens available at `now`, capped at the bucket capacity
+    fn refilled(&self, now: Instant, limit: &RateLimit) -> f64 {
+        let elapsed = now.duration_since(self.updated_at).as_secs_f64();
+        (self.tokens + elapsed * limit.refill_per_second).min(f64::from(limit.capacity))
+    }
+}
+
+#[derive(Debug, Default)]
+struct Buckets {
+    by_key: HashMap<String, Bucket>,
+    last_sweep: Option<Instant>,
+}
+
+/// In-process token bucket store
+///
+/// Buckets that have refilled to capacity are indistinguishable from new
+/// ones, so they are dropped every `SWEEP_INTERVAL` to keep memory bounded
+/// by the number of recently active clients.
+#[derive(Debug, Default)]
+pub struct InMemoryRateLimitStore {
+    buckets: Mutex<Buckets>,
+}
+
+impl InMemoryRateLimitStore {
+    /// Number of clients currently tracked
+    pub fn len(&self) -> usize {
+        self.buckets
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .by_key
+            .len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl RateLimitStore for InMemoryRateLimitStore {
+    fn acquire(&self, key: &str, limit: &RateLimit) -> Result<(), Duration> {
+        let now = Instant::now();
+        let capacity = f64::from(limit.capacity);
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        let last_sweep = *buckets.last_sweep.get_or_insert(now);
+        if now.duration_since(last_sweep) >= SWEEP_INTERVAL {
+            buckets
+                .by_key
+                .retain(|_, bucket| bucket.refilled(now, limit) < capacity);
+            buckets.last_sweep = Some(now);
+        }
+        let bucket = buckets.by_key.entry(key.to_string()).or_insert(Bucket {
+            tokens: capacity,
+            updated_at: now,
+        });
+        bucket.tokens = bucket.refilled(now, limit);
+        bucket.updated_at = now;
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            return Ok(());
+        }
+        if limit.refill_per_second <= 0.0 {
+            return Err(Duration::MAX);
+        }
+        let wait = (1.0 - bucket.tokens) / limit.refill_per_second;
+        Err(Duration::from_secs_f64(wait))
+    }
+}
+
+/// Rate limiting middleware
+///
+/// Applies a token bucket per client: the authenticated user ID when
+/// `Context::auth` is set, otherwise the client IP. The IP is the
+/// connection's `peer_addr` unless `trusted_proxies` is set, in which case
+/// it is read from `ip_header` at the position appended by the outermost
+/// trusted proxy; entries left of it are supplied by the client and ignored.
+/// Requests over the limit are rejected with a `TOO_MANY_REQUESTS` error
+/// whose `Retry-After` detail holds the wait in seconds, sent as the
+/// `Retry-After` response header.
+#[derive(Clone)]
+pub struct RateLimitMiddleware {
+    /// Token bucket parameters
+    pub limit: RateLimit,
+    /// Header listing the client IP and the proxies it passed through
+    pub ip_header: String,
+    /// Number of reverse proxies in front of the server that append to
+    /// `ip_header`; 0 keys on the peer address
+    pub trusted_proxies: usize,
+    store: Arc<dyn RateLimitStore>,
+}
+
+impl std::fmt::Debug for RateLimitMiddleware {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RateLimitMiddleware")
+            .field("limit", &self.limit)
+            .field("ip_header", &self.ip_header)
+            .field("trusted_proxies", &self.trusted_proxies)
+            .finish()
+    }
+}
+
+impl RateLimitMiddleware {
+    pub fn new(capacity: u32, refill_per_second: f64) -> Self {
+        Self {
+            limit: RateLimit {
+                capacity,
+                refill_per_second,
+            },
+            ip_header: "X-Forwarded-For".to_string(),
+            trusted_proxies: 0,
+            store: Arc::new(InMemoryRateLimitStore::default()),
+        }
+    }
+
+    /// Read the forwarded client IP from a different header
+    pub fn ip_header(mut self, ip_header: impl Into<String>) -> Self {
+        self.ip_header = ip_header.into();
+        self
+    }
+
+    /// Trust `ip_header` as appended by this many reverse proxies
+    pub fn trusted_proxies(mut self, hops: usize) -> Self {
+        self.trusted_proxies = hops;
+        self
+    }
+
+    /// Use a custom bucket store
+    pub fn store(mut self, store: Arc<dyn RateLimitStore>) -> Self {
+        self.store = store;
+        self
+    }
+
+    /// Key identifying the client of a request
+    fn client_key(&self, request: &Request) -> String {
+        if let Some(auth) = &request.context.auth {
+            return format!("user:{}", auth.user_id);
+        }
+        match self.client_ip(request) {
+            Some(ip) => format!("ip:{}", ip),
+            None => "ip:unknown".to_string(),
+        }
+    }
+
+    /// Client IP, from the trusted forwarding hop or the peer address
+    fn client_ip(&self, request: &Request) -> Option<String> {
+        if self.trusted_proxies > 0 {
+            let hops: Vec<&str> = request
+                .headers
+                .get_all(&self.ip_header)
+                .iter()
+                .flat_map(|value| value.split(','))
+                .map(str::trim)
+                .collect();
+            if let Some(index) = hops.len().checked_sub(self.trusted_proxies) {
+                let ip = hops[index];
+                if !ip.is_empty() {
+                    return Some(ip.to_string());
+                }
+            }
+        }
+        request.peer_addr.map(|addr| addr.ip().to_string())
+    }
+}
+
+impl Middleware for RateLimitMiddleware {
+    fn pre_process(&self, request: &mut Request) -> Result<(), Error> {
+        let key = self.client_key(request);
+        self.store.acquire(&key, &self.limit).map_err(|wait| {
+            let retry_after = wait.as_secs_f64().ceil().min(u32::MAX as f64) as u64;
+            Error::new("TOO_MANY_REQUESTS", "Rate limit exceeded")
+                .with_detail("Retry-After", retry_after.max(1).to_string())
+        })
+    }
+
+    fn post_process(&self, _response: &mut Response) -> Result<(), Error> {
+        Ok(())
+    }
+}
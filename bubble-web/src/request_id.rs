@@ -0,0 +1,101 @@
+use crate::types::{Error, Middleware, Request, Response};
+
+/// Default header used to carry the request ID
+pub const DEFAULT_REQUEST_ID_HEADER: &str = "X-Request-Id";
+
+/// Longest incoming request ID that is reused
+pub const MAX_REQUEST_ID_LEN: usize = 128;
+
+/// Request ID propagation middleware
+///
+/// Reuses an incoming request ID header when present, otherwise generates a
+/// new UUID. Incoming IDs longer than `MAX_REQUEST_ID_LEN` or containing
+/// anything but visible ASCII are replaced so they cannot forge log lines or
+/// response headers. The ID is stored in `Context::request_id` and echoed on the
+/// response under the same header name.
+#[derive(Debug, Clone)]
+pub struct RequestIdMiddleware {
+    /// Header name used to read and echo the request ID
+    pub header_name: String,
+}
+
+impl Default for RequestIdMiddleware {
+    fn default() -> Self {
+        Self {
+            header_name: DEFAULT_REQUEST_ID_HEADER.to_string(),
+        }
+    }
+}
+
+impl RequestIdMiddleware {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Use a custom header name
+    pub fn header_name(mut self, header_name: impl Into<String>) -> Self {
+        self.header_name = header_name.into();
+        self
+    }
+
+    /// Generate a new request ID
+    pub fn generate_id() -> String {
+        uuid::Uuid::new_v4().to_string()
+    }
+
+    /// Open a tracing span carrying the request ID.
+    /// `ErrorHandlers::dispatch` enters this span for the lifetime of the
+    /// request so every log line emitted by middleware and handlers carries
+    /// the ID; an ID assigned later by `pre_process` is recorded on it then.
+    pub fn span(request: &Request) -> tracing::Span {
+        let span = tracing::info_span!(
+            "request",
+            request_id = tracing::field::Empty,
+            method = %request.method,
+            path = %request.path,
+        );
+        if !request.context.request_id.is_empty() {
+            span.record("request_id", request.context.request_id.as_str());
+        }
+        span
+    }
+
+    fn incoming_id(&self, request: &Request) -> Option<String> {
+        request
+            .header(&self.header_name)
+            .map(str::trim)
+            .filter(|value| {
+                !value.is_empty()
+                    && value.len() <= MAX_REQUEST_ID_LEN
+                    && value.bytes().all(|b| b.is_ascii_graphic())
+            })
+            .map(str::to_string)
+    }
+}
+
+impl Middleware for RequestIdMiddleware {
+    fn pre_process(&self, request: &mut Request) -> Result<(), Error> {
+        if request.context.request_id.is_empty() {
+            request.context.request_id =
+                self.incoming_id(request).unwrap_or_else(Self::generate_id);
+        }
+        Ok(())
+    }
+
+    fn post_process(&self, _response: &mut Response) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn post_process_with_request(
+        &self,
+        request: &Request,
+        response: &mut Response,
+    ) -> Result<(), Error> {
+        if !request.context.request_id.is_empty() {
+            response
+                .headers
+                .set(&self.header_name, request.context.request_id.clone());
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,270 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bubble_db::redis::RedisConnection;
+use redis::aio::MultiplexedConnection;
+
+use crate::types::{CookieOptions, Error, Middleware, Request, Response, Session};
+
+/// Default cookie carrying the session ID
+pub const DEFAULT_SESSION_COOKIE: &str = "bubble_session";
+
+/// Current time in seconds since the Unix epoch
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Persistence for user sessions
+pub trait SessionStore: Send + Sync {
+    /// Load a session, returning `None` when it is missing or expired
+    fn load(&self, session_id: &str) -> Result<Option<Session>, Error>;
+    /// Save a session until its `expires_at`
+    fn save(&self, session: &Session) -> Result<(), Error>;
+    /// Remove a session
+    fn destroy(&self, session_id: &str) -> Result<(), Error>;
+}
+
+fn store_error(err: impl ToString) -> Error {
+    Error::new("SESSION_STORE", err.to_string())
+}
+
+/// Whether the session's `expires_at` has passed
+fn is_expired(session: &Session) -> bool {
+    session.expires_at <= now_secs()
+}
+
+/// In-process session store
+///
+/// Expired sessions are never returned and are purged when looked up.
+#[derive(Debug, Default)]
+pub struct InMemorySessionStore {
+    sessions: Mutex<HashMap<String, Session>>,
+}
+
+impl InMemorySessionStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Remove all expired sessions
+    pub fn purge_expired(&self) {
+        let mut sessions = self.sessions.lock().unwrap_or_else(|e| e.into_inner());
+        sessions.retain(|_, session| !is_expired(session));
+    }
+}
+
+impl SessionStore for InMemorySessionStore {
+    fn load(&self, session_id: &str) -> Result<Option<Session>, Error> {
+        let mut sessions = self.sessions.lock().unwrap_or_else(|e| e.into_inner());
+        match sessions.get(session_id) {
+            Some(session) if is_expired(session) => {
+                sessions.remove(session_id);
+                Ok(None)
+            }
+            Some(session) => Ok(Some(session.clone())),
+            None => Ok(None),
+        }
+    }
+
+    fn save(&self, session: &Session) -> Result<(), Error> {
+        let mut sessions = self.sessions.lock().unwrap_or_else(|e| e.into_inner());
+        if is_expired(session) {
+            sessions.remove(&session.id);
+        } else {
+            sessions.insert(session.id.clone(), session.clone());
+        }
+        Ok(())
+    }
+
+    fn destroy(&self, session_id: &str) -> Result<(), Error> {
+        let mut sessions = self.sessions.lock().unwrap_or_else(|e| e.into_inner());
+        sessions.remove(session_id);
+        Ok(())
+    }
+}
+
+/// Redis-backed session store
+///
+/// Sessions are stored as JSON under `<prefix><session id>` with a TTL
+/// derived from `expires_at`, so Redis evicts them on expiry.
+///
+/// Commands share one multiplexed async connection, opened on first use.
+/// `SessionStore` is synchronous, so each call hands its worker thread over
+/// to blocking work while the command runs; this needs a multi-threaded
+/// Tokio runtime.
+#[derive(Clone)]
+pub struct RedisSessionStore {
+    connection: Arc<RedisConnection>,
+    multiplexed: Arc<tokio::sync::OnceCell<MultiplexedConnection>>,
+    prefix: String,
+}
+
+impl std::fmt::Debug for RedisSessionStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RedisSessionStore")
+            .field("connection", &self.connection)
+            .field("connected", &self.multiplexed.initialized())
+            .field("prefix", &self.prefix)
+            .finish()
+    }
+}
+
+impl RedisSessionStore {
+    pub fn new(connection: Arc<RedisConnection>) -> Self {
+        Self {
+            connection,
+            multiplexed: Arc::new(tokio::sync::OnceCell::new()),
+            prefix: "session:".to_string(),
+        }
+    }
+
+    /// Use a custom key prefix
+    pub fn prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+
+    fn key(&self, session_id: &str) -> String {
+        format!("{}{}", self.prefix, session_id)
+    }
+
+    /// Run a command on the shared multiplexed connection
+    fn query<T: redis::FromRedisValue>(&self, cmd: &redis::Cmd) -> Result<T, Error> {
+        let runtime = tokio::runtime::Handle::try_current().map_err(store_error)?;
+        if runtime.runtime_flavor() != tokio::runtime::RuntimeFlavor::MultiThread {
+            return Err(store_error(
+                "RedisSessionStore requires a multi-threaded Tokio runtime",
+            ));
+        }
+        tokio::task::block_in_place(|| {
+            runtime.block_on(async {
+                let mut conn = self
+                    .multiplexed
+                    .get_or_try_init(|| self.connection.get_multiplexed_connection())
+                    .await
+                    .map_err(store_error)?
+                    .clone();
+                cmd.query_async(&mut conn).await.map_err(store_error)
+            })
+        })
+    }
+}
+
+impl SessionStore for RedisSessionStore {
+    fn load(&self, session_id: &str) -> Result<Option<Session>, Error> {
+        let value: Option<String> = self.query(redis::cmd("GET").arg(self.key(session_id)))?;
+        let session = match value {
+            Some(json) => serde_json::from_str::<Session>(&json).map_err(store_error)?,
+            None => return Ok(None),
+        };
+        if is_expired(&session) {
+            return Ok(None);
+        }
+        Ok(Some(session))
+    }
+
+    fn save(&self, session: &Session) -> Result<(), Error> {
+        let ttl = session.expires_at.saturating_sub(now_secs());
+        if ttl == 0 {
+            return self.destroy(&session.id);
+        }
+        let json = serde_json::to_string(session).map_err(store_error)?;
+        self.query(
+            redis::cmd("SET")
+                .arg(self.key(&session.id))
+                .arg(json)
+                .arg("EX")
+                .arg(ttl),
+        )
+    }
+
+    fn destroy(&self, session_id: &str) -> Result<(), Error> {
+        self.query(redis::cmd("DEL").arg(self.key(session_id)))
+    }
+}
+
+/// Session middleware
+///
+/// Loads the session named by the session cookie into `Context::session`
+/// before the handler runs, and persists it afterwards if the handler
+/// changed it. Handlers change the session through the shared handle, e.g.
+/// with `Context::set_session`; a session removed from the context by the
+/// handler is destroyed in the store.
+#[derive(Clone)]
+pub struct SessionMiddleware {
+    store: Arc<dyn SessionStore>,
+    /// Name of the session cookie
+    pub cookie_name: String,
+}
+
+impl std::fmt::Debug for SessionMiddleware {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SessionMiddleware")
+            .field("cookie_name", &self.cookie_name)
+            .finish()
+    }
+}
+
+impl SessionMiddleware {
+    pub fn new(store: Arc<dyn SessionStore>) -> Self {
+        Self {
+            store,
+            cookie_name: DEFAULT_SESSION_COOKIE.to_string(),
+        }
+    }
+
+    /// Use a custom cookie name
+    pub fn cookie_name(mut self, cookie_name: impl Into<String>) -> Self {
+        self.cookie_name = cookie_name.into();
+        self
+    }
+
+    /// Session ID sent by the client
+    fn session_id(&self, request: &Request) -> Option<String> {
+        request.cookie(&self.cookie_name)
+    }
+}
+
+impl Middleware for SessionMiddleware {
+    fn pre_process(&self, request: &mut Request) -> Result<(), Error> {
+        if let Some(session_id) = self.session_id(request) {
+            let session = self.store.load(&session_id)?;
+            request.context.loaded_session = session.clone();
+            request.context.set_session(session);
+        }
+        Ok(())
+    }
+
+    fn post_process(&self, _response: &mut Response) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn post_process_with_request(
+        &self,
+        request: &Request,
+        response: &mut Response,
+    ) -> Result<(), Error> {
+        match request.context.session() {
+            // Unchanged sessions are neither saved nor sent again
+            Some(session) if request.context.loaded_session.as_ref() == Some(&session) => {}
+            Some(session) => {
+                self.store.save(&session)?;
+                let max_age = session.expires_at.saturating_sub(now_secs());
+                let options = CookieOptions::new().path("/").max_age(max_age).http_only();
+                response.set_cookie(&self.cookie_name, &session.id, &options);
+            }
+            None => {
+                if let Some(session_id) = self.session_id(request) {
+                    self.store.destroy(&session_id)?;
+                    let options = CookieOptions::new().path("/").max_age(0).http_only();
+                    response.set_cookie(&self.cookie_name, "", &options);
+                }
+            }
+        }
+        Ok(())
+    }
+}
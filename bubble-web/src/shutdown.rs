@@ -0,0 +1,53 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Mutex, OnceLock};
+
+use tokio::sync::watch;
+
+type ShutdownHook = Box<dyn FnOnce() -> Pin<Box<dyn Future<Output = ()> + Send>> + Send>;
+
+static SHUTDOWN_HOOKS: Mutex<Vec<ShutdownHook>> = Mutex::new(Vec::new());
+static SHUTDOWN: OnceLock<watch::Sender<bool>> = OnceLock::new();
+
+fn shutdown_sender() -> &'static watch::Sender<bool> {
+    SHUTDOWN.get_or_init(|| watch::channel(false).0)
+}
+
+/// Run `hook` once shutdown begins, e.g. to flush buffers or deregister
+/// from service discovery; hooks run in registration order
+pub fn on_shutdown<F, Fut>(hook: F)
+where
+    F: FnOnce() -> Fut + Send + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    let hook: ShutdownHook = Box::new(move || Box::pin(hook()));
+    SHUTDOWN_HOOKS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .push(hook);
+}
+
+/// Whether shutdown has begun
+pub fn is_shutting_down() -> bool {
+    *shutdown_sender().borrow()
+}
+
+/// Resolves once shutdown begins; servers should stop accepting new
+/// connections and let `main` return after in-flight requests complete
+pub async fn shutdown_signal() {
+    let mut shutdown = shutdown_sender().subscribe();
+    let _ = shutdown.wait_for(|started| *started).await;
+}
+
+/// Start shutdown, waking every `shutdown_signal` waiter
+pub fn begin_shutdown() {
+    shutdown_sender().send_replace(true);
+}
+
+/// Run the registered `on_shutdown` hooks, each at most once
+pub async fn run_shutdown_hooks() {
+    let hooks = std::mem::take(&mut *SHUTDOWN_HOOKS.lock().unwrap_or_else(|e| e.into_inner()));
+    for hook in hooks {
+        hook().await;
+    }
+}
@@ -0,0 +1,896 @@
+use std::collections::HashMap;
+use std::fmt::{Debug, Display};
+
+/// HTTP Request structure
+#[derive(Debug, Clone, Default)]
+pub struct Request {
+    /// HTTP method
+    pub method: HttpMethod,
+    /// Request path
+    pub path: String,
+    /// Query parameters
+    pub query_params: HashMap<String, String>,
+    /// Path parameters
+    pub path_params: HashMap<String, String>,
+    /// Request headers
+    pub headers: HeaderMap,
+    /// Request body (raw bytes)
+    pub body: Vec<u8>,
+    /// Address of the connected peer, when the server knows it
+    pub peer_addr: Option<std::net::SocketAddr>,
+    /// Request context
+    pub context: Context,
+}
+
+impl Request {
+    /// Get the first value of a header using case-insensitive name matching
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get_one(name)
+    }
+
+    /// Get the `Content-Type` header
+    pub fn content_type(&self) -> Option<&str> {
+        self.header("Content-Type")
+    }
+
+    /// Get the parsed `Content-Length` header
+    pub fn content_length(&self) -> Option<u64> {
+        self.header("Content-Length")
+            .and_then(|value| value.trim().parse().ok())
+    }
+
+    /// Get the token from an `Authorization: Bearer <token>` header
+    pub fn bearer_token(&self) -> Option<&str> {
+        let value = self.header("Authorization")?.trim();
+        let (scheme, token) = value.split_once(' ')?;
+        if !scheme.eq_ignore_ascii_case("Bearer") {
+            return None;
+        }
+        let token = token.trim();
+        if token.is_empty() { None } else { Some(token) }
+    }
+
+    /// Parse the `Cookie` headers into a name to value map
+    pub fn cookies(&self) -> HashMap<String, String> {
+        self.headers
+            .get_all("Cookie")
+            .iter()
+            .flat_map(|header| header.split(';'))
+            .filter_map(|pair| {
+                let (name, value) = pair.split_once('=')?;
+                let name = name.trim();
+                if name.is_empty() {
+                    return None;
+                }
+                Some((name.to_string(), value.trim().trim_matches('"').to_string()))
+            })
+            .collect()
+    }
+
+    /// Get a single cookie value
+    pub fn cookie(&self, name: &str) -> Option<String> {
+        self.cookies().remove(name)
+    }
+
+    /// Deserialize a JSON body.
+    ///
+    /// Failures are `BAD_REQUEST` with the serde message under the `reason`
+    /// detail and its position under `line` and `column`.
+    pub fn json<T: serde::de::DeserializeOwned>(&self) -> Result<T, Error> {
+        serde_json::from_slice(&self.body).map_err(|e| {
+            Error::new("BAD_REQUEST", format!("Invalid JSON body: {}", e))
+                .with_detail("reason", e.to_string())
+                .with_detail("line", e.line().to_string())
+                .with_detail("column", e.column().to_string())
+        })
+    }
+
+    /// The body as UTF-8 text; invalid UTF-8 is a `BAD_REQUEST`
+    pub fn text(&self) -> Result<String, Error> {
+        String::from_utf8(self.body.clone()).map_err(|e| {
+            Error::new("BAD_REQUEST", "Request body is not valid UTF-8")
+                .with_detail("reason", e.to_string())
+        })
+    }
+
+    /// Deserialize an `application/x-www-form-urlencoded` body.
+    ///
+    /// Percent-escapes and `+` are decoded; missing required fields are
+    /// reported as `BAD_REQUEST`.
+    pub fn form<T: serde::de::DeserializeOwned>(&self) -> Result<T, Error> {
+        serde_urlencoded::from_bytes(&self.body)
+            .map_err(|e| Error::new("BAD_REQUEST", format!("Invalid form body: {}", e)))
+    }
+
+    /// Deserialize the body according to its `Content-Type`: URL-encoded
+    /// forms and JSON are supported, a missing content type is read as JSON
+    pub fn body_as<T: serde::de::DeserializeOwned>(&self) -> Result<T, Error> {
+        let media_type = self.content_type().map(|value| {
+            value
+                .split(';')
+                .next()
+                .unwrap_or("")
+                .trim()
+                .to_ascii_lowercase()
+        });
+        match media_type.as_deref() {
+            Some("application/x-www-form-urlencoded") => self.form(),
+            None | Some("application/json") => self.json(),
+            Some(other) if other.ends_with("+json") => self.json(),
+            Some(other) => Err(Error::new(
+                "UNSUPPORTED_MEDIA_TYPE",
+                format!("Unsupported request body content type '{}'", other),
+            )),
+        }
+    }
+}
+
+/// HTTP header map holding every value of repeated headers.
+///
+/// Names are matched case-insensitively and stored lowercased.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HeaderMap {
+    entries: HashMap<String, Vec<String>>,
+}
+
+impl HeaderMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// First value of a header
+    pub fn get_one(&self, name: &str) -> Option<&str> {
+        self.entries
+            .get(&name.to_ascii_lowercase())
+            .and_then(|values| values.first())
+            .map(String::as_str)
+    }
+
+    /// All values of a header, in insertion order
+    pub fn get_all(&self, name: &str) -> &[String] {
+        self.entries
+            .get(&name.to_ascii_lowercase())
+            .map(Vec::as_slice)
+            .unwrap_or(&[])
+    }
+
+    /// Add a value, keeping existing values of the header
+    pub fn append(&mut self, name: impl AsRef<str>, value: impl Into<String>) {
+        self.entries
+            .entry(name.as_ref().to_ascii_lowercase())
+            .or_default()
+            .push(value.into());
+    }
+
+    /// Set a header, replacing any existing values
+    pub fn set(&mut self, name: impl AsRef<str>, value: impl Into<String>) {
+        self.entries
+            .insert(name.as_ref().to_ascii_lowercase(), vec![value.into()]);
+    }
+
+    /// Remove a header, returning its values
+    pub fn remove(&mut self, name: &str) -> Vec<String> {
+        self.entries
+            .remove(&name.to_ascii_lowercase())
+            .unwrap_or_default()
+    }
+
+    /// Whether the header is present
+    pub fn contains(&self, name: &str) -> bool {
+        self.entries.contains_key(&name.to_ascii_lowercase())
+    }
+
+    /// Iterate over every (name, value) pair
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.entries.iter().flat_map(|(name, values)| {
+            values
+                .iter()
+                .map(move |value| (name.as_str(), value.as_str()))
+        })
+    }
+
+    /// Number of distinct header names
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+impl<K: AsRef<str>, V: Into<String>> FromIterator<(K, V)> for HeaderMap {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut headers = Self::new();
+        for (name, value) in iter {
+            headers.append(name, value);
+        }
+        headers
+    }
+}
+
+/// HTTP Response structure
+#[derive(Debug, Clone, Default)]
+pub struct Response {
+    /// HTTP status code
+    pub status: HttpStatus,
+    /// Response headers
+    pub headers: HeaderMap,
+    /// Response body
+    pub body: ResponseBody,
+    /// Response metadata
+    pub metadata: ResponseMetadata,
+}
+
+/// `SameSite` cookie attribute
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SameSite {
+    Strict,
+    Lax,
+    None,
+}
+
+/// Attributes of a cookie set with `Response::set_cookie`
+#[derive(Debug, Clone, Default)]
+pub struct CookieOptions {
+    /// `Max-Age` in seconds
+    pub max_age: Option<u64>,
+    /// `HttpOnly` flag
+    pub http_only: bool,
+    /// `Secure` flag
+    pub secure: bool,
+    /// `SameSite` attribute
+    pub same_site: Option<SameSite>,
+    /// `Path` attribute
+    pub path: Option<String>,
+}
+
+impl CookieOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set `Max-Age` in seconds
+    pub fn max_age(mut self, seconds: u64) -> Self {
+        self.max_age = Some(seconds);
+        self
+    }
+
+    /// Set the `HttpOnly` flag
+    pub fn http_only(mut self) -> Self {
+        self.http_only = true;
+        self
+    }
+
+    /// Set the `Secure` flag
+    pub fn secure(mut self) -> Self {
+        self.secure = true;
+        self
+    }
+
+    /// Set the `SameSite` attribute
+    pub fn same_site(mut self, same_site: SameSite) -> Self {
+        self.same_site = Some(same_site);
+        self
+    }
+
+    /// Set the `Path` attribute
+    pub fn path(mut self, path: impl Into<String>) -> Self {
+        self.path = Some(path.into());
+        self
+    }
+}
+
+impl Response {
+    /// Response with status 200 and the given body
+    pub fn ok(body: ResponseBody) -> Self {
+        Self {
+            status: HttpStatus {
+                code: 200,
+                message: "OK".to_string(),
+            },
+            body,
+            ..Default::default()
+        }
+    }
+
+    /// JSON error response `{"code", "message", "details"}` with the
+    /// status derived from the error code
+    pub fn error(error: Error) -> Self {
+        crate::error_handler::default_error_response(error)
+    }
+
+    /// Add a `Set-Cookie` header; repeated calls set several cookies
+    pub fn set_cookie(&mut self, name: &str, value: &str, options: &CookieOptions) {
+        let mut cookie = format!("{}={}", name, value);
+        if let Some(path) = &options.path {
+            cookie.push_str(&format!("; Path={}", path));
+        }
+        if let Some(max_age) = options.max_age {
+            cookie.push_str(&format!("; Max-Age={}", max_age));
+        }
+        if let Some(same_site) = options.same_site {
+            let same_site = match same_site {
+                SameSite::Strict => "Strict",
+                SameSite::Lax => "Lax",
+                SameSite::None => "None",
+            };
+            cookie.push_str(&format!("; SameSite={}", same_site));
+        }
+        if options.secure {
+            cookie.push_str("; Secure");
+        }
+        if options.http_only {
+            cookie.push_str("; HttpOnly");
+        }
+        self.headers.append("Set-Cookie", cookie);
+    }
+}
+
+/// Response body enum supporting multiple formats
+#[derive(Debug, Clone, Default)]
+pub enum ResponseBody {
+    /// Text response
+    Text(String),
+    /// JSON response
+    Json(serde_json::Value),
+    /// Binary data
+    Binary(Vec<u8>),
+    /// Empty response
+    #[default]
+    Empty,
+}
+
+/// Response metadata
+#[derive(Debug, Clone, Default)]
+pub struct ResponseMetadata {
+    /// Response duration in milliseconds
+    pub duration: u64,
+    /// Whether response is cached
+    pub cached: bool,
+    /// Additional metadata
+    pub extra: HashMap<String, String>,
+}
+
+/// HTTP method enumeration
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub enum HttpMethod {
+    #[default]
+    GET,
+    POST,
+    PUT,
+    DELETE,
+    PATCH,
+    HEAD,
+    OPTIONS,
+    TRACE,
+    CONNECT,
+    CUSTOM(String),
+}
+
+impl HttpMethod {
+    /// Method name as sent on the request line
+    pub fn as_str(&self) -> &str {
+        match self {
+            HttpMethod::GET => "GET",
+            HttpMethod::POST => "POST",
+            HttpMethod::PUT => "PUT",
+            HttpMethod::DELETE => "DELETE",
+            HttpMethod::PATCH => "PATCH",
+            HttpMethod::HEAD => "HEAD",
+            HttpMethod::OPTIONS => "OPTIONS",
+            HttpMethod::TRACE => "TRACE",
+            HttpMethod::CONNECT => "CONNECT",
+            HttpMethod::CUSTOM(method) => method,
+        }
+    }
+}
+
+impl Display for HttpMethod {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Map a method name to its variant; names are case-sensitive as in HTTP,
+/// so anything other than the standard upper-case verbs becomes `CUSTOM`
+impl From<&str> for HttpMethod {
+    fn from(method: &str) -> Self {
+        match method {
+            "GET" => HttpMethod::GET,
+            "POST" => HttpMethod::POST,
+            "PUT" => HttpMethod::PUT,
+            "DELETE" => HttpMethod::DELETE,
+            "PATCH" => HttpMethod::PATCH,
+            "HEAD" => HttpMethod::HEAD,
+            "OPTIONS" => HttpMethod::OPTIONS,
+            "TRACE" => HttpMethod::TRACE,
+            "CONNECT" => HttpMethod::CONNECT,
+            other => HttpMethod::CUSTOM(other.to_string()),
+        }
+    }
+}
+
+impl std::str::FromStr for HttpMethod {
+    type Err = std::convert::Infallible;
+
+    fn from_str(method: &str) -> Result<Self, Self::Err> {
+        Ok(HttpMethod::from(method))
+    }
+}
+
+/// Serialized as the method name, e.g. `"GET"`, for route tables
+impl serde::Serialize for HttpMethod {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for HttpMethod {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let method = String::deserialize(deserializer)?;
+        Ok(HttpMethod::from(method.as_str()))
+    }
+}
+
+/// HTTP status code wrapper
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct HttpStatus {
+    /// Status code number
+    pub code: u16,
+    /// Status message
+    pub message: String,
+}
+
+impl HttpStatus {
+    /// Status with the standard reason phrase for `code`
+    pub fn new(code: u16) -> Self {
+        Self {
+            code,
+            message: crate::error_handler::status_reason(code).to_string(),
+        }
+    }
+}
+
+/// Request context for passing contextual information
+#[derive(Debug, Clone, Default)]
+pub struct Context {
+    /// Unique request identifier
+    pub request_id: String,
+    /// User session information, shared with the handler so that changes
+    /// it makes are persisted by the session middleware
+    pub session: SessionHandle,
+    /// Authentication information
+    pub auth: Option<AuthInfo>,
+    /// Locale information
+    pub locale: String,
+    /// Custom context data
+    pub data: HashMap<String, String>,
+    /// Session as loaded by the session middleware, compared against
+    /// `session` afterwards to tell whether it needs saving
+    pub(crate) loaded_session: Option<Session>,
+}
+
+/// Mutable session shared by clones of a request's `Context`
+pub type SessionHandle = std::sync::Arc<std::sync::Mutex<Option<Session>>>;
+
+/// User session information
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Session {
+    /// Session ID
+    pub id: String,
+    /// User ID
+    pub user_id: String,
+    /// Session creation timestamp
+    pub created_at: u64,
+    /// Session expiration timestamp
+    pub expires_at: u64,
+    /// Session data storage
+    pub data: HashMap<String, String>,
+}
+
+/// Authentication information
+#[derive(Debug, Clone)]
+pub struct AuthInfo {
+    /// Authenticated user ID
+    pub user_id: String,
+    /// User roles
+    pub roles: Vec<String>,
+    /// User permissions
+    pub permissions: Vec<String>,
+    /// Authentication token
+    pub token: String,
+}
+
+/// Error type for framework operations
+#[derive(Debug, Clone)]
+pub struct Error {
+    /// Error code
+    pub code: String,
+    /// Error message
+    pub message: String,
+    /// Error details
+    pub details: Option<HashMap<String, String>>,
+}
+
+impl Error {
+    pub fn new(code: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            code: code.into(),
+            message: message.into(),
+            details: None,
+        }
+    }
+
+    /// Attach a detail entry to the error
+    pub fn with_detail(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.details
+            .get_or_insert_with(HashMap::new)
+            .insert(key.into(), value.into());
+        self
+    }
+
+    /// Convert a database error, choosing the code from its kind: missing rows
+    /// become `NOT_FOUND`, constraint violations `CONFLICT` and connection
+    /// failures `SERVICE_UNAVAILABLE`.
+    ///
+    /// ```rust,ignore
+    /// let user = User::find_by_id(id).await.map_err(Error::from_db)?;
+    /// ```
+    pub fn from_db(error: impl Into<String>) -> Self {
+        let error = error.into();
+        let code = match bubble_db::DbErrorKind::of(&error) {
+            bubble_db::DbErrorKind::NotFound => "NOT_FOUND",
+            bubble_db::DbErrorKind::ConstraintViolation => "CONFLICT",
+            bubble_db::DbErrorKind::Connection => "SERVICE_UNAVAILABLE",
+            bubble_db::DbErrorKind::Other => "DATABASE_ERROR",
+        };
+        Self::new(code, error)
+    }
+
+    /// HTTP status code derived from the error code
+    pub fn status(&self) -> u16 {
+        match self.code.as_str() {
+            "BAD_REQUEST" => 400,
+            "UNAUTHORIZED" | "TOKEN_EXPIRED" | "TOKEN_INVALID" => 401,
+            "FORBIDDEN" => 403,
+            "NOT_FOUND" => 404,
+            "METHOD_NOT_ALLOWED" => 405,
+            "CONFLICT" => 409,
+            "PAYLOAD_TOO_LARGE" => 413,
+            "UNSUPPORTED_MEDIA_TYPE" => 415,
+            "UNPROCESSABLE_ENTITY" | "VALIDATION_FAILED" => 422,
+            "TOO_MANY_REQUESTS" => 429,
+            "SERVICE_UNAVAILABLE" => 503,
+            _ => 500,
+        }
+    }
+}
+
+impl Context {
+    /// Snapshot of the current session
+    pub fn session(&self) -> Option<Session> {
+        self.session
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Replace the session; `None` logs the client out
+    pub fn set_session(&self, session: Option<Session>) {
+        *self.session.lock().unwrap_or_else(|e| e.into_inner()) = session;
+    }
+}
+
+impl AuthInfo {
+    /// Whether the user has the given role
+    pub fn has_role(&self, role: &str) -> bool {
+        self.roles.iter().any(|r| r == role)
+    }
+
+    /// Whether the user has the given permission
+    pub fn has_permission(&self, permission: &str) -> bool {
+        self.permissions.iter().any(|p| p == permission)
+    }
+}
+
+/// Declarative validation, implemented by `#[derive(Validate)]`
+pub trait Validate {
+    /// Check every rule, returning a `VALIDATION_FAILED` error listing each
+    /// failing field in `details`
+    fn validate(&self) -> Result<(), Error>;
+}
+
+/// Handler invoked by the router for a matched route
+pub type RouteHandler = std::sync::Arc<dyn Fn(&Request) -> Result<Response, Error> + Send + Sync>;
+
+/// Handler run with the upgraded connection of a WebSocket route
+pub type WebSocketHandler = std::sync::Arc<
+    dyn Fn(WebSocket) -> std::pin::Pin<Box<dyn std::future::Future<Output = ()> + Send>>
+        + Send
+        + Sync,
+>;
+
+/// Route configuration
+#[derive(Clone)]
+pub struct Route {
+    /// HTTP method
+    pub method: HttpMethod,
+    /// Route path pattern
+    pub path: String,
+    /// Handler function name
+    pub handler: String,
+    /// Middleware chain
+    pub middleware: Vec<String>,
+    /// Route kind
+    pub kind: RouteKind,
+    /// Dispatch closure calling the handler
+    pub dispatch: Option<RouteHandler>,
+    /// Handler taking over the connection of a `RouteKind::WebSocket` route
+    pub websocket: Option<WebSocketHandler>,
+    /// Body size limit overriding the server's `max_body_size`
+    pub max_body_size: Option<usize>,
+}
+
+impl Debug for Route {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Route")
+            .field("method", &self.method)
+            .field("path", &self.path)
+            .field("handler", &self.handler)
+            .field("middleware", &self.middleware)
+            .field("kind", &self.kind)
+            .field("dispatch", &self.dispatch.is_some())
+            .field("websocket", &self.websocket.is_some())
+            .field("max_body_size", &self.max_body_size)
+            .finish()
+    }
+}
+
+impl Route {
+    /// Parameters captured when `path` matches the route's pattern.
+    ///
+    /// `:name` matches a single segment and a trailing `*name` matches the
+    /// rest of the path, e.g. `/files/*path` captures `a/b/c.txt` from
+    /// `/files/a/b/c.txt` into `path`. Empty segments are ignored, so a
+    /// trailing slash makes no difference.
+    pub fn match_path(&self, path: &str) -> Option<HashMap<String, String>> {
+        match_route_path(&self.path, path).map(|(params, _)| params)
+    }
+}
+
+/// The route for `method` and `path` along with its captured parameters.
+///
+/// When several patterns match, static segments win over `:param`
+/// segments, which win over a `*wildcard`, comparing segment by segment
+/// from the start of the path; on a tie the earlier route wins.
+pub fn find_route<'a>(
+    routes: &'a [Route],
+    method: &HttpMethod,
+    path: &str,
+) -> Option<(&'a Route, HashMap<String, String>)> {
+    let mut best: Option<(&Route, HashMap<String, String>, Vec<u8>)> = None;
+    for route in routes.iter().filter(|route| &route.method == method) {
+        let Some((params, rank)) = match_route_path(&route.path, path) else {
+            continue;
+        };
+        if best
+            .as_ref()
+            .is_none_or(|(_, _, best_rank)| rank < *best_rank)
+        {
+            best = Some((route, params, rank));
+        }
+    }
+    best.map(|(route, params, _)| (route, params))
+}
+
+/// Match `path` against `pattern`, returning the captures and the rank of
+/// each pattern segment (0 static, 1 `:param`, 2 `*wildcard`)
+fn match_route_path(pattern: &str, path: &str) -> Option<(HashMap<String, String>, Vec<u8>)> {
+    let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+    let mut pattern_segments = pattern.split('/').filter(|s| !s.is_empty()).peekable();
+    let mut params = HashMap::new();
+    let mut rank = Vec::new();
+    let mut index = 0;
+    while let Some(segment) = pattern_segments.next() {
+        if let Some(name) = segment.strip_prefix('*') {
+            // Only valid as the last segment; captures the rest, possibly empty
+            if pattern_segments.peek().is_some() {
+                return None;
+            }
+            params.insert(
+                name.to_string(),
+                segments[index.min(segments.len())..].join("/"),
+            );
+            rank.push(2);
+            return Some((params, rank));
+        }
+        let value = segments.get(index)?;
+        if let Some(name) = segment.strip_prefix(':') {
+            params.insert(name.to_string(), value.to_string());
+            rank.push(1);
+        } else if segment == *value {
+            rank.push(0);
+        } else {
+            return None;
+        }
+        index += 1;
+    }
+    (index == segments.len()).then_some((params, rank))
+}
+
+/// Conversion of handler return values into a response
+pub trait IntoResponse {
+    fn into_response(self) -> Result<Response, Error>;
+}
+
+impl IntoResponse for Response {
+    fn into_response(self) -> Result<Response, Error> {
+        Ok(self)
+    }
+}
+
+impl IntoResponse for String {
+    fn into_response(self) -> Result<Response, Error> {
+        Ok(Response::ok(ResponseBody::Text(self)))
+    }
+}
+
+impl IntoResponse for &str {
+    fn into_response(self) -> Result<Response, Error> {
+        Ok(Response::ok(ResponseBody::Text(self.to_string())))
+    }
+}
+
+impl IntoResponse for serde_json::Value {
+    fn into_response(self) -> Result<Response, Error> {
+        Ok(Response::ok(ResponseBody::Json(self)))
+    }
+}
+
+impl<T: IntoResponse, E: Into<Error>> IntoResponse for Result<T, E> {
+    fn into_response(self) -> Result<Response, Error> {
+        self.map_err(Into::into)?.into_response()
+    }
+}
+
+/// Override the status of the inner response, e.g.
+/// `(HttpStatus::new(201), json!({"id": id}))`
+impl<T: IntoResponse> IntoResponse for (HttpStatus, T) {
+    fn into_response(self) -> Result<Response, Error> {
+        let (status, body) = self;
+        let mut response = body.into_response()?;
+        response.status = status;
+        Ok(response)
+    }
+}
+
+/// Same as `(HttpStatus, T)` with the standard reason phrase, e.g. `(201, body)`
+impl<T: IntoResponse> IntoResponse for (u16, T) {
+    fn into_response(self) -> Result<Response, Error> {
+        (HttpStatus::new(self.0), self.1).into_response()
+    }
+}
+
+/// Kind of endpoint a route serves
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RouteKind {
+    /// Plain HTTP request/response handler
+    #[default]
+    Http,
+    /// WebSocket endpoint upgraded from a GET request
+    WebSocket,
+}
+
+/// WebSocket message
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WebSocketMessage {
+    /// UTF-8 text frame
+    Text(String),
+    /// Binary frame
+    Binary(Vec<u8>),
+    /// Ping frame
+    Ping(Vec<u8>),
+    /// Pong frame
+    Pong(Vec<u8>),
+    /// Close frame
+    Close,
+}
+
+/// WebSocket connection handed to `#[websocket]` handlers
+#[derive(Debug)]
+pub struct WebSocket {
+    /// Messages received from the client
+    incoming: tokio::sync::mpsc::Receiver<WebSocketMessage>,
+    /// Messages sent to the client
+    outgoing: tokio::sync::mpsc::Sender<WebSocketMessage>,
+    /// Request that initiated the upgrade
+    pub request: Request,
+}
+
+impl WebSocket {
+    pub fn new(
+        request: Request,
+        incoming: tokio::sync::mpsc::Receiver<WebSocketMessage>,
+        outgoing: tokio::sync::mpsc::Sender<WebSocketMessage>,
+    ) -> Self {
+        Self {
+            incoming,
+            outgoing,
+            request,
+        }
+    }
+
+    /// Receive the next message, returning `None` once the connection is closed
+    pub async fn recv(&mut self) -> Option<WebSocketMessage> {
+        match self.incoming.recv().await {
+            Some(WebSocketMessage::Close) | None => None,
+            Some(message) => Some(message),
+        }
+    }
+
+    /// Send a message to the client, waiting while the outgoing queue is full
+    pub async fn send(&self, message: WebSocketMessage) -> Result<(), Error> {
+        self.outgoing.send(message).await.map_err(|_| Error {
+            code: "WEBSOCKET_CLOSED".to_string(),
+            message: "WebSocket connection is closed".to_string(),
+            details: None,
+        })
+    }
+
+    /// Close the connection
+    pub async fn close(&self) -> Result<(), Error> {
+        self.send(WebSocketMessage::Close).await
+    }
+}
+
+/// Application configuration
+#[derive(Debug, Clone, Default)]
+pub struct AppConfig {
+    /// Server host address
+    pub host: String,
+    /// Server port
+    pub port: u16,
+    /// Database connection URL
+    pub database_url: String,
+    /// Redis connection URL
+    pub redis_url: String,
+    /// JWT secret key
+    pub jwt_secret: String,
+    /// CORS configuration
+    pub cors: CorsConfig,
+    /// Request body size limits
+    pub body_limits: crate::body_limit::BodyLimits,
+}
+
+/// CORS configuration
+#[derive(Debug, Clone, Default)]
+pub struct CorsConfig {
+    /// Allowed origins
+    pub allowed_origins: Vec<String>,
+    /// Allowed methods
+    pub allowed_methods: Vec<String>,
+    /// Allowed headers
+    pub allowed_headers: Vec<String>,
+    /// Allow credentials
+    pub allow_credentials: bool,
+}
+
+/// Middleware trait definition
+pub trait Middleware: Send + Sync {
+    /// Process request before handler
+    fn pre_process(&self, request: &mut Request) -> Result<(), Error>;
+    /// Process response after handler
+    fn post_process(&self, response: &mut Response) -> Result<(), Error>;
+    /// Process response after handler with access to the originating request
+    fn post_process_with_request(
+        &self,
+        _request: &Request,
+        response: &mut Response,
+    ) -> Result<(), Error> {
+        self.post_process(response)
+    }
+}
+
+/// Database result type alias
+pub type DbResult<T> = Result<T, String>;
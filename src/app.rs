@@ -0,0 +1,177 @@
+// Programmatic application setup, an alternative to the #[bubble] attribute
+#![allow(clippy::result_large_err)]
+
+use crate::types::{FrameworkError, FrameworkResult};
+use std::fmt::Display;
+use std::future::Future;
+
+/// Builder for an `App`
+///
+/// ```rust,ignore
+/// App::builder()
+///     .port(8080)
+///     .host("0.0.0.0")
+///     .workers(4)
+///     .build()?
+///     .run(async { serve().await })?;
+/// ```
+#[derive(Debug, Clone)]
+pub struct AppBuilder {
+    port: u16,
+    host: String,
+    workers: usize,
+    current_thread: bool,
+    log_level: String,
+    log_format: String,
+    database: Option<bubble_db::DatabaseConfig>,
+}
+
+impl Default for AppBuilder {
+    fn default() -> Self {
+        Self {
+            port: 3000,
+            host: "127.0.0.1".to_string(),
+            workers: 0,
+            current_thread: false,
+            log_level: "info".to_string(),
+            log_format: "text".to_string(),
+            database: None,
+        }
+    }
+}
+
+impl AppBuilder {
+    pub fn port(mut self, port: u16) -> Self {
+        self.port = port;
+        self
+    }
+
+    pub fn host(mut self, host: impl Into<String>) -> Self {
+        self.host = host.into();
+        self
+    }
+
+    /// Number of Tokio worker threads; `0` lets Tokio choose
+    pub fn workers(mut self, workers: usize) -> Self {
+        self.workers = workers;
+        self
+    }
+
+    /// Run on a single-threaded runtime
+    pub fn current_thread(mut self) -> Self {
+        self.current_thread = true;
+        self
+    }
+
+    /// `error`, `warn`, `info`, `debug` or `trace`
+    pub fn log_level(mut self, level: impl Into<String>) -> Self {
+        self.log_level = level.into();
+        self
+    }
+
+    /// `text` or `json`
+    pub fn log_format(mut self, format: impl Into<String>) -> Self {
+        self.log_format = format.into().to_lowercase();
+        self
+    }
+
+    /// Connect to this database on startup and register it as `"default"`
+    /// (see `bubble_db::database`).
+    ///
+    /// `#[orm]` models only use it when declared with
+    /// `connection = "default"`; without that option they read
+    /// `crate::DATABASE_CONNECTION`.
+    pub fn database(mut self, config: bubble_db::DatabaseConfig) -> Self {
+        self.database = Some(config);
+        self
+    }
+
+    /// Validate the settings
+    pub fn build(self) -> FrameworkResult<App> {
+        if self.current_thread && self.workers > 0 {
+            return Err(FrameworkError::new(
+                "INVALID_CONFIG",
+                "workers cannot be set on a current-thread runtime",
+            ));
+        }
+        if !matches!(self.log_format.as_str(), "text" | "json") {
+            return Err(FrameworkError::new(
+                "INVALID_CONFIG",
+                format!("unknown log format '{}'", self.log_format),
+            ));
+        }
+        Ok(App { settings: self })
+    }
+}
+
+/// Application runtime set up with the same helpers as `#[bubble]` (see
+/// `crate::runtime`): logging, the Tokio runtime, the database connection and
+/// Ctrl+C shutdown. No listener is opened; `main` binds whatever it serves.
+#[derive(Debug, Clone)]
+pub struct App {
+    settings: AppBuilder,
+}
+
+impl App {
+    pub fn builder() -> AppBuilder {
+        AppBuilder::default()
+    }
+
+    /// Run `main` to completion, or until Ctrl+C is received, then close
+    /// the registered database connections.
+    ///
+    /// Unlike the generated `main`, failures are returned instead of exiting
+    /// the process.
+    pub fn run<F, T, E>(self, main: F) -> FrameworkResult<T>
+    where
+        F: Future<Output = Result<T, E>>,
+        E: Display,
+    {
+        let settings = self.settings;
+        crate::runtime::init_logging(&settings.log_level, &settings.log_format);
+        log::info!("Starting Bubble Application");
+        log::info!(
+            "Configuration: port={}, host={}, workers={}",
+            settings.port,
+            settings.host,
+            settings.workers
+        );
+        let rt = crate::runtime::runtime_builder(settings.current_thread, settings.workers)
+            .build()
+            .map_err(|err| {
+                FrameworkError::new(
+                    "RUNTIME_FAILED",
+                    format!("Failed to create Tokio runtime: {}", err),
+                )
+            })?;
+        rt.block_on(async {
+            if let Some(config) = &settings.database {
+                log::info!("Initializing {} database", config.database_type);
+                let conn = bubble_db::connect(config)
+                    .await
+                    .map_err(|err| FrameworkError::new("DATABASE_FAILED", err))?;
+                bubble_db::register_database("default", conn);
+            }
+            log::info!("Executing user application");
+            let result = tokio::select! {
+                signal = tokio::signal::ctrl_c() => {
+                    if let Err(err) = signal {
+                        log::error!("Failed to listen for shutdown signal: {}", err);
+                    }
+                    log::info!("Shutting down gracefully...");
+                    Err(FrameworkError::new("INTERRUPTED", "Application interrupted by user"))
+                }
+                result = main => {
+                    result.map_err(|err| FrameworkError::new("APPLICATION_FAILED", err.to_string()))
+                }
+            };
+            if settings.database.is_some() {
+                log::info!("Closing database connections");
+                if let Err(err) = bubble_db::close_databases().await {
+                    log::warn!("Failed to close database connections: {}", err);
+                }
+            }
+            result
+        })
+    }
+}
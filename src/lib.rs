@@ -1 +1,7 @@
+pub mod app;
+pub mod runtime;
 pub mod types;
+
+pub use app::{App, AppBuilder};
+pub use bubble_db as db;
+pub use bubble_web as web;
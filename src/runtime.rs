@@ -0,0 +1,210 @@
+// Startup steps shared by the #[bubble] attribute and App
+
+/// Install the global logger.
+///
+/// `level` is `error`, `warn`, `info`, `debug` or `trace` (anything else is
+/// `info`); `format` is `text` or `json`, the latter writing one object per
+/// line with `timestamp`, `level`, `target` and `message` fields. A logger
+/// that is already installed (e.g. by a test harness) is kept.
+pub fn init_logging(level: &str, format: &str) {
+    use std::io::Write;
+    let filter = match level.to_lowercase().as_str() {
+        "error" => log::LevelFilter::Error,
+        "warn" => log::LevelFilter::Warn,
+        "debug" => log::LevelFilter::Debug,
+        "trace" => log::LevelFilter::Trace,
+        _ => log::LevelFilter::Info,
+    };
+    let mut builder = env_logger::Builder::from_default_env();
+    builder.filter_level(filter);
+    if format.eq_ignore_ascii_case("json") {
+        builder.format(|buf, record| {
+            let line = serde_json::json!({
+                "timestamp": buf.timestamp_millis().to_string(),
+                "level": record.level().to_string(),
+                "target": record.target(),
+                "message": record.args().to_string(),
+            });
+            writeln!(buf, "{}", line)
+        });
+    } else {
+        builder
+            .format_timestamp(Some(env_logger::TimestampPrecision::Millis))
+            .format_module_path(false);
+    }
+    if builder.try_init().is_err() {
+        log::debug!("Logger already initialized, keeping the existing one");
+        return;
+    }
+    log::info!("Logging initialized with level: {}", level);
+}
+
+/// Tokio runtime builder with every driver enabled.
+///
+/// `workers` sets the worker threads of a multi-threaded runtime, capped at
+/// four per available CPU so a mistyped value can't exhaust the host; `0`
+/// lets Tokio choose.
+pub fn runtime_builder(current_thread: bool, workers: usize) -> tokio::runtime::Builder {
+    let mut builder = if current_thread {
+        tokio::runtime::Builder::new_current_thread()
+    } else {
+        tokio::runtime::Builder::new_multi_thread()
+    };
+    builder
+        .enable_all()
+        .on_thread_start(|| log::debug!("Tokio worker thread started"))
+        .on_thread_stop(|| log::debug!("Tokio worker thread stopped"));
+    if !current_thread && workers > 0 {
+        let cpus = std::thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(1);
+        let max_workers = cpus * 4;
+        if workers > max_workers {
+            log::warn!(
+                "workers = {} exceeds {} ({} available CPUs x 4), using {}",
+                workers,
+                max_workers,
+                cpus,
+                max_workers
+            );
+        }
+        builder.worker_threads(workers.min(max_workers));
+    }
+    builder
+}
+
+/// Process exit code for a listener that failed to bind: 2 when the address
+/// is already in use, 3 when permission is denied, 4 when the address is not
+/// available on this host and 1 otherwise
+pub fn bind_exit_code(err: &std::io::Error) -> i32 {
+    match err.kind() {
+        std::io::ErrorKind::AddrInUse => 2,
+        std::io::ErrorKind::PermissionDenied => 3,
+        std::io::ErrorKind::AddrNotAvailable => 4,
+        _ => 1,
+    }
+}
+
+/// Describe a failed TCP bind of `host:port` with a hint for the common causes
+pub fn bind_error_message(host: &str, port: u16, err: &std::io::Error) -> String {
+    match err.kind() {
+        std::io::ErrorKind::AddrInUse => format!(
+            "Address {}:{} is already in use; stop the other process or choose a different port",
+            host, port
+        ),
+        std::io::ErrorKind::PermissionDenied if port < 1024 => format!(
+            "Permission denied binding to port {}; ports below 1024 require elevated privileges, run as root or choose a port >= 1024",
+            port
+        ),
+        std::io::ErrorKind::AddrNotAvailable => format!(
+            "Address {} is not available on this machine; check the host setting",
+            host
+        ),
+        _ => format!("Failed to bind {}:{}: {}", host, port, err),
+    }
+}
+
+/// Log a failed TCP bind and exit with its `bind_exit_code`
+pub fn exit_on_bind_error(host: &str, port: u16, err: &std::io::Error) -> ! {
+    let message = bind_error_message(host, port, err);
+    log::error!("{}", message);
+    eprintln!("error: {}", message);
+    std::process::exit(bind_exit_code(err));
+}
+
+/// Read a configuration file as JSON, parsing it as `format` (`toml`, `yaml`
+/// or `json`).
+///
+/// A missing or unreadable file keeps the defaults and returns `None`; only a
+/// malformed one is an error.
+pub fn load_config_file(path: &str, format: &str) -> Result<Option<serde_json::Value>, String> {
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+            log::debug!("No configuration file at {}, using defaults", path);
+            return Ok(None);
+        }
+        Err(err) => {
+            log::warn!(
+                "Failed to read configuration file {}: {}, using defaults",
+                path,
+                err
+            );
+            return Ok(None);
+        }
+    };
+    let parsed = match format {
+        "yaml" => serde_yaml::from_str(&content).map_err(|err| err.to_string()),
+        "json" => serde_json::from_str(&content).map_err(|err| err.to_string()),
+        _ => toml::from_str(&content).map_err(|err| err.to_string()),
+    };
+    parsed
+        .map(Some)
+        .map_err(|err| format!("Invalid configuration file {}: {}", path, err))
+}
+
+/// Serve Prometheus metrics at `/metrics` on `listener` until the task is
+/// dropped.
+///
+/// Exports the uptime since the call and the worker and alive task gauges of
+/// the current Tokio runtime; other paths get 404. Failed accepts (e.g. out
+/// of file descriptors) are logged and retried after a backoff of up to one
+/// second.
+pub async fn serve_metrics(listener: tokio::net::TcpListener) {
+    const MAX_BACKOFF: std::time::Duration = std::time::Duration::from_secs(1);
+    let started = std::time::Instant::now();
+    let runtime_metrics = tokio::runtime::Handle::current().metrics();
+    let mut backoff = std::time::Duration::from_millis(10);
+    loop {
+        let mut stream = match listener.accept().await {
+            Ok((stream, _)) => {
+                backoff = std::time::Duration::from_millis(10);
+                stream
+            }
+            Err(err) => {
+                log::warn!(
+                    "Metrics listener failed to accept a connection: {}, retrying in {:?}",
+                    err,
+                    backoff
+                );
+                tokio::time::sleep(backoff).await;
+                backoff = (backoff * 2).min(MAX_BACKOFF);
+                continue;
+            }
+        };
+        let runtime_metrics = runtime_metrics.clone();
+        tokio::spawn(async move {
+            use tokio::io::{AsyncReadExt, AsyncWriteExt};
+            let mut buf = [0u8; 1024];
+            let Ok(read) = stream.read(&mut buf).await else {
+                return;
+            };
+            let request = String::from_utf8_lossy(&buf[..read]);
+            let response = if request.starts_with("GET /metrics ") {
+                let body = format!(
+                    "# HELP bubble_uptime_seconds Seconds since the application started\n\
+                     # TYPE bubble_uptime_seconds gauge\n\
+                     bubble_uptime_seconds {}\n\
+                     # HELP tokio_workers Number of runtime worker threads\n\
+                     # TYPE tokio_workers gauge\n\
+                     tokio_workers {}\n\
+                     # HELP tokio_alive_tasks Number of tasks alive in the runtime\n\
+                     # TYPE tokio_alive_tasks gauge\n\
+                     tokio_alive_tasks {}\n",
+                    started.elapsed().as_secs_f64(),
+                    runtime_metrics.num_workers(),
+                    runtime_metrics.num_alive_tasks(),
+                );
+                format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                )
+            } else {
+                "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                    .to_string()
+            };
+            let _ = stream.write_all(response.as_bytes()).await;
+        });
+    }
+}
@@ -1,4 +1,7 @@
 // Core framework system types (completely web-independent)
+// FrameworkError is returned unboxed throughout the public API
+#![allow(clippy::result_large_err)]
+
 use std::any::Any;
 use std::collections::HashMap;
 use std::error::Error as StdError;
@@ -233,3 +236,268 @@ pub struct ConfigSchema {
     /// Whether configuration can be updated at runtime
     pub runtime_updatable: bool,
 }
+
+impl FrameworkError {
+    /// Create a new error with the given code and message
+    pub fn new(code: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            code: code.into(),
+            message: message.into(),
+            severity: ErrorSeverity::Error,
+            stack_trace: None,
+            causes: Vec::new(),
+            context: HashMap::new(),
+        }
+    }
+
+    /// Attach a context entry to the error
+    pub fn with_context(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.context.insert(key.into(), value.into());
+        self
+    }
+}
+
+impl Display for FrameworkError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[{}] {}", self.code, self.message)
+    }
+}
+
+impl StdError for FrameworkError {}
+
+/// Resolve the load order of the given modules.
+///
+/// Modules are topologically sorted so that every module appears after the
+/// modules it depends on. Resolution fails when a required dependency is
+/// missing, when a dependency's version falls outside
+/// `[min_version, max_version]`, or when the dependency graph contains a cycle.
+pub fn resolve_dependencies(
+    modules: &[ModuleDescriptor],
+) -> FrameworkResult<Vec<&ModuleDescriptor>> {
+    let mut by_name: HashMap<&str, &ModuleDescriptor> = HashMap::new();
+    for module in modules {
+        if by_name.insert(module.name.as_str(), module).is_some() {
+            return Err(FrameworkError::new(
+                "MODULE_DUPLICATE",
+                format!("Module '{}' is declared more than once", module.name),
+            )
+            .with_context("module", module.name.clone()));
+        }
+    }
+    for module in modules {
+        for dependency in &module.dependencies {
+            match by_name.get(dependency.name.as_str()) {
+                Some(target) => check_dependency_version(module, dependency, target)?,
+                None if dependency.required => {
+                    return Err(FrameworkError::new(
+                        "MODULE_DEPENDENCY_MISSING",
+                        format!(
+                            "Module '{}' requires missing dependency '{}'",
+                            module.name, dependency.name
+                        ),
+                    )
+                    .with_context("module", module.name.clone())
+                    .with_context("dependency", dependency.name.clone()));
+                }
+                None => {}
+            }
+        }
+    }
+    let mut states: HashMap<&str, VisitState> = HashMap::new();
+    let mut stack: Vec<&str> = Vec::new();
+    let mut ordered = Vec::with_capacity(modules.len());
+    for module in modules {
+        visit_module(module, &by_name, &mut states, &mut stack, &mut ordered)?;
+    }
+    Ok(ordered)
+}
+
+/// Depth-first traversal state used for cycle detection
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum VisitState {
+    /// Module is on the current traversal path
+    Visiting,
+    /// Module and all its dependencies have been ordered
+    Done,
+}
+
+fn visit_module<'a>(
+    module: &'a ModuleDescriptor,
+    by_name: &HashMap<&'a str, &'a ModuleDescriptor>,
+    states: &mut HashMap<&'a str, VisitState>,
+    stack: &mut Vec<&'a str>,
+    ordered: &mut Vec<&'a ModuleDescriptor>,
+) -> FrameworkResult<()> {
+    match states.get(module.name.as_str()) {
+        Some(VisitState::Done) => return Ok(()),
+        Some(VisitState::Visiting) => {
+            let start = stack
+                .iter()
+                .position(|name| *name == module.name)
+                .unwrap_or(0);
+            let mut cycle: Vec<&str> = stack[start..].to_vec();
+            cycle.push(module.name.as_str());
+            return Err(FrameworkError::new(
+                "MODULE_DEPENDENCY_CYCLE",
+                format!("Cyclic module dependency: {}", cycle.join(" -> ")),
+            )
+            .with_context("cycle", cycle.join(" -> ")));
+        }
+        None => {}
+    }
+    states.insert(module.name.as_str(), VisitState::Visiting);
+    stack.push(module.name.as_str());
+    for dependency in &module.dependencies {
+        if let Some(target) = by_name.get(dependency.name.as_str()) {
+            visit_module(target, by_name, states, stack, ordered)?;
+        }
+    }
+    stack.pop();
+    states.insert(module.name.as_str(), VisitState::Done);
+    ordered.push(module);
+    Ok(())
+}
+
+fn check_dependency_version(
+    module: &ModuleDescriptor,
+    dependency: &Dependency,
+    target: &ModuleDescriptor,
+) -> FrameworkResult<()> {
+    let parse = |value: &str, field: &str| {
+        semver::Version::parse(value).map_err(|e| {
+            FrameworkError::new(
+                "MODULE_VERSION_INVALID",
+                format!("Invalid version '{}': {}", value, e),
+            )
+            .with_context("module", module.name.clone())
+            .with_context("dependency", dependency.name.clone())
+            .with_context("field", field.to_string())
+        })
+    };
+    let actual = parse(&target.version, "version")?;
+    let min = parse(&dependency.min_version, "min_version")?;
+    let max = match &dependency.max_version {
+        Some(max) => Some(parse(max, "max_version")?),
+        None => None,
+    };
+    let in_range = actual >= min && max.as_ref().is_none_or(|max| actual <= *max);
+    if in_range {
+        return Ok(());
+    }
+    let range = match &dependency.max_version {
+        Some(max) => format!("[{}, {}]", dependency.min_version, max),
+        None => format!("[{}, *)", dependency.min_version),
+    };
+    Err(FrameworkError::new(
+        "MODULE_DEPENDENCY_VERSION",
+        format!(
+            "Module '{}' requires '{}' in {}, found {}",
+            module.name, dependency.name, range, target.version
+        ),
+    )
+    .with_context("module", module.name.clone())
+    .with_context("dependency", dependency.name.clone())
+    .with_context("required", range)
+    .with_context("found", target.version.clone()))
+}
+
+impl ConfigValue {
+    /// Convert the value into its JSON representation
+    pub fn to_json(&self) -> serde_json::Value {
+        match self {
+            ConfigValue::String(s) => serde_json::Value::String(s.clone()),
+            ConfigValue::Int(i) => serde_json::Value::from(*i),
+            ConfigValue::Float(f) => serde_json::Number::from_f64(*f)
+                .map(serde_json::Value::Number)
+                .unwrap_or(serde_json::Value::Null),
+            ConfigValue::Bool(b) => serde_json::Value::Bool(*b),
+            ConfigValue::List(items) => {
+                serde_json::Value::Array(items.iter().map(ConfigValue::to_json).collect())
+            }
+            ConfigValue::Nested(config) => config.to_json(),
+        }
+    }
+}
+
+impl Config {
+    /// Convert the configuration values into a JSON object
+    pub fn to_json(&self) -> serde_json::Value {
+        let map = self
+            .values
+            .iter()
+            .map(|(key, value)| (key.clone(), value.to_json()))
+            .collect();
+        serde_json::Value::Object(map)
+    }
+}
+
+impl ConfigSchema {
+    /// Fill in schema defaults for keys absent from the configuration
+    pub fn apply_defaults(&self, config: &mut Config) {
+        for (key, value) in &self.defaults {
+            config
+                .values
+                .entry(key.clone())
+                .or_insert_with(|| value.clone());
+        }
+    }
+
+    /// Validate a configuration against the JSON schema.
+    ///
+    /// Defaults are applied to a copy of the configuration before validation,
+    /// so absent keys with a declared default never cause a violation. Every
+    /// violation is reported as a cause of the returned error, with the
+    /// offending path stored in its `context`.
+    pub fn validate(&self, config: &Config) -> FrameworkResult<()> {
+        let schema: serde_json::Value = serde_json::from_str(&self.schema).map_err(|e| {
+            FrameworkError::new(
+                "CONFIG_SCHEMA_INVALID",
+                format!("Invalid JSON schema: {}", e),
+            )
+            .with_context("config", config.id.clone())
+        })?;
+        let validator = jsonschema::validator_for(&schema).map_err(|e| {
+            FrameworkError::new(
+                "CONFIG_SCHEMA_INVALID",
+                format!("Invalid JSON schema: {}", e),
+            )
+            .with_context("config", config.id.clone())
+        })?;
+        let mut effective = config.clone();
+        self.apply_defaults(&mut effective);
+        let instance = effective.to_json();
+        let causes: Vec<FrameworkError> = validator
+            .iter_errors(&instance)
+            .map(|error| {
+                let path = error.instance_path.to_string();
+                FrameworkError::new("CONFIG_VALIDATION", error.to_string())
+                    .with_context("config", config.id.clone())
+                    .with_context(
+                        "path",
+                        if path.is_empty() {
+                            "/".to_string()
+                        } else {
+                            path
+                        },
+                    )
+            })
+            .collect();
+        if causes.is_empty() {
+            return Ok(());
+        }
+        let mut error = FrameworkError::new(
+            "CONFIG_VALIDATION",
+            format!(
+                "Configuration '{}' failed validation with {} error(s)",
+                config.id,
+                causes.len()
+            ),
+        )
+        .with_context("config", config.id.clone());
+        if let Some(path) = causes[0].context.get("path") {
+            error = error.with_context("path", path.clone());
+        }
+        error.causes = causes;
+        Err(error)
+    }
+}
@@ -1,5 +1,264 @@
 #[cfg(test)]
 mod db_test {
+    use bubble::web::{Error, Response};
+    use bubble_db::futures::StreamExt;
+    use bubble_db::{
+        ConnectionPool, DatabaseConfig, DatabaseConnection, DatabaseType, DbErrorKind,
+        MockConnection, PoolConfig, STREAM_CHUNK_SIZE, SqlParam, SqlValue, SqliteConfig, ToSql,
+    };
+    use std::sync::Arc;
+
+    fn sqlite_memory() -> DatabaseConfig {
+        DatabaseConfig {
+            database_type: DatabaseType::Sqlite,
+            host: String::new(),
+            port: 0,
+            username: String::new(),
+            password: String::new(),
+            database: ":memory:".to_string(),
+            pool: PoolConfig::default(),
+            replicas: Vec::new(),
+            sqlite: SqliteConfig::default(),
+        }
+    }
+
     #[test]
-    fn db_test() {}
+    fn none_binds_as_null() {
+        assert_eq!(Option::<i64>::None.to_sql(), SqlParam::Null);
+        assert_eq!(Option::<String>::None.to_sql(), SqlParam::Null);
+        assert_eq!(Some(7i64).to_sql(), SqlParam::Int(7));
+    }
+
+    #[test]
+    fn dates_and_uuids_bind_typed() {
+        let date = chrono::NaiveDate::from_ymd_opt(2024, 1, 23).unwrap();
+        let timestamp = date.and_hms_opt(10, 30, 45).unwrap();
+        let id = uuid::Uuid::parse_str("67e55044-10b1-426f-9247-bb680e5fe0c8").unwrap();
+
+        assert_eq!(date.to_sql(), SqlParam::Date(date));
+        assert_eq!(timestamp.to_sql(), SqlParam::Timestamp(timestamp));
+        assert_eq!(
+            timestamp.and_utc().to_sql(),
+            SqlParam::TimestampTz(timestamp.and_utc())
+        );
+        assert_eq!(id.to_sql(), SqlParam::Uuid(id));
+        assert_eq!(Some(id).to_sql(), SqlParam::Uuid(id));
+        assert_eq!(Option::<uuid::Uuid>::None.to_sql(), SqlParam::Null);
+    }
+
+    #[tokio::test]
+    async fn none_is_stored_as_null() {
+        let conn = bubble_db::connect(&sqlite_memory()).await.unwrap();
+        conn.execute("CREATE TABLE events (id INTEGER PRIMARY KEY, happened_at TEXT)")
+            .await
+            .unwrap();
+        let happened_at: Option<chrono::NaiveDateTime> = None;
+        conn.execute_with(
+            "INSERT INTO events (id, happened_at) VALUES (?, ?)",
+            &[&1i64, &happened_at],
+        )
+        .await
+        .unwrap();
+
+        let rows = conn
+            .query_rows("SELECT id FROM events WHERE happened_at IS NULL")
+            .await
+            .unwrap();
+        assert_eq!(rows.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn mock_records_bound_params() {
+        let conn = MockConnection::new(DatabaseType::Postgres);
+        let name: Option<String> = None;
+        conn.execute_with("UPDATE users SET name = $1 WHERE id = $2", &[&name, &3i64])
+            .await
+            .unwrap();
+        assert_eq!(
+            conn.last_sql().as_deref(),
+            Some("UPDATE users SET name = $1 WHERE id = $2")
+        );
+        assert_eq!(
+            conn.last_params(),
+            Some(vec![SqlParam::Null, SqlParam::Int(3)])
+        );
+
+        let err = conn
+            .execute_with("DELETE FROM users WHERE id = $1", &[])
+            .await
+            .unwrap_err();
+        assert!(err.contains("$1"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn query_rows_keeps_null() {
+        let conn = bubble_db::connect(&sqlite_memory()).await.unwrap();
+        conn.execute("CREATE TABLE notes (id INTEGER PRIMARY KEY, body TEXT)")
+            .await
+            .unwrap();
+        let body: Option<String> = None;
+        conn.execute_with(
+            "INSERT INTO notes (id, body) VALUES (?, ?)",
+            &[&1i64, &body],
+        )
+        .await
+        .unwrap();
+        conn.execute_with("INSERT INTO notes (id, body) VALUES (?, ?)", &[&2i64, &""])
+            .await
+            .unwrap();
+
+        let rows = conn
+            .query_rows("SELECT id, body FROM notes ORDER BY id")
+            .await
+            .unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].get("id"), Some(&SqlValue::Int(1)));
+        assert_eq!(rows[0].get("body"), Some(&SqlValue::Null));
+        assert_eq!(rows[1].get("body"), Some(&SqlValue::Text(String::new())));
+
+        // The JSON form built on query_rows keeps the distinction
+        let json: serde_json::Value = serde_json::from_str(
+            &conn
+                .query("SELECT body FROM notes ORDER BY id")
+                .await
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(json, serde_json::json!([{ "body": null }, { "body": "" }]));
+    }
+
+    #[tokio::test]
+    async fn query_stream_yields_every_row_across_chunks() {
+        let conn = bubble_db::connect(&sqlite_memory()).await.unwrap();
+        conn.execute("CREATE TABLE numbers (n INTEGER)")
+            .await
+            .unwrap();
+        let total = STREAM_CHUNK_SIZE as i64 * 2 + 500;
+        conn.execute_with(
+            "INSERT INTO numbers (n) WITH RECURSIVE seq(n) AS \
+             (SELECT 1 UNION ALL SELECT n + 1 FROM seq WHERE n < ?) SELECT n FROM seq",
+            &[&total],
+        )
+        .await
+        .unwrap();
+
+        let mut stream =
+            conn.query_stream("SELECT n FROM numbers WHERE n > ? ORDER BY n", &[&0i64]);
+        let mut expected = 1;
+        while let Some(row) = stream.next().await {
+            assert_eq!(row.unwrap().get("n"), Some(&SqlValue::Int(expected)));
+            expected += 1;
+        }
+        assert_eq!(expected - 1, total);
+    }
+
+    #[tokio::test]
+    async fn in_memory_sqlite_is_shared_by_pool_checkouts() {
+        let pool = ConnectionPool::new(sqlite_memory());
+        let conn = pool.get().await.unwrap();
+        conn.execute("CREATE TABLE items (name TEXT)")
+            .await
+            .unwrap();
+        conn.execute("INSERT INTO items (name) VALUES ('first')")
+            .await
+            .unwrap();
+
+        // A second checkout waits for the single connection to be returned
+        let waiter = tokio::spawn({
+            let pool = pool.clone();
+            async move {
+                let conn = pool.get().await.unwrap();
+                conn.query_rows("SELECT name FROM items").await.unwrap()
+            }
+        });
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        assert!(!waiter.is_finished());
+        assert_eq!(pool.status().waiting, 1);
+        drop(conn);
+
+        let rows = waiter.await.unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(
+            rows[0].get("name"),
+            Some(&SqlValue::Text("first".to_string()))
+        );
+    }
+
+    #[tokio::test]
+    async fn db_errors_map_to_http_statuses() {
+        let conn = bubble_db::connect(&sqlite_memory()).await.unwrap();
+        conn.execute("CREATE TABLE accounts (id INTEGER PRIMARY KEY)")
+            .await
+            .unwrap();
+        conn.execute("INSERT INTO accounts (id) VALUES (1)")
+            .await
+            .unwrap();
+
+        let missing = conn
+            .query_one("SELECT id FROM accounts WHERE id = 2")
+            .await
+            .unwrap_err();
+        assert_eq!(DbErrorKind::of(&missing).http_status(), 404);
+        let response = Response::error(Error::from_db(missing));
+        assert_eq!(response.status.code, 404);
+
+        let duplicate = conn
+            .execute("INSERT INTO accounts (id) VALUES (1)")
+            .await
+            .unwrap_err();
+        assert_eq!(DbErrorKind::of(&duplicate).http_status(), 409);
+        assert_eq!(Error::from_db(duplicate).status(), 409);
+
+        assert_eq!(Error::from_db("Pool error: timed out").status(), 503);
+        assert_eq!(Error::from_db("syntax error").status(), 500);
+    }
+
+    #[tokio::test]
+    async fn registering_a_name_again_closes_the_replaced_connection() {
+        let first = Arc::new(MockConnection::new(DatabaseType::Sqlite));
+        bubble_db::register_shared_database("replaced", first.clone());
+        bubble_db::register_database("replaced", MockConnection::new(DatabaseType::Postgres));
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+
+        assert!(first.is_closed());
+        let current = bubble_db::database("replaced").unwrap();
+        assert_eq!(current.database_type(), DatabaseType::Postgres);
+    }
+
+    #[tokio::test]
+    async fn collect_db_health_reports_every_pool() {
+        let up = ConnectionPool::new(sqlite_memory());
+        let mut unreachable = sqlite_memory();
+        unreachable.database_type = DatabaseType::Postgres;
+        unreachable.host = "127.0.0.1".to_string();
+        unreachable.port = 1;
+        unreachable.database = "app".to_string();
+        unreachable.pool.connection_timeout = std::time::Duration::from_millis(200);
+        let down = ConnectionPool::new(unreachable);
+
+        let health = bubble_db::collect_db_health(&[&up, &down]).await.unwrap();
+        assert!(!health.healthy);
+        assert_eq!(health.connections.len(), 2);
+        assert_eq!(health.pools.len(), 2);
+        assert!(health.connections[0].ping.healthy);
+        assert_eq!(health.connections[0].connection.database, ":memory:");
+        assert!(!health.connections[1].ping.healthy);
+        assert!(health.connections[1].ping.error.is_some());
+        assert_eq!(health.pools[0].idle, 1);
+
+        assert!(bubble_db::collect_db_health(&[]).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn pool_monitor_records_the_latest_status() {
+        let pool = ConnectionPool::new(sqlite_memory());
+        assert_eq!(pool.last_monitored_status(), None);
+        drop(pool.get().await.unwrap());
+
+        let monitor = pool.spawn_monitor(std::time::Duration::from_millis(10));
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        assert_eq!(pool.last_monitored_status(), Some(pool.status()));
+        assert_eq!(pool.last_monitored_status().unwrap().idle, 1);
+        monitor.abort();
+    }
 }
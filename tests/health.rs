@@ -0,0 +1,22 @@
+#[cfg(test)]
+mod health_test {
+    use bubble::web::{HealthCheck, Request};
+    use bubble_db::{DatabaseType, MockConnection};
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn readiness_pings_the_database() {
+        let check =
+            HealthCheck::new().database(Arc::new(MockConnection::new(DatabaseType::Sqlite)));
+        let routes = check.routes();
+        assert_eq!(routes[0].path, "/healthz");
+        assert_eq!(routes[1].path, "/readyz");
+        let ready = routes[1].dispatch.clone().unwrap();
+
+        // Nothing has been pinged yet
+        assert_eq!(ready(&Request::default()).unwrap().status.code, 503);
+
+        assert_eq!(check.readiness().await.status.code, 200);
+        assert_eq!(ready(&Request::default()).unwrap().status.code, 200);
+    }
+}
@@ -1,6 +1,13 @@
 #[cfg(test)]
 mod test {
-    use bubble_macro::{controller, delete, get, head, options, patch, post, put, route};
+    use bubble::web::{
+        AuthInfo, Error, ErrorHandlers, HttpMethod, Request, Response, ResponseBody, Route,
+        RouteKind, find_route,
+    };
+    use bubble_macro::{
+        controller, delete, error_handler, get, head, options, patch, post, put, requires, route,
+    };
+    use std::sync::Arc;
 
     #[controller("/api/users")]
     struct UserController {
@@ -83,4 +90,130 @@ mod test {
         println!("10. {}", controller.trace());
         println!("=== Test completed ===");
     }
+
+    #[requires(role = "admin")]
+    fn delete_account(req: &Request, id: i64) -> Result<String, Error> {
+        Ok(format!("Account {} deleted by {}", id, req.path))
+    }
+
+    #[delete("/users/:id")]
+    #[requires(permission = "users.delete")]
+    fn delete_user(_req: &Request, id: i64) -> Result<String, Error> {
+        Ok(format!("User {} deleted", id))
+    }
+
+    fn request_with_auth(roles: &[&str], permissions: &[&str]) -> Request {
+        let mut req = Request {
+            path: "/accounts/7".to_string(),
+            ..Request::default()
+        };
+        req.context.auth = Some(AuthInfo {
+            user_id: "1".to_string(),
+            roles: roles.iter().map(|role| role.to_string()).collect(),
+            permissions: permissions.iter().map(|p| p.to_string()).collect(),
+            token: String::new(),
+        });
+        req
+    }
+
+    fn request_with_roles(roles: &[&str]) -> Request {
+        request_with_auth(roles, &[])
+    }
+
+    #[test]
+    fn requires_rejects_missing_role() {
+        let err = delete_account(&request_with_roles(&["user"]), 7).unwrap_err();
+        assert_eq!(err.code, "FORBIDDEN");
+        assert!(delete_account(&Request::default(), 7).is_err());
+
+        let ok = delete_account(&request_with_roles(&["user", "admin"]), 7).unwrap();
+        assert_eq!(ok, "Account 7 deleted by /accounts/7");
+    }
+
+    #[test]
+    fn requires_checks_permissions_under_a_method_macro() {
+        let err = delete_user(&request_with_auth(&["admin"], &["users.read"]), 7).unwrap_err();
+        assert_eq!(err.code, "FORBIDDEN");
+
+        let ok = delete_user(&request_with_auth(&[], &["users.delete"]), 7).unwrap();
+        assert_eq!(ok, "User 7 deleted");
+    }
+
+    fn route(path: &str) -> Route {
+        Route {
+            method: HttpMethod::GET,
+            path: path.to_string(),
+            handler: path.to_string(),
+            middleware: Vec::new(),
+            kind: RouteKind::Http,
+            dispatch: None,
+            websocket: None,
+            max_body_size: None,
+        }
+    }
+
+    #[test]
+    fn wildcard_captures_rest_of_path() {
+        let params = route("/files/*path")
+            .match_path("/files/a/b/c.txt")
+            .unwrap();
+        assert_eq!(params.get("path").map(String::as_str), Some("a/b/c.txt"));
+        assert!(
+            route("/files/:name")
+                .match_path("/files/a/b/c.txt")
+                .is_none()
+        );
+
+        let routes = vec![
+            route("/files/*path"),
+            route("/files/:name"),
+            route("/files/readme"),
+        ];
+        let (matched, params) = find_route(&routes, &HttpMethod::GET, "/files/readme").unwrap();
+        assert_eq!(matched.path, "/files/readme");
+        assert!(params.is_empty());
+        let (matched, params) = find_route(&routes, &HttpMethod::GET, "/files/notes").unwrap();
+        assert_eq!(matched.path, "/files/:name");
+        assert_eq!(params.get("name").map(String::as_str), Some("notes"));
+    }
+
+    #[error_handler(code = "VALIDATION_FAILED")]
+    fn handle_validation(err: Error) -> Response {
+        let mut response = Response::ok(ResponseBody::Json(serde_json::json!({
+            "error": err.message,
+        })));
+        response.status.code = 422;
+        response
+    }
+
+    #[test]
+    fn error_handler_renders_registered_shape() {
+        let handlers = ErrorHandlers::new().register(HANDLE_VALIDATION);
+        let mut failing = route("/users");
+        failing.dispatch = Some(Arc::new(|_: &Request| {
+            Err(Error::new("VALIDATION_FAILED", "name is required"))
+        }));
+        let response = handlers.dispatch(&failing, &mut Request::default(), &[]);
+        assert_eq!(response.status.code, 422);
+        match response.body {
+            ResponseBody::Json(body) => {
+                assert_eq!(body, serde_json::json!({ "error": "name is required" }))
+            }
+            other => panic!("unexpected body {:?}", other),
+        }
+
+        // Unmatched errors get the default JSON body
+        failing.dispatch = Some(Arc::new(|_: &Request| {
+            Err(Error::new("NOT_FOUND", "no such user"))
+        }));
+        let response = handlers.dispatch(&failing, &mut Request::default(), &[]);
+        assert_eq!(response.status.code, 404);
+        match response.body {
+            ResponseBody::Json(body) => {
+                assert_eq!(body["code"], "NOT_FOUND");
+                assert_eq!(body["message"], "no such user");
+            }
+            other => panic!("unexpected body {:?}", other),
+        }
+    }
 }
@@ -1,5 +1,123 @@
+use bubble_db::{DatabaseType, MockConnection};
+use std::sync::LazyLock;
+
+pub type DbResult<T> = bubble_db::DbResult<T>;
+
+/// Connection the `#[orm]` models below run against
+static DATABASE_CONNECTION: LazyLock<MockConnection> =
+    LazyLock::new(|| MockConnection::new(DatabaseType::Sqlite));
+
 #[cfg(test)]
 mod db_test {
-    #[test]
-    fn db_orm_test() {}
+    use super::DATABASE_CONNECTION;
+    use bubble_db::{DatabaseConnection, DatabaseType, MockConnection, SqlParam};
+    use bubble_macro::orm;
+    use tokio::sync::Mutex;
+
+    /// The models share one mock connection, so tests take turns with it
+    static LOCK: Mutex<()> = Mutex::const_new(());
+
+    #[derive(Debug, Default, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+    #[serde(rename_all = "lowercase")]
+    enum Status {
+        #[default]
+        Active,
+        Disabled,
+    }
+
+    #[orm(table = "users", db_type = "sqlite")]
+    struct User {
+        id: i64,
+        name: String,
+        nickname: Option<String>,
+        #[column(enum)]
+        status: Status,
+    }
+
+    #[tokio::test]
+    async fn insert_binds_fields() {
+        let _guard = LOCK.lock().await;
+        DATABASE_CONNECTION.reset();
+
+        let user = User {
+            id: 0,
+            name: "alice".to_string(),
+            nickname: None,
+            status: Status::Disabled,
+        };
+        let inserted = user.insert().await.unwrap();
+
+        assert_eq!(
+            DATABASE_CONNECTION.executed(),
+            vec!["INSERT INTO users (id, name, nickname, status) VALUES (?, ?, ?, ?)"]
+        );
+        // A zero id is left to the database and `None` is written as NULL
+        assert_eq!(
+            DATABASE_CONNECTION.last_params(),
+            Some(vec![
+                SqlParam::Null,
+                SqlParam::Text("alice".to_string()),
+                SqlParam::Null,
+                SqlParam::Text("disabled".to_string()),
+            ])
+        );
+        assert_eq!(inserted.id, 1);
+    }
+
+    #[tokio::test]
+    async fn find_by_id_maps_row() {
+        let _guard = LOCK.lock().await;
+        DATABASE_CONNECTION.reset();
+        DATABASE_CONNECTION.push_rows(
+            "FROM users",
+            r#"[{"id": "5", "name": "bob", "nickname": null, "status": "active"}]"#,
+        );
+
+        let user = User::find_by_id(5).await.unwrap();
+        assert_eq!(user.name, "bob");
+        assert_eq!(user.nickname, None);
+        assert_eq!(user.status, Status::Active);
+        assert_eq!(
+            DATABASE_CONNECTION.last_params(),
+            Some(vec![SqlParam::Int(5)])
+        );
+    }
+
+    #[tokio::test]
+    async fn missing_id_is_none() {
+        let _guard = LOCK.lock().await;
+        DATABASE_CONNECTION.reset();
+
+        assert!(User::try_find_by_id(42).await.unwrap().is_none());
+        assert!(User::find_by_id(42).await.is_err());
+
+        // A failing query is an error, not a missing row
+        DATABASE_CONNECTION.push_error("FROM users", "connection refused");
+        match User::try_find_by_id(42).await {
+            Err(err) => assert!(err.contains("connection refused"), "{}", err),
+            Ok(user) => panic!("expected an error, got {:?}", user.map(|user| user.id)),
+        }
+    }
+
+    #[orm(table = "tags", db_type = "sqlite", connection = "tags")]
+    struct Tag {
+        id: i64,
+        label: String,
+    }
+
+    #[tokio::test]
+    async fn named_connection_is_looked_up_in_the_registry() {
+        use bubble_db::futures::StreamExt;
+
+        let conn = std::sync::Arc::new(MockConnection::new(DatabaseType::Sqlite));
+        conn.push_rows("FROM tags", r#"[{"id": "1", "label": "rust"}]"#);
+        conn.push_rows("FROM tags", r#"[{"id": "1", "label": "rust"}]"#);
+        bubble_db::register_shared_database("tags", conn.clone());
+
+        let tag = Tag::find_by_id(1).await.unwrap();
+        assert_eq!(tag.label, "rust");
+        let streamed: Vec<_> = Tag::stream_all().collect().await;
+        assert_eq!(streamed.len(), 1);
+        assert_eq!(conn.executed().len(), 2);
+    }
 }
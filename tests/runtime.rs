@@ -0,0 +1,46 @@
+#[cfg(test)]
+mod runtime_test {
+    use bubble::runtime::{bind_error_message, bind_exit_code, serve_metrics};
+    use std::net::TcpListener;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    #[test]
+    fn occupied_port_has_its_own_exit_code() {
+        let held = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = held.local_addr().unwrap().port();
+        let err = TcpListener::bind(("127.0.0.1", port)).unwrap_err();
+
+        assert_eq!(bind_exit_code(&err), 2);
+        assert!(bind_error_message("127.0.0.1", port, &err).contains("already in use"));
+
+        let other = std::io::Error::other("boom");
+        assert_eq!(bind_exit_code(&other), 1);
+    }
+
+    async fn get(addr: std::net::SocketAddr, path: &str) -> String {
+        let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+        stream
+            .write_all(format!("GET {} HTTP/1.1\r\n\r\n", path).as_bytes())
+            .await
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        response
+    }
+
+    #[tokio::test]
+    async fn metrics_endpoint_exports_runtime_gauges() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(serve_metrics(listener));
+
+        let metrics = get(addr, "/metrics").await;
+        assert!(metrics.starts_with("HTTP/1.1 200 OK"), "{}", metrics);
+        assert!(metrics.contains("bubble_uptime_seconds "), "{}", metrics);
+        assert!(metrics.contains("tokio_workers 1"), "{}", metrics);
+        assert!(metrics.contains("tokio_alive_tasks "), "{}", metrics);
+
+        assert!(get(addr, "/other").await.starts_with("HTTP/1.1 404"));
+        server.abort();
+    }
+}
@@ -0,0 +1,77 @@
+#[cfg(test)]
+mod session_test {
+    use bubble::web::{
+        Error, InMemorySessionStore, Middleware, Request, Response, Session, SessionMiddleware,
+        SessionStore,
+    };
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// In-memory store counting its saves
+    #[derive(Default)]
+    struct CountingStore {
+        inner: InMemorySessionStore,
+        saves: AtomicUsize,
+    }
+
+    impl SessionStore for CountingStore {
+        fn load(&self, session_id: &str) -> Result<Option<Session>, Error> {
+            self.inner.load(session_id)
+        }
+
+        fn save(&self, session: &Session) -> Result<(), Error> {
+            self.saves.fetch_add(1, Ordering::SeqCst);
+            self.inner.save(session)
+        }
+
+        fn destroy(&self, session_id: &str) -> Result<(), Error> {
+            self.inner.destroy(session_id)
+        }
+    }
+
+    /// Run the middleware around `handler` for a request carrying the cookie
+    fn handle(middleware: &SessionMiddleware, handler: impl FnOnce(&Request)) -> Response {
+        let mut request = Request::default();
+        request.headers.set("Cookie", "bubble_session=s1");
+        middleware.pre_process(&mut request).unwrap();
+        handler(&request);
+        let mut response = Response::default();
+        middleware
+            .post_process_with_request(&request, &mut response)
+            .unwrap();
+        response
+    }
+
+    #[test]
+    fn only_changed_sessions_are_saved() {
+        let store = Arc::new(CountingStore::default());
+        let expires_at = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+            + 3600;
+        store
+            .inner
+            .save(&Session {
+                id: "s1".to_string(),
+                user_id: "7".to_string(),
+                created_at: 0,
+                expires_at,
+                data: Default::default(),
+            })
+            .unwrap();
+        let middleware = SessionMiddleware::new(store.clone());
+
+        handle(&middleware, |_| {});
+        assert_eq!(store.saves.load(Ordering::SeqCst), 0);
+
+        handle(&middleware, |request| {
+            let mut session = request.context.session().unwrap();
+            session.data.insert("theme".to_string(), "dark".to_string());
+            request.context.set_session(Some(session));
+        });
+        assert_eq!(store.saves.load(Ordering::SeqCst), 1);
+        let saved = store.inner.load("s1").unwrap().unwrap();
+        assert_eq!(saved.data.get("theme").map(String::as_str), Some("dark"));
+    }
+}